@@ -0,0 +1,922 @@
+//! Conversion between a [`TreeConfig`] and [BehaviorTree.CPP](https://www.behaviortree.dev) v4 xml.
+//!
+//! Nodes from `betula_std` that have an equivalent in BehaviorTree.CPP are converted with the
+//! mapping table, like a `sequence` with memory becoming a `Sequence`. Other node types are passed
+//! through, their type is the name of the element and their config is stored as json in the
+//! `betula_config` attribute, with its version in `betula_config_version` if that isn't 0. Port connections become `{key}` remappings, the key is the port of
+//! the blackboard if the tree has a single blackboard, or `blackboard/port` otherwise.
+//!
+//! Literal attributes like `message="hello"` are imported as constant values on the
+//! `betula_literals` blackboard, with the type the input of the node reads. Inputs connected to
+//! that blackboard are exported as literals again.
+//!
+//! Each root becomes a `BehaviorTree`, the first one is the main tree. Blackboard values, parents
+//! and persistent ports have no equivalent and are not exported. On import, `SubTree` references
+//! are inlined, attributes starting with an underscore are ignored and port directions are
+//! obtained from the `TreeNodesModel` or from the node created by the [`TreeSupport`].
+use betula_core::blackboard::{
+    BlackboardInputInterface, BlackboardPort, NodePort, PortConnection, PortDirection, PortName,
+    Read,
+};
+use betula_core::{BetulaError, BlackboardId, Node, NodeError, NodeId};
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
+use quick_xml::{Reader, Writer};
+use serde_json::{json, Value};
+use std::any::TypeId;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::tree_support::{v2, SerializedConfig, SerializedValue, TreeConfig, TreeSupport};
+
+/// Attribute holding the json config of a node that can't be expressed otherwise.
+const CONFIG_ATTRIBUTE: &str = "betula_config";
+/// Attribute holding the version of the config if it is not 0.
+const CONFIG_VERSION_ATTRIBUTE: &str = "betula_config_version";
+
+/// Attribute marking a `BehaviorTree` that holds nodes which are not a root.
+const DETACHED_ATTRIBUTE: &str = "betula_detached";
+
+/// Name of the blackboard holding the values of literal attributes.
+const LITERALS_BLACKBOARD: &str = "betula_literals";
+
+/// Node types that are passed through, with their ports and most children of a node.
+type Models = BTreeMap<String, (BTreeSet<(String, PortDirection)>, usize)>;
+
+/// How a config field is expressed as attribute.
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    /// An unsigned integer, written as is.
+    Count,
+}
+
+/// A node type of `betula_std` and its BehaviorTree.CPP equivalent.
+#[derive(Debug)]
+struct Mapping {
+    node_type: &'static str,
+    element: &'static str,
+    /// The config fields that select this element, `None` if the node has no config.
+    config: Option<Value>,
+    /// Config fields that are attributes, with the name of the attribute.
+    attributes: &'static [(&'static str, &'static str, Field)],
+}
+
+fn mappings() -> Vec<Mapping> {
+    let mapping = |node_type, element, config, attributes| Mapping {
+        node_type,
+        element,
+        config,
+        attributes,
+    };
+    vec![
+        mapping(
+            "sequence",
+            "ReactiveSequence",
+            Some(json!({"memory": false, "retry": false})),
+            &[],
+        ),
+        mapping(
+            "sequence",
+            "Sequence",
+            Some(json!({"memory": true, "retry": false})),
+            &[],
+        ),
+        mapping(
+            "sequence",
+            "SequenceWithMemory",
+            Some(json!({"memory": true, "retry": true})),
+            &[],
+        ),
+        mapping(
+            "selector",
+            "ReactiveFallback",
+            Some(json!({"memory": false})),
+            &[],
+        ),
+        mapping("selector", "Fallback", Some(json!({"memory": true})), &[]),
+        mapping(
+            "parallel",
+            "Parallel",
+            Some(json!({"memory": false})),
+            &[("success_threshold", "success_count", Field::Count)],
+        ),
+        mapping(
+            "std_if_then_else",
+            "IfThenElse",
+            Some(json!({"memory": false})),
+            &[],
+        ),
+        mapping("std_negate", "Inverter", None, &[]),
+        mapping("std_force_success", "ForceSuccess", None, &[]),
+        mapping("success", "AlwaysSuccess", None, &[]),
+        mapping("failure", "AlwaysFailure", None, &[]),
+    ]
+}
+
+impl Mapping {
+    /// Whether the config selects this mapping.
+    fn matches(&self, node_type: &str, config: &Option<Value>) -> bool {
+        if self.node_type != node_type {
+            return false;
+        }
+        match (&self.config, config) {
+            (None, None) => true,
+            (Some(Value::Object(fixed)), Some(Value::Object(config))) => {
+                fixed.iter().all(|(k, v)| config.get(k) == Some(v))
+            }
+            _ => false,
+        }
+    }
+
+    fn attributes(&self, config: &Option<Value>) -> Vec<(String, String)> {
+        let mut attributes = vec![];
+        for (field, attribute, kind) in self.attributes {
+            let Some(value) = config.as_ref().and_then(|c| c.get(field)) else {
+                continue;
+            };
+            let value = match kind {
+                Field::Count => value.to_string(),
+            };
+            attributes.push((attribute.to_string(), value));
+        }
+        attributes
+    }
+
+    /// The config for this element with the provided attributes.
+    fn config(&self, attributes: &[(String, String)]) -> Result<Option<Value>, BetulaError> {
+        let Some(mut config) = self.config.clone() else {
+            return Ok(None);
+        };
+        for (field, attribute, kind) in self.attributes {
+            let Some((_, value)) = attributes.iter().find(|(k, _)| k == attribute) else {
+                continue;
+            };
+            let value = match kind {
+                Field::Count => json!(value.parse::<u64>()?),
+            };
+            config[*field] = value;
+        }
+        Ok(Some(config))
+    }
+}
+
+/// Whether the node type can be used as element name.
+fn is_element_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || "_-.".contains(c))
+}
+
+/// A parsed xml element.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn new(name: &str) -> Self {
+        Element {
+            name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn with_attribute(mut self, name: &str, value: &str) -> Self {
+        self.attributes.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    fn parse(xml: &str) -> Result<Element, BetulaError> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+        let start = |e: &BytesStart| -> Result<Element, BetulaError> {
+            let mut element = Element::new(std::str::from_utf8(e.name().as_ref())?);
+            for attribute in e.attributes() {
+                let attribute = attribute?;
+                let key = std::str::from_utf8(attribute.key.as_ref())?.to_owned();
+                element
+                    .attributes
+                    .push((key, attribute.unescape_value()?.into_owned()));
+            }
+            Ok(element)
+        };
+        let mut stack: Vec<Element> = vec![Element::default()];
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) => stack.push(start(&e)?),
+                Event::Empty(e) => {
+                    let element = start(&e)?;
+                    stack.last_mut().unwrap().children.push(element);
+                }
+                Event::End(_) => {
+                    let element = stack.pop().unwrap();
+                    stack
+                        .last_mut()
+                        .ok_or("unbalanced xml")?
+                        .children
+                        .push(element);
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        let mut document = stack
+            .pop()
+            .filter(|_| stack.is_empty())
+            .ok_or("unbalanced xml")?;
+        if document.children.len() != 1 {
+            return Err("expected a single root element".into());
+        }
+        Ok(document.children.remove(0))
+    }
+
+    fn write(&self, writer: &mut Writer<Vec<u8>>) -> Result<(), BetulaError> {
+        let mut start = BytesStart::new(self.name.as_str());
+        for (k, v) in self.attributes.iter() {
+            start.push_attribute((k.as_str(), v.as_str()));
+        }
+        if self.children.is_empty() {
+            writer.write_event(Event::Empty(start))?;
+        } else {
+            writer.write_event(Event::Start(start))?;
+            for child in self.children.iter() {
+                child.write(writer)?;
+            }
+            writer.write_event(Event::End(BytesEnd::new(self.name.as_str())))?;
+        }
+        Ok(())
+    }
+}
+
+/// Convert a tree config to BehaviorTree.CPP v4 xml.
+pub fn to_xml(config: &TreeConfig) -> Result<String, BetulaError> {
+    let root = config.clone().into_latest();
+    let mappings = mappings();
+
+    // The keys of the blackboards, only prefixed if there is more than one.
+    let mut names: HashMap<&str, usize> = HashMap::new();
+    for name in root.blackboards.iter().filter_map(|b| b.name.as_deref()) {
+        *names.entry(name).or_default() += 1;
+    }
+    let is_literals = |bb: &v2::Blackboard| bb.name.as_deref() == Some(LITERALS_BLACKBOARD);
+    let keyed = root
+        .blackboards
+        .iter()
+        .filter(|bb| !is_literals(bb))
+        .count();
+    let mut prefixes: HashMap<BlackboardId, String> = HashMap::new();
+    for (i, bb) in root.blackboards.iter().enumerate() {
+        let prefix = match bb.name.as_deref() {
+            _ if keyed == 1 && !is_literals(bb) => String::new(),
+            Some(name) if names[name] == 1 && !name.contains(['/', '{', '}', '@']) => {
+                format!("{name}/")
+            }
+            _ => format!("blackboard_{i}/"),
+        };
+        prefixes.insert(bb.id, prefix);
+    }
+    // Inputs connected to the literals blackboard are written as the value.
+    let mut literals: HashMap<(BlackboardId, PortName), String> = HashMap::new();
+    for bb in root.blackboards.iter().filter(|bb| is_literals(bb)) {
+        for (port, value) in bb.values.iter() {
+            let literal = match &value.data {
+                Value::String(literal) => literal.clone(),
+                data => data.to_string(),
+            };
+            literals.insert((bb.id, port.clone()), literal);
+        }
+    }
+    let mut connections: HashMap<NodeId, Vec<&PortConnection>> = HashMap::new();
+    // The attribute of each connection, holding the literal or the key of the port.
+    let mut port_attributes: HashMap<NodeId, Vec<(String, String)>> = HashMap::new();
+    for connection in root.blackboards.iter().flat_map(|b| b.connections.iter()) {
+        connections
+            .entry(connection.node.node())
+            .or_default()
+            .push(connection);
+        let literal = literals.get(&(connection.blackboard_id(), connection.blackboard.name()));
+        let value = match literal {
+            Some(literal) if connection.node.direction() == PortDirection::Input => literal.clone(),
+            _ => {
+                let prefix = prefixes
+                    .get(&connection.blackboard_id())
+                    .ok_or(format!("could not find blackboard of {connection:?}"))?;
+                format!("{{{prefix}{}}}", connection.blackboard.name().0)
+            }
+        };
+        port_attributes
+            .entry(connection.node.node())
+            .or_default()
+            .push((connection.node.name().0, value));
+    }
+
+    let nodes: HashMap<NodeId, &v2::TreeNode> = root.nodes.iter().map(|n| (n.id, n)).collect();
+    let mut models = Models::new();
+    let mut written = HashSet::new();
+
+    fn convert(
+        id: NodeId,
+        nodes: &HashMap<NodeId, &v2::TreeNode>,
+        mappings: &[Mapping],
+        port_attributes: &HashMap<NodeId, Vec<(String, String)>>,
+        connections: &HashMap<NodeId, Vec<&PortConnection>>,
+        models: &mut Models,
+        written: &mut HashSet<NodeId>,
+    ) -> Result<Element, BetulaError> {
+        if !written.insert(id) {
+            return Err(format!("node {id:?} occurs more than once").into());
+        }
+        let node = nodes
+            .get(&id)
+            .ok_or(format!("could not find node {id:?}"))?;
+        // The mapping table describes the first version of the configs.
+        let mapping = mappings
+            .iter()
+            .find(|m| node.config_version == 0 && m.matches(&node.node_type, &node.config));
+        let mut element = match mapping {
+            Some(mapping) => {
+                let mut element = Element::new(mapping.element);
+                element.attributes = mapping.attributes(&node.config);
+                if mapping.config(&element.attributes)? != node.config {
+                    let config = serde_json::to_string(&node.config)?;
+                    element
+                        .attributes
+                        .push((CONFIG_ATTRIBUTE.to_owned(), config));
+                }
+                element
+            }
+            None => {
+                let mut element = if is_element_name(&node.node_type) {
+                    Element::new(&node.node_type)
+                } else {
+                    Element::new("Action").with_attribute("ID", &node.node_type)
+                };
+                if let Some(config) = &node.config {
+                    let config = serde_json::to_string(config)?;
+                    element
+                        .attributes
+                        .push((CONFIG_ATTRIBUTE.to_owned(), config));
+                }
+                if node.config_version != 0 {
+                    let version = node.config_version.to_string();
+                    element
+                        .attributes
+                        .push((CONFIG_VERSION_ATTRIBUTE.to_owned(), version));
+                }
+                let model = models.entry(node.node_type.clone()).or_default();
+                model.1 = model.1.max(node.children.len());
+                for connection in connections.get(&id).into_iter().flatten() {
+                    let port = connection.node.name().0;
+                    model.0.insert((port, connection.node.direction()));
+                }
+                element
+            }
+        };
+        if let Some(name) = &node.name {
+            element.attributes.push(("name".to_owned(), name.clone()));
+        }
+        let attributes = port_attributes.get(&id).into_iter().flatten();
+        element.attributes.extend(attributes.cloned());
+        for child in node.children.iter() {
+            element.children.push(convert(
+                *child,
+                nodes,
+                mappings,
+                port_attributes,
+                connections,
+                models,
+                written,
+            )?);
+        }
+        Ok(element)
+    }
+
+    let children: HashSet<NodeId> = root.nodes.iter().flat_map(|n| n.children.clone()).collect();
+    let detached = root
+        .nodes
+        .iter()
+        .filter(|n| !root.tree_roots.contains(&n.id) && !children.contains(&n.id))
+        .map(|n| n.id);
+    let trees = root
+        .tree_roots
+        .iter()
+        .map(|id| (*id, false))
+        .chain(detached.map(|id| (id, true)));
+    let mut document = Element::new("root").with_attribute("BTCPP_format", "4");
+    for (i, (id, is_detached)) in trees.enumerate() {
+        let tree_id = format!("Tree{i}");
+        if i == 0 {
+            document = document.with_attribute("main_tree_to_execute", &tree_id);
+        }
+        let mut tree = Element::new("BehaviorTree").with_attribute("ID", &tree_id);
+        if is_detached {
+            tree = tree.with_attribute(DETACHED_ATTRIBUTE, "true");
+        }
+        tree.children.push(convert(
+            id,
+            &nodes,
+            &mappings,
+            &port_attributes,
+            &connections,
+            &mut models,
+            &mut written,
+        )?);
+        document.children.push(tree);
+    }
+    if written.len() != root.nodes.len() {
+        return Err("nodes that are not reachable from a root or a detached node".into());
+    }
+
+    let mut tree_nodes_model = Element::new("TreeNodesModel");
+    for (node_type, (ports, children)) in models {
+        let kind = match children {
+            0 => "Action",
+            1 => "Decorator",
+            _ => "Control",
+        };
+        let mut model = Element::new(kind).with_attribute("ID", &node_type);
+        for (port, direction) in ports {
+            let kind = match direction {
+                PortDirection::Input => "input_port",
+                PortDirection::Output => "output_port",
+            };
+            model
+                .children
+                .push(Element::new(kind).with_attribute("name", &port));
+        }
+        tree_nodes_model.children.push(model);
+    }
+    if !tree_nodes_model.children.is_empty() {
+        document.children.push(tree_nodes_model);
+    }
+
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    document.write(&mut writer)?;
+    let mut xml = String::from_utf8(writer.into_inner())?;
+    xml.push('\n');
+    Ok(xml)
+}
+
+/// State while converting the xml to a tree config.
+struct Importer<'a> {
+    tree_support: &'a TreeSupport,
+    mappings: Vec<Mapping>,
+    trees: HashMap<&'a str, &'a Element>,
+    models: HashMap<String, HashMap<String, PortDirection>>,
+    blackboards: BTreeMap<String, BlackboardId>,
+    values: HashMap<BlackboardId, BTreeMap<PortName, SerializedValue>>,
+    nodes: Vec<v2::TreeNode>,
+    connections: Vec<PortConnection>,
+}
+
+/// Records the type that each input of a node reads.
+#[derive(Default)]
+struct InputTypes(HashMap<PortName, TypeId>);
+
+impl BlackboardInputInterface for InputTypes {
+    fn reader(&mut self, id: &TypeId, key: &PortName) -> Result<Read, NodeError> {
+        self.0.insert(key.clone(), *id);
+        Ok(Box::new(|| Err("input is not connected".into())))
+    }
+}
+
+impl<'a> Importer<'a> {
+    /// Create a node with its config, to inspect its ports.
+    fn create_node(
+        &self,
+        node_type: &str,
+        config: &Option<Value>,
+        config_version: u32,
+    ) -> Result<Box<dyn Node>, BetulaError> {
+        let node_type = node_type.into();
+        let mut node = self.tree_support.create_node(&node_type)?;
+        if let Some(data) = config.clone() {
+            let (_, data) = self
+                .tree_support
+                .upgrade_config(&node_type, config_version, data)?;
+            let config = self
+                .tree_support
+                .config_deserialize(SerializedConfig { node_type, data })?;
+            node.set_config(&*config)?;
+        }
+        Ok(node)
+    }
+
+    /// The direction of a port, from the model or from the node itself.
+    fn direction(
+        &self,
+        model_type: &str,
+        node_type: &str,
+        config: &Option<Value>,
+        config_version: u32,
+        port: &str,
+    ) -> Result<PortDirection, BetulaError> {
+        if let Some(direction) = self.models.get(model_type).and_then(|m| m.get(port)) {
+            return Ok(*direction);
+        }
+        let node = self.create_node(node_type, config, config_version)?;
+        let ports = node.ports()?;
+        let port = ports
+            .iter()
+            .find(|p| p.name().0 == port)
+            .ok_or(format!("could not determine direction of port {port:?}"))?;
+        Ok(port.direction())
+    }
+
+    /// The value of a literal attribute, with the type that the input of the node reads.
+    fn literal(
+        &self,
+        node_type: &str,
+        config: &Option<Value>,
+        config_version: u32,
+        port: &str,
+        literal: &str,
+    ) -> Result<SerializedValue, BetulaError> {
+        let mut node = self.create_node(node_type, config, config_version)?;
+        let mut types = InputTypes::default();
+        node.setup_inputs(&mut types)?;
+        let type_id = types
+            .0
+            .get(&port.into())
+            .ok_or(format!("could not determine the type of input {port:?}"))?;
+        let type_name = self
+            .tree_support
+            .value_type_name(*type_id)
+            .ok_or(format!("no value support for the type of input {port:?}"))?;
+        // Numbers and booleans are json, other literals are strings.
+        let parsed = serde_json::from_str(literal).ok();
+        for data in parsed
+            .into_iter()
+            .chain([Value::String(literal.to_owned())])
+        {
+            let value = SerializedValue {
+                type_id: type_name.to_owned(),
+                data,
+            };
+            if self.tree_support.value_deserialize(value.clone()).is_ok() {
+                return Ok(value);
+            }
+        }
+        Err(format!("could not convert {literal:?} to {type_name} for input {port:?}").into())
+    }
+
+    fn blackboard(&mut self, key: &str) -> (BlackboardId, PortName) {
+        let (prefix, port) = key.rsplit_once('/').unwrap_or(("", key));
+        let id = *self
+            .blackboards
+            .entry(prefix.to_owned())
+            .or_insert_with(|| BlackboardId(Uuid::new_v4()));
+        (id, port.into())
+    }
+
+    /// The port on the literals blackboard holding this value, shared by equal literals.
+    fn literal_port(
+        &mut self,
+        attribute: &str,
+        value: SerializedValue,
+    ) -> (BlackboardId, PortName) {
+        let (blackboard, _) = self.blackboard(&format!("{LITERALS_BLACKBOARD}/{attribute}"));
+        let values = self.values.entry(blackboard).or_default();
+        let mut port: PortName = attribute.into();
+        let mut i = 0;
+        while values.get(&port).is_some_and(|v| *v != value) {
+            i += 1;
+            port = format!("{attribute}_{i}").as_str().into();
+        }
+        values.insert(port.clone(), value);
+        (blackboard, port)
+    }
+
+    fn node(
+        &mut self,
+        element: &'a Element,
+        parents: &mut Vec<&'a str>,
+    ) -> Result<NodeId, BetulaError> {
+        if element.name == "SubTree" {
+            let tree_id = element.attribute("ID").ok_or("SubTree without ID")?;
+            if parents.contains(&tree_id) {
+                return Err(format!("SubTree {tree_id:?} includes itself").into());
+            }
+            let tree = self.tree(tree_id)?;
+            parents.push(tree_id);
+            let id = self.node(tree, parents);
+            parents.pop();
+            return id;
+        }
+        let is_generic =
+            ["Action", "Condition", "Decorator", "Control"].contains(&element.name.as_str());
+        let model_type = if is_generic {
+            element
+                .attribute("ID")
+                .ok_or(format!("{} without ID", element.name))?
+        } else {
+            &element.name
+        };
+        let mapping = self.mappings.iter().find(|m| m.element == model_type);
+        let mapped: Vec<&str> = mapping
+            .map(|m| m.attributes.iter().map(|(_, a, _)| *a).collect())
+            .unwrap_or_default();
+        let (node_type, mut config) = match mapping {
+            Some(mapping) => (
+                mapping.node_type.to_owned(),
+                mapping.config(&element.attributes)?,
+            ),
+            None => (model_type.to_owned(), None),
+        };
+        if let Some(stored) = element.attribute(CONFIG_ATTRIBUTE) {
+            config = serde_json::from_str(stored)?;
+        }
+        let config_version = match element.attribute(CONFIG_VERSION_ATTRIBUTE) {
+            Some(version) => version
+                .parse()
+                .map_err(|_| format!("invalid config version {version:?}"))?,
+            None => 0,
+        };
+
+        let id = NodeId(Uuid::new_v4());
+        let mut name = None;
+        for (attribute, value) in element.attributes.iter() {
+            let known = (is_generic && attribute == "ID")
+                || attribute == CONFIG_ATTRIBUTE
+                || attribute == CONFIG_VERSION_ATTRIBUTE
+                || attribute.starts_with('_')
+                || mapped.contains(&attribute.as_str());
+            if known {
+                continue;
+            }
+            if attribute == "name" {
+                name = Some(value.clone());
+                continue;
+            }
+            let direction =
+                self.direction(model_type, &node_type, &config, config_version, attribute)?;
+            let remapping = value.strip_prefix('{').and_then(|v| v.strip_suffix('}'));
+            let (blackboard, bb_port) = match remapping {
+                Some(key) => {
+                    let key = match key.trim_start_matches('@') {
+                        "=" => attribute.as_str(),
+                        key => key,
+                    };
+                    self.blackboard(key)
+                }
+                None if direction == PortDirection::Input => {
+                    let value =
+                        self.literal(&node_type, &config, config_version, attribute, value)?;
+                    self.literal_port(attribute, value)
+                }
+                None => {
+                    return Err(format!(
+                        "output {attribute:?} of {model_type:?} is not a {{key}} remapping"
+                    )
+                    .into())
+                }
+            };
+            self.connections.push(PortConnection::new(
+                NodePort::new(id, &attribute.as_str().into(), direction),
+                BlackboardPort::new(blackboard, &bb_port),
+            ));
+        }
+
+        let mut children = vec![];
+        for child in element.children.iter() {
+            children.push(self.node(child, parents)?);
+        }
+        self.nodes.push(v2::TreeNode {
+            id,
+            node_type,
+            config,
+            config_version,
+            children,
+            name,
+        });
+        Ok(id)
+    }
+
+    /// The single child of a behavior tree.
+    fn tree(&self, tree_id: &str) -> Result<&'a Element, BetulaError> {
+        let tree = self
+            .trees
+            .get(tree_id)
+            .ok_or(format!("could not find BehaviorTree {tree_id:?}"))?;
+        match tree.children.as_slice() {
+            [node] => Ok(node),
+            _ => Err(format!("BehaviorTree {tree_id:?} must have one child").into()),
+        }
+    }
+}
+
+/// Collect the ids of the subtrees referenced by an element and its children.
+fn subtrees<'a>(element: &'a Element, ids: &mut HashSet<&'a str>) {
+    if element.name == "SubTree" {
+        ids.extend(element.attribute("ID"));
+    }
+    for child in element.children.iter() {
+        subtrees(child, ids);
+    }
+}
+
+/// Convert BehaviorTree.CPP v4 xml to a tree config, with new ids for nodes and blackboards.
+///
+/// The tree support is used to determine the direction of ports that are not in the
+/// `TreeNodesModel` of the xml.
+pub fn from_xml(tree_support: &TreeSupport, xml: &str) -> Result<TreeConfig, BetulaError> {
+    let document = Element::parse(xml)?;
+    if document.name != "root" {
+        return Err(format!("expected root element, got {:?}", document.name).into());
+    }
+    let mut importer = Importer {
+        tree_support,
+        mappings: mappings(),
+        trees: HashMap::new(),
+        models: HashMap::new(),
+        blackboards: BTreeMap::new(),
+        values: HashMap::new(),
+        nodes: vec![],
+        connections: vec![],
+    };
+    let mut order = vec![];
+    for element in document.children.iter() {
+        match element.name.as_str() {
+            "BehaviorTree" => {
+                let tree_id = element.attribute("ID").ok_or("BehaviorTree without ID")?;
+                importer.trees.insert(tree_id, element);
+                order.push(tree_id);
+            }
+            "TreeNodesModel" => {
+                for model in element.children.iter() {
+                    let model_type = model.attribute("ID").ok_or("model without ID")?;
+                    let ports = importer.models.entry(model_type.to_owned()).or_default();
+                    for port in model.children.iter() {
+                        let direction = match port.name.as_str() {
+                            "output_port" => PortDirection::Output,
+                            _ => PortDirection::Input,
+                        };
+                        let name = port.attribute("name").ok_or("port without name")?;
+                        ports.insert(name.to_owned(), direction);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // The main tree is the first root, trees used as subtree are not roots themselves.
+    if let Some(main) = document.attribute("main_tree_to_execute") {
+        order.retain(|t| *t != main);
+        order.insert(0, main);
+    }
+    let mut referenced = HashSet::new();
+    for tree in importer.trees.values() {
+        subtrees(tree, &mut referenced);
+    }
+    let mut tree_roots = vec![];
+    for tree_id in order {
+        if referenced.contains(tree_id) {
+            continue;
+        }
+        let is_detached = importer.trees[tree_id].attribute(DETACHED_ATTRIBUTE) == Some("true");
+        let node = importer.tree(tree_id)?;
+        let id = importer.node(node, &mut vec![tree_id])?;
+        if !is_detached {
+            tree_roots.push(id);
+        }
+    }
+
+    let mut blackboards = vec![];
+    for (prefix, id) in importer.blackboards.iter() {
+        let connections = importer
+            .connections
+            .iter()
+            .filter(|c| c.blackboard_id() == *id)
+            .cloned()
+            .collect();
+        blackboards.push(v2::Blackboard {
+            id: *id,
+            values: importer.values.remove(id).unwrap_or_default(),
+            connections,
+            name: Some(prefix.clone()).filter(|p| !p.is_empty()),
+            parent: None,
+            persistent: vec![],
+        });
+    }
+    Ok(TreeConfig::V2(v2::Root {
+        nodes: importer.nodes,
+        blackboards,
+        tree_roots,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tree_support::test_tree_support;
+    use betula_core::basic::BasicTree;
+
+    #[test]
+    fn test_xml_roundtrip() -> Result<(), BetulaError> {
+        let tree_support = test_tree_support();
+        let text = r#"blackboard globals
+  value time f64 = 0.0
+root sequence main = {"memory":true,"retry":false}
+  node std_time
+    output time -> globals
+  node selector = {"memory":false}
+    node std_delay = {"interval":1.5}
+      input time <- globals
+      node failure
+    node success
+node sequence = {"memory":false,"retry":true}
+"#;
+        let mut tree = BasicTree::new();
+        tree_support.import_tree_text(&mut tree, text)?;
+        let xml = to_xml(&tree_support.export_tree_config(&tree)?)?;
+        assert!(xml.contains(r#"<Sequence name="main">"#));
+        assert!(xml.contains(r#"<std_time time="{time}"/>"#));
+        assert!(
+            xml.contains(r#"<std_delay betula_config="{&quot;interval&quot;:1.5}" time="{time}">"#)
+        );
+        assert!(xml.contains("<ReactiveFallback>"));
+        assert!(xml.contains("<AlwaysSuccess/>"));
+        assert!(xml.contains(r#"<output_port name="time"/>"#));
+
+        let mut new_tree = BasicTree::new();
+        let config = from_xml(&tree_support, &xml)?;
+        tree_support.import_tree_config(&mut new_tree, &config)?;
+        // The blackboard name is lost with a single blackboard.
+        let expected = text.replace("globals", "@0");
+        assert_eq!(tree_support.export_tree_text(&new_tree)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_xml_import() -> Result<(), BetulaError> {
+        let tree_support = test_tree_support();
+        let xml = r#"<?xml version="1.0"?>
+<root BTCPP_format="4" main_tree_to_execute="Main">
+  <BehaviorTree ID="Main">
+    <Fallback name="root">
+      <SubTree ID="Wait"/>
+      <Action ID="SaySomething" message="{msg}" _description="ignored"/>
+    </Fallback>
+  </BehaviorTree>
+  <BehaviorTree ID="Wait">
+    <Inverter>
+      <std_delay time="2.5">
+        <AlwaysSuccess/>
+      </std_delay>
+    </Inverter>
+  </BehaviorTree>
+  <TreeNodesModel>
+    <Action ID="SaySomething">
+      <input_port name="message"/>
+    </Action>
+  </TreeNodesModel>
+</root>
+"#;
+        let root = from_xml(&tree_support, xml)?.into_latest();
+        assert_eq!(root.tree_roots.len(), 1);
+        assert_eq!(root.nodes.len(), 5);
+        let main = root
+            .nodes
+            .iter()
+            .find(|n| n.id == root.tree_roots[0])
+            .unwrap();
+        assert_eq!(main.node_type, "selector");
+        assert_eq!(main.config, Some(json!({"memory": true})));
+        assert_eq!(main.name.as_deref(), Some("root"));
+        assert!(root.nodes.iter().any(|n| n.node_type == "std_negate"));
+        assert_eq!(root.blackboards.len(), 2);
+        let keys = &root.blackboards[0];
+        assert_eq!(keys.name, None);
+        let connection = &keys.connections[0];
+        assert_eq!(connection.node.direction(), PortDirection::Input);
+        assert_eq!(connection.blackboard.name(), "msg".into());
+
+        // The literal is a constant with the type of the input.
+        let literals = &root.blackboards[1];
+        assert_eq!(literals.name.as_deref(), Some(LITERALS_BLACKBOARD));
+        let value = &literals.values[&"time".into()];
+        assert_eq!(value.type_id, "f64");
+        assert_eq!(value.data, json!(2.5));
+        assert_eq!(literals.connections.len(), 1);
+        let xml = to_xml(&TreeConfig::V2(root))?;
+        assert!(xml.contains(r#"<std_delay time="2.5">"#));
+        assert!(xml.contains(r#"message="{msg}""#));
+
+        // Literals need the type that the input reads.
+        let xml = r#"<root><BehaviorTree ID="A"><Talk text="hi"/></BehaviorTree></root>"#;
+        assert!(from_xml(&tree_support, xml).is_err());
+        Ok(())
+    }
+}
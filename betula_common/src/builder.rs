@@ -0,0 +1,287 @@
+//! Builder to construct trees in code.
+//!
+//! Instead of creating ids and connecting nodes, children and ports one call at a time, the
+//! [`TreeBuilder`] describes the structure of a tree with nested closures:
+//! ```
+//! # use betula_common::builder::TreeBuilder;
+//! # use betula_core::Tree;
+//! # use betula_std::nodes::{SequenceNode, SuccessNode, FailureNode, TimeNode};
+//! let mut builder = TreeBuilder::new();
+//! let globals = builder.blackboard("globals");
+//! builder.root(SequenceNode::default(), |s| {
+//!     s.node(TimeNode::default()).connect("time", globals);
+//!     s.node(SuccessNode {}).name("done");
+//! });
+//! let tree = builder.build().unwrap();
+//! assert_eq!(tree.nodes().len(), 3);
+//! ```
+use betula_core::basic::{BasicBlackboard, BasicTree};
+use betula_core::blackboard::{BlackboardPort, PortConnection, PortName};
+use betula_core::{BetulaError, Blackboard, BlackboardId, Node, NodeId, Tree};
+use uuid::Uuid;
+
+use crate::tree_support::{TreeConfig, TreeSupport};
+
+#[derive(Debug)]
+struct BuilderNode {
+    id: NodeId,
+    node: Box<dyn Node>,
+    name: Option<String>,
+    children: Vec<usize>,
+    /// Node port, blackboard and blackboard port.
+    connections: Vec<(PortName, BlackboardId, PortName)>,
+}
+
+#[derive(Debug)]
+struct BuilderBlackboard {
+    id: BlackboardId,
+    name: String,
+    blackboard: Box<dyn Blackboard>,
+}
+
+/// Builder that describes a tree, see the [module documentation](self).
+#[derive(Debug, Default)]
+pub struct TreeBuilder {
+    nodes: Vec<BuilderNode>,
+    blackboards: Vec<BuilderBlackboard>,
+    roots: Vec<usize>,
+}
+
+/// Builder for the children of a node, created by [`TreeBuilder::root`] and
+/// [`ChildrenBuilder::node_with`].
+#[derive(Debug)]
+pub struct ChildrenBuilder<'a> {
+    builder: &'a mut TreeBuilder,
+    children: Vec<usize>,
+}
+
+impl TreeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn add_node(&mut self, node: Box<dyn Node>) -> usize {
+        self.nodes.push(BuilderNode {
+            id: NodeId(Uuid::new_v4()),
+            node,
+            name: None,
+            children: vec![],
+            connections: vec![],
+        });
+        self.nodes.len() - 1
+    }
+
+    fn add_node_with<F: FnOnce(&mut ChildrenBuilder)>(
+        &mut self,
+        node: Box<dyn Node>,
+        children: F,
+    ) -> usize {
+        let index = self.add_node(node);
+        let mut child_builder = ChildrenBuilder {
+            builder: self,
+            children: vec![],
+        };
+        children(&mut child_builder);
+        let children = child_builder.children;
+        self.nodes[index].children = children;
+        index
+    }
+
+    /// Add a [`BasicBlackboard`] with the provided name.
+    pub fn blackboard(&mut self, name: &str) -> BlackboardId {
+        self.blackboard_boxed(name, Box::new(BasicBlackboard::default()))
+    }
+
+    /// Add a blackboard with the provided name.
+    pub fn blackboard_boxed(
+        &mut self,
+        name: &str,
+        blackboard: Box<dyn Blackboard>,
+    ) -> BlackboardId {
+        let id = BlackboardId(Uuid::new_v4());
+        self.blackboards.push(BuilderBlackboard {
+            id,
+            name: name.to_owned(),
+            blackboard,
+        });
+        id
+    }
+
+    /// Add a root node, its children are added by the closure.
+    pub fn root<N: Node + 'static, F: FnOnce(&mut ChildrenBuilder)>(
+        &mut self,
+        node: N,
+        children: F,
+    ) -> NodeBuilder<'_> {
+        let index = self.add_node_with(Box::new(node), children);
+        self.roots.push(index);
+        NodeBuilder {
+            builder: self,
+            index,
+        }
+    }
+
+    /// Add the nodes and blackboards to the tree, set the roots and connect the ports.
+    pub fn build_into(self, tree: &mut dyn Tree) -> Result<(), BetulaError> {
+        let ids: Vec<NodeId> = self.nodes.iter().map(|n| n.id).collect();
+        for bb in self.blackboards {
+            tree.add_blackboard_boxed(bb.id, bb.blackboard)?;
+            tree.set_blackboard_name(bb.id, Some(&bb.name))?;
+        }
+        let mut connections = vec![];
+        for n in self.nodes {
+            tree.add_node_boxed(n.id, n.node)?;
+            tree.set_node_name(n.id, n.name.as_deref())?;
+            let children: Vec<NodeId> = n.children.iter().map(|i| ids[*i]).collect();
+            tree.set_children(n.id, &children)?;
+            connections.extend(n.connections.into_iter().map(|c| (n.id, c)));
+        }
+        let roots: Vec<NodeId> = self.roots.iter().map(|i| ids[*i]).collect();
+        tree.set_roots(&roots)?;
+
+        // Ports are only known once the node is in the tree.
+        for (id, (port, blackboard, blackboard_port)) in connections {
+            let node_port = tree
+                .node_ports(id)?
+                .into_iter()
+                .find(|p| p.name() == port)
+                .ok_or(format!("node {id:?} has no port {port:?}"))?;
+            tree.connect_port(&PortConnection::new(
+                node_port,
+                BlackboardPort::new(blackboard, &blackboard_port),
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Build a [`BasicTree`].
+    pub fn build(self) -> Result<BasicTree, BetulaError> {
+        let mut tree = BasicTree::new();
+        self.build_into(&mut tree)?;
+        Ok(tree)
+    }
+
+    /// Build a [`TreeConfig`], the tree support must support all nodes and values.
+    pub fn build_config(self, tree_support: &TreeSupport) -> Result<TreeConfig, BetulaError> {
+        let tree = self.build()?;
+        tree_support.export_tree_config(&tree)
+    }
+}
+
+impl ChildrenBuilder<'_> {
+    /// Add a child without children.
+    pub fn node<N: Node + 'static>(&mut self, node: N) -> NodeBuilder<'_> {
+        self.node_with(node, |_| {})
+    }
+
+    /// Add a child, its children are added by the closure.
+    pub fn node_with<N: Node + 'static, F: FnOnce(&mut ChildrenBuilder)>(
+        &mut self,
+        node: N,
+        children: F,
+    ) -> NodeBuilder<'_> {
+        let index = self.builder.add_node_with(Box::new(node), children);
+        self.children.push(index);
+        NodeBuilder {
+            builder: self.builder,
+            index,
+        }
+    }
+}
+
+/// Handle to a node that was just added to a [`TreeBuilder`].
+#[derive(Debug)]
+pub struct NodeBuilder<'a> {
+    builder: &'a mut TreeBuilder,
+    index: usize,
+}
+
+impl NodeBuilder<'_> {
+    fn entry(&mut self) -> &mut BuilderNode {
+        &mut self.builder.nodes[self.index]
+    }
+
+    /// The id the node will have in the tree.
+    pub fn id(&self) -> NodeId {
+        self.builder.nodes[self.index].id
+    }
+
+    /// Use the provided id instead of a random one.
+    pub fn with_id(mut self, id: NodeId) -> Self {
+        self.entry().id = id;
+        self
+    }
+
+    /// Set the name of the node.
+    pub fn name(mut self, name: &str) -> Self {
+        self.entry().name = Some(name.to_owned());
+        self
+    }
+
+    /// Connect the port of the node to the blackboard port of the same name.
+    pub fn connect(self, port: &str, blackboard: BlackboardId) -> Self {
+        self.connect_as(port, blackboard, port)
+    }
+
+    /// Connect the port of the node to the blackboard port with the provided name.
+    pub fn connect_as(
+        mut self,
+        port: &str,
+        blackboard: BlackboardId,
+        blackboard_port: &str,
+    ) -> Self {
+        self.entry()
+            .connections
+            .push((port.into(), blackboard, blackboard_port.into()));
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use betula_std::nodes::{DelayNode, SelectorNode, SequenceNode, SuccessNode, TimeNode};
+
+    #[test]
+    fn test_builder() -> Result<(), BetulaError> {
+        let mut builder = TreeBuilder::new();
+        let globals = builder.blackboard("globals");
+        let root_id = NodeId(Uuid::new_v4());
+        builder
+            .root(SequenceNode::default(), |s| {
+                s.node(TimeNode::default()).connect("time", globals);
+                s.node_with(SelectorNode::default(), |d| {
+                    d.node(TimeNode::default())
+                        .connect_as("time", globals, "now");
+                    d.node(SuccessNode {}).name("done");
+                });
+                s.node(DelayNode::default()).connect("time", globals);
+            })
+            .with_id(root_id);
+        let tree = builder.build()?;
+
+        assert_eq!(tree.roots(), vec![root_id]);
+        assert_eq!(tree.nodes().len(), 6);
+        let children = tree.children(root_id)?;
+        assert_eq!(children.len(), 3);
+        let grandchildren = tree.children(children[1])?;
+        assert_eq!(grandchildren.len(), 2);
+        assert_eq!(tree.node_name(grandchildren[1])?.as_deref(), Some("done"));
+
+        assert_eq!(tree.blackboards(), vec![globals]);
+        assert_eq!(tree.blackboard_name(globals)?.as_deref(), Some("globals"));
+        let connections = tree.blackboard_connections(globals);
+        assert_eq!(connections.len(), 3);
+        let bb = tree.blackboard_ref(globals).ok_or("missing blackboard")?;
+        let mut ports = bb.borrow().ports();
+        ports.sort();
+        assert_eq!(ports, vec!["now".into(), "time".into()]);
+
+        // Connecting a port that doesn't exist fails on build.
+        let mut builder = TreeBuilder::new();
+        let bb = builder.blackboard("bb");
+        builder.root(SuccessNode {}, |_| {}).connect("missing", bb);
+        assert!(builder.build().is_err());
+        Ok(())
+    }
+}
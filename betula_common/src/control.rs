@@ -1,10 +1,11 @@
 use betula_core::{
     blackboard::{BlackboardId, PortConnection, PortName},
+    error::ErrorReport,
     BetulaError, ExecutionStatus, NodeId, NodeType,
 };
 
 pub use crate::tree_support::SerializedBlackboardValues;
-use crate::{tree_support::SerializedConfig, tree_support::TreeConfig};
+use crate::tree_support::{SerializedConfig, SerializedValue, TreeConfig};
 
 use serde::{Deserialize, Serialize};
 // we want asynchronous control & interaction with the tree.
@@ -106,6 +107,56 @@ pub struct RunSettings {
     pub interval: Option<std::time::Duration>,
 }
 
+/// Condition of a [`Breakpoint`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum BreakCondition {
+    /// Pause after the node returned this status.
+    Status(ExecutionStatus),
+    /// Pause after the node returned an error.
+    Error,
+    /// Pause before the node executes if the port on the blackboard holds this value.
+    Value {
+        blackboard: BlackboardId,
+        port: PortName,
+        value: SerializedValue,
+    },
+}
+
+/// Pauses execution at a node, before it executes unless the condition is about its result.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    pub node: NodeId,
+    pub condition: Option<BreakCondition>,
+}
+
+/// Commands for the debugger of the server thread.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum DebugCommand {
+    /// Replace all breakpoints.
+    SetBreakpoints(Vec<Breakpoint>),
+    /// Pause before the next node executes.
+    Pause,
+    /// Resume until the next breakpoint.
+    Continue,
+    /// Resume and pause before the next node executes, the first child of the paused node if
+    /// it executes any.
+    StepInto,
+    /// Resume and pause before the next node executes that isn't a descendant of the paused
+    /// node, which may be in the next tick.
+    StepOver,
+}
+
+/// Where execution paused, see [`InteractionEvent::DebugPaused`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DebugPosition {
+    pub tick: u64,
+    pub node: NodeId,
+    /// The ancestors of the node that are executing, outermost first.
+    pub stack: Vec<NodeId>,
+    /// The result of the node if paused after it returned, `None` if paused before it executes.
+    pub returned: Option<Result<ExecutionStatus, ErrorReport>>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum InteractionCommand {
     /// Add a new node.
@@ -131,6 +182,20 @@ pub enum InteractionCommand {
     /// Name a blackboard.
     SetBlackboardName(BlackboardId, Option<String>),
 
+    /// Set the parent of a blackboard.
+    SetBlackboardParent(BlackboardId, Option<BlackboardId>),
+
+    /// Set the ports of a blackboard that are persisted in snapshots.
+    SetBlackboardPersistentPorts(BlackboardId, Vec<PortName>),
+
+    /// Write a snapshot of the persistent ports of blackboards to the tree's directory, all
+    /// blackboards if empty.
+    SnapshotBlackboards(Vec<BlackboardId>),
+
+    /// Restore persistent ports of blackboards from the snapshot in the tree's directory, all
+    /// blackboards if empty.
+    RestoreBlackboards(Vec<BlackboardId>),
+
     /// Set a node's configuration.
     SetConfig(SetConfigCommand),
 
@@ -164,12 +229,25 @@ pub enum InteractionCommand {
     /// Set the directory used by the tree.
     SetDirectory(Option<String>),
 
+    /// Start recording a trace to the file at the path, or stop recording if `None`, see
+    /// [`crate::trace`]. Only supported by the server thread.
+    RecordTrace(Option<String>),
+
+    /// Profile the execution time of nodes, sending an [`ExecutionProfile`] every this many
+    /// ticks, or stop profiling if `None`. Only supported by the server thread.
+    Profile(Option<u64>),
+
+    /// Set breakpoints, pause and step through execution. While paused other commands are held
+    /// until the tick finished. Only supported by the server thread.
+    Debug(DebugCommand),
+
     /// Call the function on the tree, this _obviously_ only works for the
     /// inter process situation, but it is helpful for unit tests.
     #[serde(skip)]
     TreeCall(Box<dyn TreeCall>),
 }
 
+use crate::snapshot::BlackboardSnapshot;
 use crate::tree_support::TreeSupport;
 use betula_core::Tree;
 
@@ -216,6 +294,18 @@ impl InteractionCommand {
         InteractionCommand::SetDirectory(path)
     }
 
+    pub fn record_trace(path: Option<&std::path::Path>) -> Self {
+        InteractionCommand::RecordTrace(path.map(|p| p.to_string_lossy().into_owned()))
+    }
+
+    pub fn profile(window: Option<u64>) -> Self {
+        InteractionCommand::Profile(window)
+    }
+
+    pub fn debug(command: DebugCommand) -> Self {
+        InteractionCommand::Debug(command)
+    }
+
     pub fn connect_port(port_connection: PortConnection) -> Self {
         Self::port_disconnect_connect(&[], &[port_connection])
     }
@@ -256,6 +346,22 @@ impl InteractionCommand {
         InteractionCommand::SetBlackboardName(id, name)
     }
 
+    pub fn set_blackboard_parent(id: BlackboardId, parent: Option<BlackboardId>) -> Self {
+        InteractionCommand::SetBlackboardParent(id, parent)
+    }
+
+    pub fn set_blackboard_persistent_ports(id: BlackboardId, ports: &[PortName]) -> Self {
+        InteractionCommand::SetBlackboardPersistentPorts(id, ports.to_vec())
+    }
+
+    pub fn snapshot_blackboards(ids: &[BlackboardId]) -> Self {
+        InteractionCommand::SnapshotBlackboards(ids.to_vec())
+    }
+
+    pub fn restore_blackboards(ids: &[BlackboardId]) -> Self {
+        InteractionCommand::RestoreBlackboards(ids.to_vec())
+    }
+
     pub fn run_specific(nodes: &[NodeId]) -> Self {
         InteractionCommand::RunSettings(RunSettings {
             roots: None,
@@ -308,11 +414,15 @@ impl InteractionCommand {
         let port_values = tree_support.blackboard_value_serialize(&**bb)?;
         let connections = tree.blackboard_connections(blackboard_id);
         let name = tree.blackboard_name(blackboard_id)?;
+        let parent = tree.blackboard_parent(blackboard_id)?;
+        let persistent = tree.blackboard_persistent_ports(blackboard_id)?;
         Ok(BlackboardInformation {
             id: blackboard_id,
             port_values,
             connections,
             name,
+            parent,
+            persistent,
         })
     }
 
@@ -557,6 +667,60 @@ impl InteractionCommand {
                     )?),
                 ])
             }
+            InteractionCommand::SetBlackboardParent(blackboard_id, parent) => {
+                tree.set_blackboard_parent(*blackboard_id, *parent)?;
+                Ok(vec![
+                    InteractionEvent::CommandResult(CommandResult {
+                        command: self.clone(),
+                        error: None,
+                    }),
+                    InteractionEvent::BlackboardInformation(Self::blackboard_information(
+                        tree_support,
+                        *blackboard_id,
+                        tree,
+                    )?),
+                ])
+            }
+            InteractionCommand::SetBlackboardPersistentPorts(blackboard_id, ports) => {
+                tree.set_blackboard_persistent_ports(*blackboard_id, ports)?;
+                Ok(vec![
+                    InteractionEvent::CommandResult(CommandResult {
+                        command: self.clone(),
+                        error: None,
+                    }),
+                    InteractionEvent::BlackboardInformation(Self::blackboard_information(
+                        tree_support,
+                        *blackboard_id,
+                        tree,
+                    )?),
+                ])
+            }
+            InteractionCommand::SnapshotBlackboards(blackboards) => {
+                let snapshot = BlackboardSnapshot::from_tree(tree_support, tree, blackboards)?;
+                let path = BlackboardSnapshot::path(tree)?;
+                // Don't litter directories of trees without persistent ports.
+                if !snapshot.blackboards.is_empty() || path.exists() {
+                    snapshot.save(&path)?;
+                }
+                Ok(vec![InteractionEvent::CommandResult(CommandResult {
+                    command: self.clone(),
+                    error: None,
+                })])
+            }
+            InteractionCommand::RestoreBlackboards(blackboards) => {
+                let snapshot = BlackboardSnapshot::load(&BlackboardSnapshot::path(tree)?)?;
+                let restored = snapshot.restore(tree_support, tree, blackboards)?;
+                let mut reply = vec![InteractionEvent::CommandResult(CommandResult {
+                    command: self.clone(),
+                    error: None,
+                })];
+                for blackboard_id in restored {
+                    reply.push(InteractionEvent::BlackboardInformation(
+                        Self::blackboard_information(tree_support, blackboard_id, tree)?,
+                    ));
+                }
+                Ok(reply)
+            }
             InteractionCommand::SetDirectory(dir) => {
                 let dir = dir.as_ref().map(|s| std::path::PathBuf::from(&s));
                 let dir = dir.as_deref();
@@ -566,6 +730,11 @@ impl InteractionCommand {
                     error: None,
                 })])
             }
+            InteractionCommand::RecordTrace(_) => {
+                Err("recording a trace requires the server thread".into())
+            }
+            InteractionCommand::Profile(_) => Err("profiling requires the server thread".into()),
+            InteractionCommand::Debug(_) => Err("debugging requires the server thread".into()),
             InteractionCommand::TreeCall(f) => {
                 (*f).call(tree)?;
                 Ok(vec![])
@@ -597,13 +766,64 @@ impl BlackboardValues {
 #[derive(Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize)]
 pub struct NodeStatus {
     pub node: NodeId,
-    pub status: Result<ExecutionStatus, String>,
+    /// The status, errors describe the node they originated from.
+    pub status: Result<ExecutionStatus, ErrorReport>,
 }
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ExecutionResult {
     pub node_status: Vec<NodeStatus>,
 }
 
+/// Wall-clock time spent in the execute of a node, excluding its children.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct NodeProfile {
+    /// The number of times the node executed.
+    pub executions: u64,
+    pub min: std::time::Duration,
+    pub max: std::time::Duration,
+    pub total: std::time::Duration,
+}
+
+impl NodeProfile {
+    pub fn mean(&self) -> std::time::Duration {
+        if self.executions == 0 {
+            return std::time::Duration::ZERO;
+        }
+        self.total.div_f64(self.executions as f64)
+    }
+}
+
+/// The execution times of nodes over a window of ticks, see [`InteractionCommand::Profile`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ExecutionProfile {
+    /// The number of ticks in the window.
+    pub ticks: u64,
+    pub nodes: std::collections::BTreeMap<NodeId, NodeProfile>,
+}
+
+impl ExecutionProfile {
+    /// Add a single execution of a node.
+    pub fn add(&mut self, node: NodeId, duration: std::time::Duration) {
+        let profile = self.nodes.entry(node).or_insert(NodeProfile {
+            executions: 0,
+            min: duration,
+            max: duration,
+            total: std::time::Duration::ZERO,
+        });
+        profile.executions += 1;
+        profile.min = profile.min.min(duration);
+        profile.max = profile.max.max(duration);
+        profile.total += duration;
+    }
+
+    /// The nodes ordered by the total time spent in them, most first.
+    pub fn slowest(&self) -> Vec<(NodeId, NodeProfile)> {
+        let mut nodes: Vec<_> = self.nodes.iter().map(|(k, v)| (*k, *v)).collect();
+        nodes.sort_by_key(|(_, profile)| std::cmp::Reverse(profile.total));
+        nodes
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct NodeInformation {
     pub id: NodeId,
@@ -620,6 +840,10 @@ pub struct BlackboardInformation {
     pub connections: Vec<PortConnection>,
     pub port_values: SerializedBlackboardValues,
     pub name: Option<String>,
+    #[serde(default)]
+    pub parent: Option<BlackboardId>,
+    #[serde(default)]
+    pub persistent: Vec<PortName>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -654,6 +878,12 @@ pub enum InteractionEvent {
     /// Execution results for nodes.
     ExecutionResult(ExecutionResult),
 
+    /// Execution times of nodes, see [`InteractionCommand::Profile`].
+    ExecutionProfile(ExecutionProfile),
+
+    /// Execution paused at the position, or resumed if `None`, see [`InteractionCommand::Debug`].
+    DebugPaused(Option<DebugPosition>),
+
     /// Information about a node, its children and config.
     NodeInformation(NodeInformation),
 
@@ -730,3 +960,23 @@ pub fn internal_server_client() -> (InProcessControlServer, InProcessControlClie
         },
     )
 }
+
+/// Wait for the first event that `f` returns a value for, skipping the others.
+#[cfg(test)]
+pub(crate) fn wait_for<T>(
+    client: &impl TreeClient,
+    f: impl Fn(InteractionEvent) -> Option<T>,
+) -> Result<T, BetulaError> {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while std::time::Instant::now() < deadline {
+        match client.get_event()? {
+            Some(event) => {
+                if let Some(v) = f(event) {
+                    return Ok(v);
+                }
+            }
+            None => std::thread::sleep(std::time::Duration::from_millis(1)),
+        }
+    }
+    Err("timed out waiting for an event".into())
+}
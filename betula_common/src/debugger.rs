@@ -0,0 +1,353 @@
+//! Breakpoints and stepping for the server thread, see [`DebugCommand`].
+//!
+//! Pausing happens inside the execution of the tree, from the [`DebugObserver`] as nodes start
+//! and return. While paused the observer services the debug commands itself, other commands are
+//! deferred until the tick finished as the tree can't be modified while it executes.
+use crate::control::{
+    BlackboardValues, BreakCondition, Breakpoint, CommandResult, DebugCommand, DebugPosition,
+    InteractionCommand, InteractionEvent, TreeServer,
+};
+use crate::server_thread::ExecutionStack;
+use crate::TreeSupport;
+use betula_core::{
+    error::ErrorReport, ExecutionObserver, ExecutionStatus, NodeError, NodeId, Tree,
+};
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+/// Interval at which commands are polled while paused.
+const PAUSED_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// Where execution pauses, besides the breakpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Only pause at breakpoints.
+    Run,
+    /// Pause before the next node executes.
+    Next,
+    /// Pause before the next node executes that is at most this deep.
+    Over(usize),
+}
+
+#[derive(Debug)]
+pub(crate) struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    mode: Mode,
+    /// Commands received while paused.
+    deferred: VecDeque<InteractionCommand>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Debugger {
+            breakpoints: vec![],
+            mode: Mode::Run,
+            deferred: Default::default(),
+        }
+    }
+
+    /// Whether execution may pause, nodes don't need to be observed otherwise.
+    pub fn is_active(&self) -> bool {
+        self.mode != Mode::Run || !self.breakpoints.is_empty()
+    }
+
+    /// The next command that was received while paused.
+    pub fn next_deferred(&mut self) -> Option<InteractionCommand> {
+        self.deferred.pop_front()
+    }
+
+    /// Apply a command, with the depth of the paused node if paused. Returns whether execution
+    /// resumes.
+    pub fn apply(&mut self, command: &DebugCommand, paused_depth: Option<usize>) -> bool {
+        match command {
+            DebugCommand::SetBreakpoints(breakpoints) => {
+                self.breakpoints = breakpoints.clone();
+                false
+            }
+            DebugCommand::Pause => {
+                self.mode = Mode::Next;
+                false
+            }
+            DebugCommand::Continue => {
+                self.mode = Mode::Run;
+                true
+            }
+            DebugCommand::StepInto => {
+                self.mode = Mode::Next;
+                true
+            }
+            DebugCommand::StepOver => {
+                self.mode = Mode::Over(paused_depth.unwrap_or(0));
+                true
+            }
+        }
+    }
+}
+
+/// Observer that pauses execution, see the [module](self) documentation.
+pub(crate) struct DebugObserver<'a> {
+    debugger: RefCell<&'a mut Debugger>,
+    server: &'a dyn TreeServer,
+    tree_support: &'a TreeSupport,
+    tree: &'a dyn Tree,
+    tick: u64,
+    /// The nodes that are executing.
+    stack: ExecutionStack<()>,
+}
+
+impl<'a> DebugObserver<'a> {
+    pub fn new(
+        debugger: &'a mut Debugger,
+        server: &'a dyn TreeServer,
+        tree_support: &'a TreeSupport,
+        tree: &'a dyn Tree,
+        tick: u64,
+    ) -> Self {
+        DebugObserver {
+            debugger: RefCell::new(debugger),
+            server,
+            tree_support,
+            tree,
+            tick,
+            stack: Default::default(),
+        }
+    }
+
+    /// Whether the blackboard port currently holds the value.
+    fn holds_value(&self, condition: &BreakCondition) -> bool {
+        let BreakCondition::Value {
+            blackboard,
+            port,
+            value,
+        } = condition
+        else {
+            return false;
+        };
+        let current = self
+            .tree
+            .blackboard_ref(*blackboard)
+            .and_then(|bb| bb.try_borrow().ok()?.get(port));
+        current
+            .and_then(|v| self.tree_support.value_serialize(&*v).ok())
+            .is_some_and(|v| v == *value)
+    }
+
+    /// Report the position and service debug commands until execution resumes.
+    fn pause(&self, position: DebugPosition, depth: usize) {
+        // Failing sends mean the client is gone, which is handled when getting commands.
+        let _ = self
+            .server
+            .send_event(InteractionEvent::DebugPaused(Some(position)));
+        if let Ok(values) = BlackboardValues::from_tree(self.tree_support, self.tree) {
+            let _ = self
+                .server
+                .send_event(InteractionEvent::BlackboardValues(values));
+        }
+        loop {
+            std::thread::sleep(PAUSED_POLL_INTERVAL);
+            let command = match self.server.get_command() {
+                Ok(Some(command)) => command,
+                Ok(None) => continue,
+                Err(_) => {
+                    // Without a client nobody can resume, finish the tick such that the server
+                    // thread notices the disconnect and ends.
+                    let mut debugger = self.debugger.borrow_mut();
+                    debugger.breakpoints.clear();
+                    debugger.mode = Mode::Run;
+                    return;
+                }
+            };
+            let InteractionCommand::Debug(debug_command) = &command else {
+                self.debugger.borrow_mut().deferred.push_back(command);
+                continue;
+            };
+            let resumes = self.debugger.borrow_mut().apply(debug_command, Some(depth));
+            let _ = self
+                .server
+                .send_event(InteractionEvent::CommandResult(CommandResult {
+                    command,
+                    error: None,
+                }));
+            if resumes {
+                let _ = self.server.send_event(InteractionEvent::DebugPaused(None));
+                return;
+            }
+        }
+    }
+}
+
+impl ExecutionObserver for DebugObserver<'_> {
+    fn started(&self, node: NodeId) {
+        let depth = self.stack.depth();
+        let pause = {
+            let debugger = self.debugger.borrow();
+            let stepped = match debugger.mode {
+                Mode::Run => false,
+                Mode::Next => true,
+                Mode::Over(max_depth) => depth <= max_depth,
+            };
+            stepped
+                || debugger.breakpoints.iter().any(|b| {
+                    b.node == node
+                        && match &b.condition {
+                            None => true,
+                            Some(condition) => self.holds_value(condition),
+                        }
+                })
+        };
+        if pause {
+            let position = DebugPosition {
+                tick: self.tick,
+                node,
+                stack: self.stack.nodes(),
+                returned: None,
+            };
+            self.pause(position, depth);
+        }
+        self.stack.started(node, ());
+    }
+
+    fn returned(&self, node: NodeId, status: &Result<ExecutionStatus, NodeError>) {
+        self.stack.returned(node);
+        let pause = self.debugger.borrow().breakpoints.iter().any(|b| {
+            b.node == node
+                && match (&b.condition, status) {
+                    (Some(BreakCondition::Status(expected)), Ok(status)) => expected == status,
+                    (Some(BreakCondition::Error), Err(_)) => true,
+                    _ => false,
+                }
+        });
+        if pause {
+            let stack = self.stack.nodes();
+            let depth = stack.len();
+            let position = DebugPosition {
+                tick: self.tick,
+                node,
+                stack,
+                returned: Some(match status {
+                    Ok(v) => Ok(*v),
+                    Err(e) => Err(ErrorReport::from_error(e)),
+                }),
+            };
+            self.pause(position, depth);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::control::{internal_server_client, wait_for, TreeClient};
+    use crate::create_server_thread;
+    use crate::tree_support::test_tree_support;
+    use betula_core::basic::{BasicBlackboard, BasicTree};
+    use betula_core::BetulaError;
+
+    fn next_paused(client: &impl TreeClient) -> Result<Option<DebugPosition>, BetulaError> {
+        wait_for(client, |e| match e {
+            InteractionEvent::DebugPaused(position) => Some(position),
+            _ => None,
+        })
+    }
+
+    fn paused_at(client: &impl TreeClient, node: NodeId) -> Result<DebugPosition, BetulaError> {
+        let position = next_paused(client)?.ok_or("expected a pause")?;
+        assert_eq!(position.node, node);
+        Ok(position)
+    }
+
+    fn step(client: &impl TreeClient, command: DebugCommand) -> Result<(), BetulaError> {
+        client.send_command(InteractionCommand::debug(command))?;
+        assert_eq!(next_paused(client)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_breakpoints_and_stepping() -> Result<(), BetulaError> {
+        let text = r#"blackboard globals
+  value x f64 = 0.0
+root sequence = {"memory":false,"retry":false}
+  node success
+  node success
+"#;
+        let config = crate::text_format::from_text(text)?;
+        let latest = config.clone().into_latest();
+        let root = latest.tree_roots[0];
+        let children = &latest.nodes.iter().find(|n| n.id == root).unwrap().children;
+        let (a, b) = (children[0], children[1]);
+        let globals = latest.blackboards[0].id;
+
+        let (server, client) = internal_server_client();
+        let runner =
+            create_server_thread::<BasicTree, BasicBlackboard>(Box::new(test_tree_support), server);
+        client.send_command(InteractionCommand::load_tree_config(config))?;
+
+        // The value only holds for the condition on the second child.
+        let holds = |value: f64| -> Result<_, BetulaError> {
+            Ok(Some(BreakCondition::Value {
+                blackboard: globals,
+                port: "x".into(),
+                value: test_tree_support().value_serialize(&value)?,
+            }))
+        };
+        let breakpoints = vec![
+            Breakpoint {
+                node: a,
+                condition: holds(1.0)?,
+            },
+            Breakpoint {
+                node: b,
+                condition: holds(0.0)?,
+            },
+        ];
+        client.send_command(InteractionCommand::debug(DebugCommand::SetBreakpoints(
+            breakpoints,
+        )))?;
+        client.send_command(InteractionCommand::run_specific(&[root]))?;
+        let position = paused_at(&client, b)?;
+        assert_eq!(position.stack, vec![root]);
+        assert_eq!(position.returned, None);
+
+        // Other commands wait until the tick finished.
+        client.send_command(InteractionCommand::request_tree_config())?;
+        step(&client, DebugCommand::Continue)?;
+        wait_for(&client, |e| match e {
+            InteractionEvent::ExecutionResult(_) => Some(()),
+            InteractionEvent::TreeConfig(_) => panic!("tree config before the result"),
+            _ => None,
+        })?;
+        wait_for(&client, |e| match e {
+            InteractionEvent::TreeConfig(_) => Some(()),
+            _ => None,
+        })?;
+
+        // Pause before the root, then step through the tree up to the root returning.
+        let breakpoints = vec![Breakpoint {
+            node: root,
+            condition: Some(BreakCondition::Status(ExecutionStatus::Success)),
+        }];
+        client.send_command(InteractionCommand::debug(DebugCommand::SetBreakpoints(
+            breakpoints,
+        )))?;
+        client.send_command(InteractionCommand::debug(DebugCommand::Pause))?;
+        client.send_command(InteractionCommand::run_specific(&[root]))?;
+        assert_eq!(paused_at(&client, root)?.stack, vec![]);
+        step(&client, DebugCommand::StepInto)?;
+        assert_eq!(paused_at(&client, a)?.stack, vec![root]);
+        step(&client, DebugCommand::StepOver)?;
+        paused_at(&client, b)?;
+        step(&client, DebugCommand::StepOver)?;
+        let position = paused_at(&client, root)?;
+        assert_eq!(position.returned, Some(Ok(ExecutionStatus::Success)));
+        step(&client, DebugCommand::Continue)?;
+        wait_for(&client, |e| match e {
+            InteractionEvent::ExecutionResult(_) => Some(()),
+            _ => None,
+        })?;
+
+        drop(client);
+        let _ = runner.join();
+        Ok(())
+    }
+}
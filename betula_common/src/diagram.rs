@@ -0,0 +1,285 @@
+//! Export of a tree as a Graphviz DOT or Mermaid diagram.
+//!
+//! Nodes show their name and type, roots have a double border and the edges to children are
+//! numbered in the order of execution. Blackboards are drawn as cylinders with dashed edges for
+//! the port connections, pointing from the blackboard to inputs and from outputs to the
+//! blackboard. If an [`ExecutionResult`] is provided the nodes are colored by their status.
+use betula_core::blackboard::PortDirection;
+use betula_core::{error::ErrorReport, BetulaError, ExecutionStatus, NodeId};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::control::ExecutionResult;
+use crate::tree_support::{v2, TreeConfig};
+
+type Status = Result<ExecutionStatus, ErrorReport>;
+
+/// Name and fill color of a status.
+fn status_style(status: &Status) -> (&'static str, &'static str) {
+    match status {
+        Ok(ExecutionStatus::Success) => ("success", "#7ac74f"),
+        Ok(ExecutionStatus::Failure) => ("failure", "#e05d5d"),
+        Ok(ExecutionStatus::Running) => ("running", "#f2b53a"),
+        Err(_) => ("error", "#d35fd3"),
+    }
+}
+
+/// The parts of the diagram, shared by both output formats.
+struct Diagram<'a> {
+    root: v2::Root,
+    nodes: HashMap<NodeId, usize>,
+    status: HashMap<NodeId, &'a Status>,
+}
+
+impl<'a> Diagram<'a> {
+    fn new(config: &TreeConfig, result: Option<&'a ExecutionResult>) -> Self {
+        let root = config.clone().into_latest();
+        let nodes = root.nodes.iter().enumerate().map(|(i, n)| (n.id, i));
+        let status = result
+            .into_iter()
+            .flat_map(|r| r.node_status.iter())
+            .map(|s| (s.node, &s.status));
+        let nodes = nodes.collect();
+        Diagram {
+            root,
+            nodes,
+            status: status.collect(),
+        }
+    }
+
+    /// The label lines of a node, its name if set and its type.
+    fn node_label(node: &v2::TreeNode) -> Vec<&str> {
+        node.name
+            .as_deref()
+            .into_iter()
+            .chain([node.node_type.as_str()])
+            .collect()
+    }
+
+    fn blackboard_label(blackboard: &v2::Blackboard) -> &str {
+        blackboard.name.as_deref().unwrap_or("blackboard")
+    }
+
+    /// Child relations as parent, child and one based position.
+    fn children(&self) -> Result<Vec<(usize, usize, usize)>, BetulaError> {
+        let mut edges = vec![];
+        for (i, node) in self.root.nodes.iter().enumerate() {
+            for (position, child) in node.children.iter().enumerate() {
+                let child = self
+                    .nodes
+                    .get(child)
+                    .ok_or(format!("could not find child {child:?}"))?;
+                edges.push((i, *child, position + 1));
+            }
+        }
+        Ok(edges)
+    }
+
+    /// Port connections as source, destination and label, with `b` or `n` prefixed indices.
+    fn connections(&self) -> Result<Vec<(String, String, String)>, BetulaError> {
+        let mut edges = vec![];
+        for (b, blackboard) in self.root.blackboards.iter().enumerate() {
+            for connection in blackboard.connections.iter() {
+                let node = connection.node.node();
+                let n = self
+                    .nodes
+                    .get(&node)
+                    .ok_or(format!("could not find node {node:?}"))?;
+                let port = connection.node.name();
+                let bb_port = connection.blackboard.name();
+                let label = if port == bb_port {
+                    port.0
+                } else {
+                    format!("{} → {}", port.0, bb_port.0)
+                };
+                let (n, b) = (format!("n{n}"), format!("b{b}"));
+                edges.push(match connection.node.direction() {
+                    PortDirection::Input => (b, n, label),
+                    PortDirection::Output => (n, b, label),
+                });
+            }
+        }
+        Ok(edges)
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Create a Graphviz DOT diagram of the tree, optionally colored by the execution result.
+pub fn to_dot(
+    config: &TreeConfig,
+    result: Option<&ExecutionResult>,
+) -> Result<String, BetulaError> {
+    let diagram = Diagram::new(config, result);
+    let mut out = String::new();
+    out += "digraph tree {\n";
+    out += "  ordering=out;\n";
+    out += "  node [shape=box];\n";
+    for (i, node) in diagram.root.nodes.iter().enumerate() {
+        let label: Vec<String> = Diagram::node_label(node)
+            .iter()
+            .map(|s| dot_escape(s))
+            .collect();
+        let mut attributes = vec![format!("label=\"{}\"", label.join("\\n"))];
+        if diagram.root.tree_roots.contains(&node.id) {
+            attributes.push("peripheries=2".to_owned());
+        }
+        if let Some(status) = diagram.status.get(&node.id) {
+            attributes.push(format!(
+                "style=filled, fillcolor=\"{}\"",
+                status_style(status).1
+            ));
+        }
+        out += &format!("  n{i} [{}];\n", attributes.join(", "));
+    }
+    for (i, blackboard) in diagram.root.blackboards.iter().enumerate() {
+        let label = dot_escape(Diagram::blackboard_label(blackboard));
+        out += &format!("  b{i} [label=\"{label}\", shape=cylinder];\n");
+    }
+    for (parent, child, position) in diagram.children()? {
+        out += &format!("  n{parent} -> n{child} [label=\"{position}\"];\n");
+    }
+    for (from, to, label) in diagram.connections()? {
+        let label = dot_escape(&label);
+        out += &format!("  {from} -> {to} [style=dashed, label=\"{label}\"];\n");
+    }
+    out += "}\n";
+    Ok(out)
+}
+
+fn mermaid_escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+/// Create a Mermaid flowchart of the tree, optionally colored by the execution result.
+pub fn to_mermaid(
+    config: &TreeConfig,
+    result: Option<&ExecutionResult>,
+) -> Result<String, BetulaError> {
+    let diagram = Diagram::new(config, result);
+    let mut out = String::new();
+    out += "flowchart TD\n";
+    for (i, node) in diagram.root.nodes.iter().enumerate() {
+        let label: Vec<String> = Diagram::node_label(node)
+            .iter()
+            .map(|s| mermaid_escape(s))
+            .collect();
+        let label = label.join("<br/>");
+        if diagram.root.tree_roots.contains(&node.id) {
+            out += &format!("  n{i}[[\"{label}\"]]\n");
+        } else {
+            out += &format!("  n{i}[\"{label}\"]\n");
+        }
+    }
+    for (i, blackboard) in diagram.root.blackboards.iter().enumerate() {
+        let label = mermaid_escape(Diagram::blackboard_label(blackboard));
+        out += &format!("  b{i}[(\"{label}\")]\n");
+    }
+    for (parent, child, position) in diagram.children()? {
+        out += &format!("  n{parent} -->|{position}| n{child}\n");
+    }
+    for (from, to, label) in diagram.connections()? {
+        let label = mermaid_escape(&label);
+        out += &format!("  {from} -.->|\"{label}\"| {to}\n");
+    }
+    let styles: BTreeSet<_> = diagram.status.values().map(|s| status_style(s)).collect();
+    for (class, color) in styles {
+        out += &format!("  classDef {class} fill:{color}\n");
+    }
+    for (i, node) in diagram.root.nodes.iter().enumerate() {
+        if let Some(status) = diagram.status.get(&node.id) {
+            out += &format!("  class n{i} {}\n", status_style(status).0);
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::control::NodeStatus;
+    use crate::tree_support::test_tree_support;
+
+    #[test]
+    fn test_diagram() -> Result<(), BetulaError> {
+        let tree_support = test_tree_support();
+        let text = r#"blackboard globals
+root sequence "main \"tree\""
+  node std_time
+    output time -> globals now
+  node std_delay
+    input time <- globals now
+  node failure
+"#;
+        let root = crate::text_format::from_text(text)?.into_latest();
+        let root_id = root.tree_roots[0];
+        let failure = root.nodes.iter().find(|n| n.node_type == "failure");
+        let failure = failure.unwrap().id;
+        let config = TreeConfig::V2(root);
+        let result = ExecutionResult {
+            node_status: vec![
+                NodeStatus {
+                    node: failure,
+                    status: Ok(ExecutionStatus::Failure),
+                },
+                NodeStatus {
+                    node: root_id,
+                    status: Ok(ExecutionStatus::Failure),
+                },
+            ],
+        };
+
+        let dot = to_dot(&config, Some(&result))?;
+        let expected = r##"digraph tree {
+  ordering=out;
+  node [shape=box];
+  n0 [label="std_time"];
+  n1 [label="std_delay"];
+  n2 [label="failure", style=filled, fillcolor="#e05d5d"];
+  n3 [label="main \"tree\"\nsequence", peripheries=2, style=filled, fillcolor="#e05d5d"];
+  b0 [label="globals", shape=cylinder];
+  n3 -> n0 [label="1"];
+  n3 -> n1 [label="2"];
+  n3 -> n2 [label="3"];
+  n0 -> b0 [style=dashed, label="time → now"];
+  b0 -> n1 [style=dashed, label="time → now"];
+}
+"##;
+        assert_eq!(dot, expected);
+
+        let mermaid = to_mermaid(&config, Some(&result))?;
+        let expected = r#"flowchart TD
+  n0["std_time"]
+  n1["std_delay"]
+  n2["failure"]
+  n3[["main #quot;tree#quot;<br/>sequence"]]
+  b0[("globals")]
+  n3 -->|1| n0
+  n3 -->|2| n1
+  n3 -->|3| n2
+  n0 -.->|"time → now"| b0
+  b0 -.->|"time → now"| n1
+  classDef failure fill:#e05d5d
+  class n2 failure
+  class n3 failure
+"#;
+        assert_eq!(mermaid, expected);
+
+        // Without status there is no coloring.
+        assert!(!to_dot(&config, None)?.contains("fillcolor"));
+        assert!(!to_mermaid(&config, None)?.contains("classDef"));
+
+        // The tree based entry point matches.
+        let mut tree = betula_core::basic::BasicTree::new();
+        tree_support.import_tree_config(&mut tree, &config)?;
+        let exported = tree_support.export_tree_dot(&tree, None)?;
+        assert_eq!(
+            exported.lines().count(),
+            to_dot(&config, None)?.lines().count()
+        );
+        Ok(())
+    }
+}
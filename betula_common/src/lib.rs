@@ -1,8 +1,19 @@
+pub mod btcpp;
+pub mod builder;
 pub mod callback;
 pub mod control;
+mod debugger;
+pub mod diagram;
+pub mod placeholder_node;
 mod server_thread;
+pub mod snapshot;
+pub mod subtree_node;
+pub mod text_format;
+pub mod trace;
 pub mod tree_support;
 pub mod type_support;
 
-pub use server_thread::{create_server_thread, TreeSupportCreator};
+pub use server_thread::{
+    create_server_thread, execute_profiled, execute_tracked, TreeSupportCreator,
+};
 pub use tree_support::TreeSupport;
@@ -0,0 +1,151 @@
+//! Placeholder for nodes whose type is not supported.
+//!
+//! When [`TreeSupport::add_node_placeholder`](crate::TreeSupport::add_node_placeholder) is used,
+//! nodes of unknown types are loaded as a [`PlaceholderNode`] instead of failing the import. The
+//! placeholder keeps the original node type, the serialized config and its version, and it is
+//! exported exactly as it was loaded. Children and port connections are held by the tree as for
+//! any other node. Executing a placeholder returns an error.
+use betula_core::blackboard::{PortDirection, PortName, PortType};
+use betula_core::error::{ErrorKind, ExecutionError};
+use betula_core::node_prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A port of the original node, known from its connections.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlaceholderPort {
+    pub name: PortName,
+    pub direction: PortDirection,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlaceholderNodeConfig {
+    /// The type of the original node.
+    pub node_type: NodeType,
+    /// The serialized config of the original node.
+    pub config: Option<serde_json::Value>,
+    /// The version of the serialized config.
+    #[serde(default)]
+    pub config_version: u32,
+    /// The connected ports of the original node.
+    #[serde(default)]
+    pub ports: Vec<PlaceholderPort>,
+}
+impl IsNodeConfig for PlaceholderNodeConfig {}
+
+impl Default for PlaceholderNodeConfig {
+    fn default() -> Self {
+        PlaceholderNodeConfig {
+            node_type: PlaceholderNode::static_type(),
+            config: None,
+            config_version: 0,
+            ports: vec![],
+        }
+    }
+}
+
+/// The type of the ports of a placeholder, the type of the original ports is unknown.
+#[derive(Debug)]
+pub struct UnknownPortType;
+
+/// Node that stands in for a node of an unsupported type, see the [module](self) documentation.
+#[derive(Debug, Default)]
+pub struct PlaceholderNode {
+    pub config: PlaceholderNodeConfig,
+}
+
+impl PlaceholderNode {
+    pub fn new(config: PlaceholderNodeConfig) -> Self {
+        PlaceholderNode { config }
+    }
+}
+
+impl Node for PlaceholderNode {
+    fn execute(&mut self, ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+        let _ = ctx;
+        Err(ExecutionError::new(
+            ErrorKind::Config,
+            format!(
+                "node type {:?} is not supported, this is a placeholder",
+                self.config.node_type.0
+            ),
+        )
+        .into())
+    }
+
+    fn ports(&self) -> Result<Vec<Port>, NodeError> {
+        Ok(self
+            .config
+            .ports
+            .iter()
+            .map(|p| {
+                Port::new(
+                    PortType::new::<UnknownPortType>(),
+                    p.direction,
+                    p.name.clone(),
+                )
+            })
+            .collect())
+    }
+
+    fn get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
+        Ok(Some(Box::new(self.config.clone())))
+    }
+
+    fn set_config(&mut self, config: &dyn NodeConfig) -> Result<(), NodeError> {
+        self.config.load_node_config(config)
+    }
+
+    fn static_type() -> NodeType {
+        "placeholder".into()
+    }
+
+    fn node_type(&self) -> NodeType {
+        self.config.node_type.clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tree_support::test_tree_support;
+    use betula_core::basic::BasicTree;
+    use betula_core::{BetulaError, Tree};
+
+    #[test]
+    fn test_placeholder() -> Result<(), BetulaError> {
+        let mut tree_support = test_tree_support();
+        let text = r#"blackboard globals
+  value time f64 = 0.0
+root sequence = {"memory":false,"retry":false}
+  node image_capture =2 {"capture":{"display":1}}
+    input time <- globals
+    output image -> globals
+    node success
+"#;
+        let mut tree = BasicTree::new();
+        assert!(tree_support.import_tree_text(&mut tree, text).is_err());
+
+        tree_support.add_node_placeholder();
+        tree_support.import_tree_text(&mut tree, text)?;
+        assert_eq!(tree_support.export_tree_text(&tree)?, text);
+
+        let root = tree.roots()[0];
+        let placeholder = tree.children(root)?[0];
+        let mut ports = tree.node_ports(placeholder)?;
+        ports.sort_by_key(|p| p.name());
+        assert_eq!(ports[0].name(), "image".into());
+        assert_eq!(ports[1].direction(), PortDirection::Input);
+        assert!(tree.execute(root).is_err());
+
+        // The config sent to the editor holds the ports as well.
+        let node = tree.node_ref(placeholder).ok_or("missing node")?;
+        let config = node.borrow().get_config()?.ok_or("missing config")?;
+        let serialized = tree_support.config_serialize("image_capture".into(), &*config)?;
+        let config = tree_support.config_deserialize(serialized)?;
+        let mut new_node = tree_support.create_node(&"image_capture".into())?;
+        new_node.set_config(&*config)?;
+        assert_eq!(new_node.node_type(), "image_capture".into());
+        assert_eq!(new_node.ports()?.len(), 2);
+        Ok(())
+    }
+}
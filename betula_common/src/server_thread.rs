@@ -1,38 +1,134 @@
 use crate::{
     control::{
-        BlackboardValues, CommandResult, ExecutionResult, InteractionCommand, InteractionEvent,
-        NodeStatus, TreeServer,
+        BlackboardValues, CommandResult, ExecutionProfile, ExecutionResult, InteractionCommand,
+        InteractionEvent, NodeStatus, TreeServer,
     },
+    debugger::{DebugObserver, Debugger},
+    trace::TraceRecorder,
     TreeSupport,
 };
-use betula_core::{BetulaError, ExecutionStatus, NodeError, NodeId, RunContext, Tree};
+use betula_core::{
+    error::ErrorReport, BetulaError, ExecutionObserver, ExecutionStatus, NodeError, NodeId, Tree,
+};
 
 use std::cell::RefCell;
-struct TrackedTreeContext<'a, 'b> {
-    this_node: NodeId,
-    tree: &'a dyn Tree,
-    status: &'b RefCell<Vec<NodeStatus>>,
+use std::time::{Duration, Instant};
+
+/// The nodes that are executing, outermost first, with data for each of them.
+pub(crate) struct ExecutionStack<T>(RefCell<Vec<(NodeId, T)>>);
+
+impl<T> Default for ExecutionStack<T> {
+    fn default() -> Self {
+        ExecutionStack(Default::default())
+    }
+}
+
+impl<T> ExecutionStack<T> {
+    /// Add a node that started executing.
+    pub fn started(&self, node: NodeId, data: T) {
+        self.0.borrow_mut().push((node, data));
+    }
+
+    /// Remove a node that returned, with its data if it was started.
+    pub fn returned(&self, node: NodeId) -> Option<T> {
+        let mut stack = self.0.borrow_mut();
+        // Nodes that return without executing were not started.
+        if stack.last().map(|s| s.0) != Some(node) {
+            return None;
+        }
+        stack.pop().map(|(_, data)| data)
+    }
+
+    /// The number of nodes that are executing.
+    pub fn depth(&self) -> usize {
+        self.0.borrow().len()
+    }
+
+    /// The nodes that are executing, outermost first.
+    pub fn nodes(&self) -> Vec<NodeId> {
+        self.0.borrow().iter().map(|s| s.0).collect()
+    }
+
+    /// Modify the data of the innermost node, if any node is executing.
+    pub fn innermost(&self, f: impl FnOnce(&mut T)) {
+        if let Some((_, data)) = self.0.borrow_mut().last_mut() {
+            f(data);
+        }
+    }
 }
-impl RunContext for TrackedTreeContext<'_, '_> {
-    fn children(&self) -> usize {
-        self.tree
-            .children(self.this_node)
-            .expect("node must exist in tree")
-            .len()
+
+/// Observer that collects the status of nodes and optionally the time spent in them.
+#[derive(Default)]
+struct TrackingObserver<'a> {
+    status: RefCell<Vec<NodeStatus>>,
+    /// The started nodes with their start time and the time spent in their children.
+    started: Option<ExecutionStack<(Instant, Duration)>>,
+    durations: RefCell<Vec<(NodeId, Duration)>>,
+    /// Observer that is called as well, the time spent in it is not attributed to nodes.
+    inner: Option<&'a dyn ExecutionObserver>,
+}
+
+impl ExecutionObserver for TrackingObserver<'_> {
+    fn started(&self, node: NodeId) {
+        if let Some(inner) = self.inner {
+            inner.started(node);
+        }
+        if let Some(started) = &self.started {
+            started.started(node, (Instant::now(), Duration::ZERO));
+        }
     }
-    fn run(&self, index: usize) -> Result<ExecutionStatus, NodeError> {
-        let ids = self.tree.children(self.this_node)?;
-        let (v, all) = execute_tracked(self.tree, ids[index])?;
-        let mut status = self.status.borrow_mut();
-        for s in all {
-            status.push(s);
+
+    fn returned(&self, node: NodeId, status: &Result<ExecutionStatus, NodeError>) {
+        let now = Instant::now();
+        self.track(node, status, now);
+        if let Some(inner) = self.inner {
+            inner.returned(node, status);
         }
-        v
     }
-    fn reset_recursive(&self, index: usize) -> Result<(), NodeError> {
-        let ids = self.tree.children(self.this_node)?;
-        self.tree.reset_recursive(ids[index])
+}
+
+impl TrackingObserver<'_> {
+    fn track(&self, node: NodeId, status: &Result<ExecutionStatus, NodeError>, now: Instant) {
+        self.status.borrow_mut().push(NodeStatus {
+            node,
+            status: match status {
+                Ok(v) => Ok(*v),
+                Err(e) => Err(ErrorReport::from_error(e)),
+            },
+        });
+        let Some(started) = &self.started else {
+            return;
+        };
+        let Some((start, children)) = started.returned(node) else {
+            return;
+        };
+        let elapsed = now - start;
+        started.innermost(|parent| parent.1 += elapsed);
+        self.durations
+            .borrow_mut()
+            .push((node, elapsed.saturating_sub(children)));
+    }
+}
+
+/// Execute a node, tracking the status of all nodes and optionally profiling them.
+fn execute_observing(
+    tree: &dyn Tree,
+    id: NodeId,
+    profile: Option<&mut ExecutionProfile>,
+    inner: Option<&dyn ExecutionObserver>,
+) -> Result<(Result<ExecutionStatus, BetulaError>, Vec<NodeStatus>), BetulaError> {
+    let observer = TrackingObserver {
+        started: profile.is_some().then(Default::default),
+        inner,
+        ..Default::default()
+    };
+    let v = tree.execute_observed(id, &observer);
+    if let Some(profile) = profile {
+        for (node, duration) in observer.durations.into_inner() {
+            profile.add(node, duration);
+        }
     }
+    Ok((v, observer.status.into_inner()))
 }
 
 /// Execute a node on a tree and track all node execution status.
@@ -40,54 +136,71 @@ pub fn execute_tracked(
     tree: &dyn Tree,
     id: NodeId,
 ) -> Result<(Result<ExecutionStatus, BetulaError>, Vec<NodeStatus>), BetulaError> {
-    let mut res: RefCell<Vec<NodeStatus>> = RefCell::new(vec![]);
-    let mut n = tree
-        .node_ref(id)
-        .ok_or_else(|| format!("node {id:?} does not exist").to_string())?
-        .try_borrow_mut()?;
-
-    let context = TrackedTreeContext {
-        this_node: id,
-        tree,
-        status: &mut res,
-    };
+    execute_observing(tree, id, None, None)
+}
 
-    let v = n.execute(&context);
-    if let Err(e) = v {
-        {
-            let e_string = format!("{}", e);
-            let mut modifyable = res.borrow_mut();
-            modifyable.push(NodeStatus {
-                node: id,
-                status: Err(e_string),
-            });
-        }
-        return Ok((Err(e), res.into_inner()));
-    }
-
-    let v = v.unwrap();
-    {
-        let mut modifyable = res.borrow_mut();
-        modifyable.push(NodeStatus {
-            node: id,
-            status: Ok(v),
-        });
-    }
-    Ok((Ok(v), res.into_inner()))
+/// Execute a node like [`execute_tracked`], adding the wall-clock time each executed node spent in
+/// its execute, excluding the time spent in its children, to the profile.
+pub fn execute_profiled(
+    tree: &dyn Tree,
+    id: NodeId,
+    profile: &mut ExecutionProfile,
+) -> Result<(Result<ExecutionStatus, BetulaError>, Vec<NodeStatus>), BetulaError> {
+    execute_observing(tree, id, Some(profile), None)
 }
 
 /// Function to create the tree support in the background server thread.
 pub type TreeSupportCreator = Box<dyn Fn() -> TreeSupport + Send>;
 
+/// Set the tick on all blackboards of the tree, see [`betula_core::Blackboard::set_tick`].
+pub(crate) fn set_tick(tree: &dyn betula_core::Tree, tick: u64) -> Result<(), BetulaError> {
+    for id in tree.blackboards() {
+        if let Some(blackboard) = tree.blackboard_ref(id) {
+            blackboard.try_borrow_mut()?.set_tick(tick);
+        }
+    }
+    Ok(())
+}
+
+/// The profile of the ticks in the current window, see [`InteractionCommand::Profile`].
+struct Profiling {
+    window: u64,
+    profile: ExecutionProfile,
+}
+
+/// What the server thread does besides executing the tree.
+struct Instrumentation {
+    recorder: Option<TraceRecorder>,
+    profiling: Option<Profiling>,
+    debugger: Debugger,
+}
+
 fn run_nodes(
     tree_support: &TreeSupport,
     tree: &dyn betula_core::Tree,
     roots: &[betula_core::NodeId],
+    tick: &mut u64,
+    instrumentation: &mut Instrumentation,
+    server: &dyn TreeServer,
 ) -> Result<Vec<InteractionEvent>, BetulaError> {
+    *tick += 1;
+    set_tick(tree, *tick)?;
+    let time = tree.clock().now()?;
     let mut events = vec![];
     let mut status: Vec<NodeStatus> = vec![];
     for r in roots.iter() {
-        match execute_tracked(tree, *r) {
+        let profile = instrumentation
+            .profiling
+            .as_mut()
+            .map(|profiling| &mut profiling.profile);
+        let debugger = &mut instrumentation.debugger;
+        let executed = if debugger.is_active() {
+            let observer = DebugObserver::new(debugger, server, tree_support, tree, *tick);
+            execute_observing(tree, *r, profile, Some(&observer))
+        } else {
+            execute_observing(tree, *r, profile, None)
+        };
+        match executed {
             Ok((_this_node, mut all_nodes)) => {
                 status.extend(&mut all_nodes.drain(..));
             }
@@ -97,10 +210,27 @@ fn run_nodes(
         }
     }
 
-    if !status.is_empty() {
-        events.push(InteractionEvent::ExecutionResult(ExecutionResult {
-            node_status: status,
-        }));
+    let result = ExecutionResult {
+        node_status: status,
+    };
+    if let Some(trace) = &mut instrumentation.recorder {
+        let values = BlackboardValues::from_tree(tree_support, tree)?;
+        if let Err(e) = trace.record_tick(*tick, time, &result, &values) {
+            println!("failed to record tick, stopping the trace: {e:?}");
+            instrumentation.recorder = None;
+        }
+    }
+
+    if !result.node_status.is_empty() {
+        events.push(InteractionEvent::ExecutionResult(result));
+    }
+
+    if let Some(profiling) = &mut instrumentation.profiling {
+        profiling.profile.ticks += 1;
+        if profiling.profile.ticks >= profiling.window {
+            let profile = std::mem::take(&mut profiling.profile);
+            events.push(InteractionEvent::ExecutionProfile(profile));
+        }
     }
 
     // Dump all blackboard values to the frontend for now.
@@ -112,34 +242,129 @@ fn run_nodes(
     Ok(events)
 }
 
+/// Whether the tree may be different after the command, such that a trace records it again.
+fn changes_tree(command: &InteractionCommand) -> bool {
+    !matches!(
+        command,
+        InteractionCommand::RunSettings(_)
+            | InteractionCommand::RequestTreeConfig
+            | InteractionCommand::SnapshotBlackboards(_)
+            | InteractionCommand::RestoreBlackboards(_)
+            | InteractionCommand::SetDirectory(_)
+            | InteractionCommand::RecordTrace(_)
+            | InteractionCommand::Profile(_)
+            | InteractionCommand::Debug(_)
+    )
+}
+
+/// Start a trace at the path, recording the current tree.
+fn start_trace(
+    tree_support: &TreeSupport,
+    tree: &dyn betula_core::Tree,
+    path: &str,
+) -> Result<TraceRecorder, BetulaError> {
+    let mut recorder = TraceRecorder::create(std::path::Path::new(path))?;
+    recorder.record_tree(&tree_support.export_tree_config(tree)?)?;
+    Ok(recorder)
+}
+
 /// Function to run a Tree and TreeServer in the background.
+///
+/// Blackboards of type `B` are created if the tree support has no blackboard factory. To share
+/// blackboards with other threads, register their names in a
+/// [`betula_core::sync::SharedBlackboards`] and set it with
+/// [`TreeSupport::set_shared_blackboards`].
 pub fn create_server_thread<T: betula_core::Tree, B: betula_core::Blackboard + 'static>(
     tree_support: TreeSupportCreator,
     server: impl TreeServer + std::marker::Send + 'static,
 ) -> std::thread::JoinHandle<Result<(), BetulaError>> {
     std::thread::spawn(move || -> Result<(), betula_core::BetulaError> {
         let mut tree = T::new();
-        let tree_support = tree_support();
+        let mut tree_support = tree_support();
+        if !tree_support.has_blackboard_factory() {
+            tree_support.set_blackboard_factory(Box::new(|| Box::new(B::new())));
+        }
+        tree.set_conversions(tree_support.conversions().clone())?;
 
         let mut run_roots: bool = false;
+        let mut tick: u64 = 0;
         let mut sleep_interval = std::time::Duration::from_millis(10);
+        let mut instrumentation = Instrumentation {
+            recorder: None,
+            profiling: None,
+            debugger: Debugger::new(),
+        };
         loop {
             std::thread::sleep(sleep_interval);
 
             loop {
-                let received = server.get_command()?;
+                // Commands received while paused in the debugger go first.
+                let received = match instrumentation.debugger.next_deferred() {
+                    Some(command) => Some(command),
+                    None => server.get_command()?,
+                };
                 if let Some(command) = received {
                     println!("    Executing {command:?}");
+                    if let InteractionCommand::Debug(debug_command) = &command {
+                        instrumentation.debugger.apply(debug_command, None);
+                        server.send_event(InteractionEvent::CommandResult(CommandResult {
+                            command,
+                            error: None,
+                        }))?;
+                        continue;
+                    }
+                    if let InteractionCommand::RecordTrace(path) = &command {
+                        let started = path
+                            .as_deref()
+                            .map(|path| start_trace(&tree_support, &tree, path))
+                            .transpose();
+                        let error = match started {
+                            Ok(new_recorder) => {
+                                instrumentation.recorder = new_recorder;
+                                None
+                            }
+                            Err(e) => Some(format!("{e:?}")),
+                        };
+                        server.send_event(InteractionEvent::CommandResult(CommandResult {
+                            command,
+                            error,
+                        }))?;
+                        continue;
+                    }
+                    if let InteractionCommand::Profile(window) = &command {
+                        instrumentation.profiling = window.map(|window| Profiling {
+                            window: window.max(1),
+                            profile: Default::default(),
+                        });
+                        server.send_event(InteractionEvent::CommandResult(CommandResult {
+                            command,
+                            error: None,
+                        }))?;
+                        continue;
+                    }
                     if let InteractionCommand::RunSettings(run_settings) = &command {
                         if let Some(new_value) = run_settings.roots {
                             // println!("Setting run roots to: {new_value}");
+                            if run_roots && !new_value {
+                                // Execution stops, halt anything that was running.
+                                for root in tree.roots() {
+                                    let _ = tree.halt_recursive(root);
+                                }
+                            }
                             run_roots = new_value;
                         }
                         if let Some(new_duration) = run_settings.interval {
                             sleep_interval = new_duration;
                         }
                         if !run_settings.specific.is_empty() {
-                            let events = run_nodes(&tree_support, &tree, &run_settings.specific)?;
+                            let events = run_nodes(
+                                &tree_support,
+                                &tree,
+                                &run_settings.specific,
+                                &mut tick,
+                                &mut instrumentation,
+                                &server,
+                            )?;
                             for e in events {
                                 server.send_event(e)?;
                             }
@@ -149,6 +374,19 @@ pub fn create_server_thread<T: betula_core::Tree, B: betula_core::Blackboard + '
                     let r = command.execute(&tree_support, &mut tree);
                     match r {
                         Ok(v) => {
+                            if let Some(trace) = instrumentation
+                                .recorder
+                                .as_mut()
+                                .filter(|_| changes_tree(&command))
+                            {
+                                let recorded = tree_support
+                                    .export_tree_config(&tree)
+                                    .and_then(|config| trace.record_tree(&config));
+                                if let Err(e) = recorded {
+                                    println!("failed to record tree, stopping the trace: {e:?}");
+                                    instrumentation.recorder = None;
+                                }
+                            }
                             for event in v {
                                 server.send_event(event)?;
                             }
@@ -168,7 +406,14 @@ pub fn create_server_thread<T: betula_core::Tree, B: betula_core::Blackboard + '
 
             if run_roots {
                 let roots = tree.roots();
-                let events = run_nodes(&tree_support, &tree, &roots)?;
+                let events = run_nodes(
+                    &tree_support,
+                    &tree,
+                    &roots,
+                    &mut tick,
+                    &mut instrumentation,
+                    &server,
+                )?;
                 for e in events {
                     server.send_event(e)?;
                 }
@@ -180,7 +425,10 @@ pub fn create_server_thread<T: betula_core::Tree, B: betula_core::Blackboard + '
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::control::{internal_server_client, wait_for, InProcessControlClient, TreeClient};
+    use crate::tree_support::test_tree_support;
     use betula_core::basic::BasicTree;
+    use betula_core::{Blackboard, NodeError, NodeId};
     use betula_std::nodes::*;
     use uuid::Uuid;
 
@@ -207,4 +455,130 @@ mod tests {
         assert_eq!(all_nodes[2].status, Ok(ExecutionStatus::Success));
         Ok(())
     }
+
+    #[derive(Debug, Default)]
+    struct SleepNode {}
+    impl betula_core::Node for SleepNode {
+        fn execute(
+            &mut self,
+            _ctx: &dyn betula_core::RunContext,
+        ) -> Result<ExecutionStatus, NodeError> {
+            std::thread::sleep(Duration::from_millis(20));
+            Ok(ExecutionStatus::Success)
+        }
+        fn static_type() -> betula_core::NodeType {
+            "sleep".into()
+        }
+        fn node_type(&self) -> betula_core::NodeType {
+            Self::static_type()
+        }
+    }
+
+    #[test]
+    fn sequence_profiled() -> Result<(), NodeError> {
+        let mut tree = BasicTree::new();
+        let root_id =
+            tree.add_node_boxed(NodeId(Uuid::new_v4()), Box::new(SequenceNode::default()))?;
+        let sleep_id = tree.add_node_boxed(NodeId(Uuid::new_v4()), Box::new(SleepNode {}))?;
+        let s1 = tree.add_node_boxed(NodeId(Uuid::new_v4()), Box::new(SuccessNode {}))?;
+        tree.set_children(root_id, &[sleep_id, s1])?;
+        let mut profile = ExecutionProfile::default();
+        for _ in 0..2 {
+            let (this_node, all_nodes) = execute_profiled(&tree, root_id, &mut profile)?;
+            assert_eq!(this_node.ok(), Some(ExecutionStatus::Success));
+            assert_eq!(all_nodes.len(), 3);
+        }
+        assert_eq!(profile.nodes.len(), 3);
+        let sleep = profile.nodes[&sleep_id];
+        assert_eq!(sleep.executions, 2);
+        assert!(sleep.min >= Duration::from_millis(20));
+        assert!(sleep.mean() >= sleep.min && sleep.mean() <= sleep.max);
+        assert_eq!(profile.slowest()[0].0, sleep_id);
+        // The time of the children is excluded from the sequence.
+        assert!(profile.nodes[&root_id].max < Duration::from_millis(20));
+        Ok(())
+    }
+
+    #[test]
+    fn shared_blackboards() -> Result<(), BetulaError> {
+        use betula_core::basic::BasicBlackboard;
+        use betula_core::sync::SharedBlackboards;
+
+        let shared = SharedBlackboards::new();
+        shared.add("shared");
+        let start = |text: &str| -> Result<_, BetulaError> {
+            let shared = shared.clone();
+            let (server, client) = internal_server_client();
+            let runner = create_server_thread::<BasicTree, BasicBlackboard>(
+                Box::new(move || {
+                    let mut tree_support = test_tree_support();
+                    tree_support.set_shared_blackboards(shared.clone());
+                    tree_support
+                }),
+                server,
+            );
+            let config = crate::text_format::from_text(text)?;
+            let roots = config.clone().into_latest().tree_roots;
+            client.send_command(InteractionCommand::load_tree_config(config))?;
+            wait_for(&client, |e| match e {
+                InteractionEvent::TreeState(_) => Some(()),
+                _ => None,
+            })?;
+            Ok((client, runner, roots))
+        };
+        let run = |client: &InProcessControlClient, roots: &[NodeId]| {
+            client.send_command(InteractionCommand::run_specific(roots))?;
+            wait_for(client, |e| match e {
+                InteractionEvent::ExecutionResult(result) => Some(result),
+                _ => None,
+            })
+        };
+        let status = |result: &ExecutionResult, node: NodeId| {
+            result
+                .node_status
+                .iter()
+                .rfind(|s| s.node == node)
+                .map(|s| s.status.clone())
+        };
+
+        // Both trees have a shared and a local blackboard, the writer writes to both.
+        let (writer, writer_runner, writer_roots) = start(
+            r#"blackboard local
+blackboard shared
+root std_status_write
+  output status -> shared
+  node success
+root std_status_write
+  output status -> local
+  node failure
+"#,
+        )?;
+        let (reader, reader_runner, reader_roots) = start(
+            r#"blackboard local
+  value status betula_core::ExecutionStatus = "Running"
+blackboard shared
+  value status betula_core::ExecutionStatus = "Running"
+root std_status_read
+  input status <- shared
+root std_status_read
+  input status <- local
+"#,
+        )?;
+
+        run(&writer, &writer_roots)?;
+        let result = run(&reader, &reader_roots)?;
+        use ExecutionStatus::*;
+        assert_eq!(status(&result, reader_roots[0]), Some(Ok(Success)));
+        assert_eq!(status(&result, reader_roots[1]), Some(Ok(Running)));
+        let value = shared
+            .get("shared")
+            .ok_or("not shared")?
+            .get(&"status".into());
+        assert!(value.is_some());
+
+        drop((writer, reader));
+        let _ = writer_runner.join();
+        let _ = reader_runner.join();
+        Ok(())
+    }
 }
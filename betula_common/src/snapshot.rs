@@ -0,0 +1,177 @@
+//! Snapshots of blackboard values that persist across runs.
+//!
+//! Only ports marked with [`Tree::set_blackboard_persistent_ports`] are part of a snapshot. The
+//! snapshot is stored in the directory of the tree, such that counters, learned coordinates and
+//! toggles can be restored when the tree is loaded again.
+use betula_core::{BetulaError, BlackboardId, Tree};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::tree_support::{SerializedBlackboardValues, TreeSupport};
+
+/// File name of the snapshot in the directory of the tree.
+pub const SNAPSHOT_FILE_NAME: &str = "blackboard_snapshot.json";
+
+/// The values of the persistent ports of blackboards.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct BlackboardSnapshot {
+    pub blackboards: BTreeMap<BlackboardId, SerializedBlackboardValues>,
+}
+
+impl BlackboardSnapshot {
+    /// Create a snapshot of the provided blackboards, or of all blackboards if empty.
+    pub fn from_tree(
+        tree_support: &TreeSupport,
+        tree: &dyn Tree,
+        blackboards: &[BlackboardId],
+    ) -> Result<Self, BetulaError> {
+        let mut snapshot = BlackboardSnapshot::default();
+        let blackboards = if blackboards.is_empty() {
+            tree.blackboards()
+        } else {
+            blackboards.to_vec()
+        };
+        for blackboard_id in blackboards {
+            let persistent = tree.blackboard_persistent_ports(blackboard_id)?;
+            if persistent.is_empty() {
+                continue;
+            }
+            let bb = tree
+                .blackboard_ref(blackboard_id)
+                .ok_or(format!("cannot find {blackboard_id:?}"))?;
+            let bb = bb.try_borrow()?;
+            let mut values = SerializedBlackboardValues::default();
+            for port in persistent {
+                // Ports may not have been written yet.
+                if let Some(value) = bb.get(&port) {
+                    values.insert(port, tree_support.value_serialize(&*value)?);
+                }
+            }
+            snapshot.blackboards.insert(blackboard_id, values);
+        }
+        Ok(snapshot)
+    }
+
+    /// Restore the values into the provided blackboards, or into all blackboards if empty.
+    ///
+    /// Only ports that are currently persistent are restored, blackboards that are not in the tree
+    /// are ignored. Returns the blackboards that had values restored.
+    pub fn restore(
+        &self,
+        tree_support: &TreeSupport,
+        tree: &mut dyn Tree,
+        blackboards: &[BlackboardId],
+    ) -> Result<Vec<BlackboardId>, BetulaError> {
+        let mut restored = vec![];
+        let present = tree.blackboards();
+        for (blackboard_id, values) in self.blackboards.iter() {
+            if !present.contains(blackboard_id)
+                || !(blackboards.is_empty() || blackboards.contains(blackboard_id))
+            {
+                continue;
+            }
+            let persistent = tree.blackboard_persistent_ports(*blackboard_id)?;
+            let bb = tree
+                .blackboard_mut(*blackboard_id)
+                .ok_or(format!("cannot find {blackboard_id:?}"))?;
+            for (port, value) in values.iter() {
+                if !persistent.contains(port) {
+                    continue;
+                }
+                let value = tree_support.value_deserialize(value.clone())?;
+                bb.set(port, value)
+                    .map_err(|e| format!("failed to restore {port:?}: {e}"))?;
+            }
+            restored.push(*blackboard_id);
+        }
+        Ok(restored)
+    }
+
+    /// The path of the snapshot in the directory of the tree.
+    pub fn path(tree: &dyn Tree) -> Result<PathBuf, BetulaError> {
+        let directory = tree
+            .directory()
+            .ok_or("tree has no directory to hold the snapshot")?;
+        Ok(directory.join(SNAPSHOT_FILE_NAME))
+    }
+
+    /// Write the snapshot as json.
+    pub fn save(&self, path: &Path) -> Result<(), BetulaError> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents.as_bytes())
+            .map_err(|e| format!("failed to write {path:?}: {e}"))?;
+        Ok(())
+    }
+
+    /// Read a snapshot, a file that doesn't exist is an empty snapshot.
+    pub fn load(path: &Path) -> Result<Self, BetulaError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read(path).map_err(|e| format!("failed to read {path:?}: {e}"))?;
+        Ok(serde_json::from_slice(&contents)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use betula_core::basic::{BasicBlackboard, BasicTree};
+    use betula_core::blackboard::PortName;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_snapshot_restore() -> Result<(), BetulaError> {
+        let mut tree_support = TreeSupport::new();
+        tree_support.add_value_default::<f64>();
+        tree_support.add_value_default::<bool>();
+
+        let mut tree = BasicTree::new();
+        let bb = tree.add_blackboard_boxed(
+            BlackboardId(Uuid::new_v4()),
+            Box::new(BasicBlackboard::default()),
+        )?;
+        let counter: PortName = "counter".into();
+        let toggle: PortName = "toggle".into();
+        let scratch: PortName = "scratch".into();
+        let blackboard = tree.blackboard_mut(bb).ok_or("missing blackboard")?;
+        blackboard.set(&counter, Box::new(3.0f64))?;
+        blackboard.set(&toggle, Box::new(true))?;
+        blackboard.set(&scratch, Box::new(5.0f64))?;
+        tree.set_blackboard_persistent_ports(bb, &[counter.clone(), toggle.clone()])?;
+
+        let directory = std::env::temp_dir().join(format!("betula_snapshot_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&directory)?;
+        tree.set_directory(Some(&directory));
+        let path = BlackboardSnapshot::path(&tree)?;
+        assert_eq!(
+            BlackboardSnapshot::load(&path)?,
+            BlackboardSnapshot::default()
+        );
+
+        let snapshot = BlackboardSnapshot::from_tree(&tree_support, &tree, &[])?;
+        assert_eq!(snapshot.blackboards[&bb].len(), 2);
+        snapshot.save(&path)?;
+
+        // Change all values, then restore the persistent ones.
+        let blackboard = tree.blackboard_mut(bb).ok_or("missing blackboard")?;
+        blackboard.set(&counter, Box::new(10.0f64))?;
+        blackboard.set(&toggle, Box::new(false))?;
+        blackboard.set(&scratch, Box::new(10.0f64))?;
+        let loaded = BlackboardSnapshot::load(&path)?;
+        std::fs::remove_dir_all(&directory)?;
+        assert_eq!(loaded, snapshot);
+        assert_eq!(loaded.restore(&tree_support, &mut tree, &[])?, vec![bb]);
+
+        let blackboard = tree.blackboard_ref(bb).ok_or("missing blackboard")?;
+        let blackboard = blackboard.borrow();
+        let value = blackboard.get(&counter).ok_or("missing counter")?;
+        assert!(value.is_equal(&3.0f64));
+        let value = blackboard.get(&toggle).ok_or("missing toggle")?;
+        assert!(value.is_equal(&true));
+        let value = blackboard.get(&scratch).ok_or("missing scratch")?;
+        assert!(value.is_equal(&10.0f64));
+        Ok(())
+    }
+}
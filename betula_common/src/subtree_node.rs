@@ -0,0 +1,632 @@
+use betula_core::basic::BasicTree;
+use betula_core::blackboard::{
+    PortDirection, PortName, PortType, Read, Value, ValueCreator, Write,
+};
+use betula_core::error::{ErrorKind, ExecutionError};
+use betula_core::node_prelude::*;
+use betula_core::{BetulaError, BlackboardId};
+use serde::{Deserialize, Serialize};
+
+use crate::tree_support::TreeConfig;
+use crate::type_support::{DefaultConfigConverter, NodeFactory};
+use crate::TreeSupport;
+
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Function to create the tree support that is used to load the subtree.
+pub type SubTreeSupportCreator = Rc<dyn Fn() -> TreeSupport>;
+
+/// A port of a blackboard in the subtree that is exposed on the subtree node.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SubTreePort {
+    /// The name of the blackboard in the subtree, or its id if it has no name.
+    pub blackboard: String,
+    /// The port on that blackboard, the node's port has the same name.
+    pub name: PortName,
+    /// Input ports are written into the subtree, output ports are read from it.
+    pub direction: PortDirection,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SubTreeNodeConfig {
+    /// The tree file to load, relative to the project directory.
+    pub file: Option<String>,
+    /// The blackboard ports from the subtree that become ports of this node.
+    #[serde(default)]
+    pub ports: Vec<SubTreePort>,
+}
+impl IsNodeConfig for SubTreeNodeConfig {}
+
+/// The support to load subtrees, including the files that are currently being loaded.
+#[derive(Clone)]
+struct SubTreeSupport {
+    creator: SubTreeSupportCreator,
+    parents: Vec<PathBuf>,
+}
+
+struct LoadedSubTree {
+    tree: BasicTree,
+    /// The blackboard holding each of the configured ports.
+    blackboards: Vec<BlackboardId>,
+}
+
+impl LoadedSubTree {
+    /// Find the blackboard holding each of the ports.
+    fn resolve(&mut self, ports: &[SubTreePort]) -> Result<(), NodeError> {
+        let mut blackboards = vec![];
+        for port in ports.iter() {
+            let blackboard = self
+                .tree
+                .blackboards()
+                .into_iter()
+                .find(|id| {
+                    self.tree.blackboard_name(*id).ok().flatten().as_ref() == Some(&port.blackboard)
+                        || id.0.to_string() == port.blackboard
+                })
+                .ok_or_else(|| format!("subtree has no blackboard {:?}", port.blackboard))?;
+            blackboards.push(blackboard);
+        }
+        self.blackboards = blackboards;
+        Ok(())
+    }
+
+    fn get(&self, port: &SubTreePort, blackboard: BlackboardId) -> Result<Value, NodeError> {
+        let bb = self
+            .tree
+            .blackboard_ref(blackboard)
+            .ok_or_else(|| format!("could not find blackboard {blackboard:?}"))?;
+        let value = bb.try_borrow()?.get(&port.name).ok_or_else(|| {
+            format!(
+                "port {:?} not found on subtree blackboard {:?}",
+                port.name, port.blackboard
+            )
+        })?;
+        Ok(value)
+    }
+
+    fn set(
+        &self,
+        port: &SubTreePort,
+        blackboard: BlackboardId,
+        value: Value,
+    ) -> Result<(), NodeError> {
+        let bb = self
+            .tree
+            .blackboard_ref(blackboard)
+            .ok_or_else(|| format!("could not find blackboard {blackboard:?}"))?;
+        bb.try_borrow_mut()?.set(&port.name, value)
+    }
+}
+
+/// Node that runs another tree file as if it were a single node.
+///
+/// The tree file is loaded from the project directory, this can be either a plain tree config
+/// or a file saved by the editor. The roots of the subtree are executed in order, like a
+/// sequence; the first root that does not return [`ExecutionStatus::Success`] determines the
+/// status. If all roots succeed, [`ExecutionStatus::Success`] is returned.
+///
+/// Ports of the blackboards in the subtree can be exposed as ports of this node. Values of input
+/// ports are written into the subtree before it is executed, values of output ports are read from
+/// the subtree afterwards. The port types are those of the values on the subtree's blackboards.
+#[derive(Default)]
+pub struct SubTreeNode {
+    pub config: SubTreeNodeConfig,
+    support: Option<SubTreeSupport>,
+    directory: Option<PathBuf>,
+    /// The file of the last load, also if that failed.
+    loaded_file: Option<String>,
+    subtree: Option<LoadedSubTree>,
+    load_error: Option<String>,
+    inputs: Vec<(usize, Read)>,
+    outputs: Vec<(usize, Write)>,
+}
+
+impl std::fmt::Debug for SubTreeNode {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(fmt, "SubTreeNode({:?})", self.config.file)
+    }
+}
+
+/// Load a tree config from a file, this may be a file saved by the editor or a file in the
+/// [text format](crate::text_format).
+pub fn load_tree_config_file(path: &Path) -> Result<TreeConfig, BetulaError> {
+    let content = std::fs::read(path)?;
+    if path
+        .extension()
+        .is_some_and(|e| e == crate::text_format::TEXT_EXTENSION)
+    {
+        return crate::text_format::from_text(&String::from_utf8(content)?);
+    }
+    let mut value: serde_json::Value = serde_json::from_slice(&content)?;
+    // The editor stores the tree config in the tree field, next to its own state.
+    if let Some(tree) = value.get_mut("tree") {
+        value = tree.take();
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+impl SubTreeNode {
+    /// Create a subtree node that uses tree support from the creator to load the tree.
+    pub fn with_support(creator: SubTreeSupportCreator) -> Self {
+        Self::with_parents(creator, vec![])
+    }
+
+    fn with_parents(creator: SubTreeSupportCreator, parents: Vec<PathBuf>) -> Self {
+        SubTreeNode {
+            support: Some(SubTreeSupport { creator, parents }),
+            ..Default::default()
+        }
+    }
+
+    /// Whether the subtree is currently loaded.
+    pub fn is_loaded(&self) -> bool {
+        self.loaded().is_some()
+    }
+
+    /// The error of the last attempt to load the subtree, if it failed.
+    pub fn load_error(&self) -> Option<&str> {
+        self.load_error.as_deref()
+    }
+
+    /// (Re)load the subtree from the file in the directory.
+    ///
+    /// The ports are only connected when the tree sets up this node's ports.
+    pub fn load(&mut self) -> Result<(), NodeError> {
+        self.subtree = None;
+        self.inputs.clear();
+        self.outputs.clear();
+        self.loaded_file = self.config.file.clone();
+        let r = self.load_subtree().and_then(|mut subtree| {
+            let r = subtree.resolve(&self.config.ports);
+            // The tree is kept such that changing the ports doesn't need to load it again.
+            self.subtree = Some(subtree);
+            r
+        });
+        self.set_load_result(r)
+    }
+
+    /// Apply a changed config, the file is only loaded again if it changed.
+    pub fn update(&mut self) -> Result<(), NodeError> {
+        if self.directory.is_none() {
+            return Ok(());
+        }
+        if self.loaded_file != self.config.file {
+            return self.load();
+        }
+        let Some(subtree) = self.subtree.as_mut() else {
+            // Loading this file failed and nothing changed that would make it succeed.
+            let e = self.load_error.as_deref().unwrap_or("subtree not loaded");
+            return Err(e.into());
+        };
+        self.inputs.clear();
+        self.outputs.clear();
+        let r = subtree.resolve(&self.config.ports);
+        self.set_load_result(r)
+    }
+
+    fn set_load_result(&mut self, r: Result<(), NodeError>) -> Result<(), NodeError> {
+        self.load_error = r.as_ref().err().map(|e| format!("{e}"));
+        r
+    }
+
+    /// The loaded subtree, if its ports could be resolved.
+    fn loaded(&self) -> Option<&LoadedSubTree> {
+        self.subtree.as_ref().filter(|_| self.load_error.is_none())
+    }
+
+    fn load_subtree(&self) -> Result<LoadedSubTree, NodeError> {
+        let support = self
+            .support
+            .as_ref()
+            .ok_or("subtree node has no tree support")?;
+        let directory = self
+            .directory
+            .as_ref()
+            .ok_or("subtree node has no directory")?;
+        let file = self
+            .config
+            .file
+            .as_ref()
+            .ok_or("subtree node has no file")?;
+        let path = directory.join(file).canonicalize()?;
+        if support.parents.contains(&path) {
+            return Err(format!("subtree {path:?} includes itself").into());
+        }
+        let config = load_tree_config_file(&path)?;
+
+        // Nested subtrees get the same support, but know about this file.
+        let mut tree_support = (support.creator)();
+        let mut parents = support.parents.clone();
+        parents.push(path);
+        add_subtree_support(&mut tree_support, support.creator.clone(), parents);
+
+        let mut tree = BasicTree::new();
+        tree.set_directory(Some(directory));
+        tree_support.import_tree_config(&mut tree, &config)?;
+        Ok(LoadedSubTree {
+            tree,
+            blackboards: vec![],
+        })
+    }
+
+    fn port_value(&self, index: usize) -> Result<Value, NodeError> {
+        let subtree = self.loaded().ok_or("subtree not loaded")?;
+        subtree.get(&self.config.ports[index], subtree.blackboards[index])
+    }
+}
+
+impl Node for SubTreeNode {
+    fn execute(&mut self, ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+        if let Some(subtree) = self.subtree.as_mut() {
+            // The subtree's nodes use the clock of the tree this node runs in.
+            subtree.tree.set_clock(ctx.clock().clone_boxed());
+        }
+        let subtree = self.loaded().ok_or_else(|| {
+            format!(
+                "subtree not loaded: {}",
+                self.load_error.as_deref().unwrap_or("unknown reason")
+            )
+        })?;
+
+        for (index, reader) in self.inputs.iter() {
+            subtree.set(
+                &self.config.ports[*index],
+                subtree.blackboards[*index],
+                reader()?,
+            )?;
+        }
+
+        let mut status = ExecutionStatus::Success;
+        for root in subtree.tree.roots() {
+            status = subtree.tree.execute(root).map_err(|e| {
+                // The subtree's nodes are not in this tree, so the error originates from this node.
+                let kind = ExecutionError::find(&e).map_or(ErrorKind::External, |e| e.kind());
+                ExecutionError::new(kind, e)
+            })?;
+            if status != ExecutionStatus::Success {
+                break;
+            }
+        }
+
+        for (index, writer) in self.outputs.iter() {
+            writer(subtree.get(&self.config.ports[*index], subtree.blackboards[*index])?)?;
+        }
+        Ok(status)
+    }
+
+    fn ports(&self) -> Result<Vec<Port>, NodeError> {
+        if self.loaded().is_none() {
+            return Ok(vec![]);
+        }
+        let mut ports = vec![];
+        for (index, port) in self.config.ports.iter().enumerate() {
+            let value = self.port_value(index)?;
+            ports.push(Port::new(
+                PortType::of_value(&*value),
+                port.direction,
+                port.name.clone(),
+            ));
+        }
+        Ok(ports)
+    }
+
+    fn setup_inputs(
+        &mut self,
+        interface: &mut dyn BlackboardInputInterface,
+    ) -> Result<(), NodeError> {
+        self.inputs.clear();
+        if self.loaded().is_none() {
+            return Ok(());
+        }
+        for (index, port) in self.config.ports.iter().enumerate() {
+            if port.direction != PortDirection::Input {
+                continue;
+            }
+            let value = self.port_value(index)?;
+            let reader = interface.reader(&(*value).as_any_type_id(), &port.name)?;
+            self.inputs.push((index, reader));
+        }
+        Ok(())
+    }
+
+    fn setup_outputs(
+        &mut self,
+        interface: &mut dyn BlackboardOutputInterface,
+    ) -> Result<(), NodeError> {
+        self.outputs.clear();
+        if self.loaded().is_none() {
+            return Ok(());
+        }
+        for (index, port) in self.config.ports.iter().enumerate() {
+            if port.direction != PortDirection::Output {
+                continue;
+            }
+            let value = self.port_value(index)?;
+            let type_id = (*value).as_any_type_id();
+            let default: ValueCreator = Box::new(move || value.clone());
+            let writer = interface.writer(type_id, &port.name, &default)?;
+            self.outputs.push((index, writer));
+        }
+        Ok(())
+    }
+
+    fn get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
+        Ok(Some(Box::new(self.config.clone())))
+    }
+
+    fn set_config(&mut self, config: &dyn NodeConfig) -> Result<(), NodeError> {
+        self.config.load_node_config(config)?;
+        self.update()
+    }
+
+    fn set_directory(&mut self, directory: Option<&std::path::Path>) {
+        self.directory = directory.map(|v| v.to_owned());
+        let _ = self.load();
+    }
+
+    fn reset(&mut self) {
+        if let Some(subtree) = self.subtree.as_mut() {
+            subtree.tree.reset_nodes();
+        }
+    }
+
+    fn halt(&mut self, ctx: &dyn HaltContext) -> Result<(), NodeError> {
+        if let Some(subtree) = self.subtree.as_ref() {
+            for root in subtree.tree.roots() {
+                subtree.tree.halt_recursive(root)?;
+            }
+        }
+        ctx.halt_children()
+    }
+
+    fn static_type() -> NodeType {
+        "subtree".into()
+    }
+
+    fn node_type(&self) -> NodeType {
+        Self::static_type()
+    }
+
+    fn always_execute(&self) -> bool {
+        true
+    }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..0
+    }
+}
+
+/// Factory that hands the tree support creator to new subtree nodes.
+struct SubTreeNodeFactory {
+    support: SubTreeSupport,
+}
+
+impl std::fmt::Debug for SubTreeNodeFactory {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(fmt, "SubTreeNodeFactory({:?})", self.support.parents)
+    }
+}
+
+impl NodeFactory for SubTreeNodeFactory {
+    fn create(&self) -> Result<Box<dyn Node>, BetulaError> {
+        Ok(Box::new(SubTreeNode::with_parents(
+            self.support.creator.clone(),
+            self.support.parents.clone(),
+        )))
+    }
+}
+
+/// Register the [`SubTreeNode`], nested subtrees know which files are being loaded by `parents`.
+pub(crate) fn add_subtree_support(
+    tree_support: &mut TreeSupport,
+    creator: SubTreeSupportCreator,
+    parents: Vec<PathBuf>,
+) {
+    tree_support.add_node_factory(
+        SubTreeNode::static_type(),
+        Box::new(SubTreeNodeFactory {
+            support: SubTreeSupport { creator, parents },
+        }),
+    );
+    tree_support
+        .add_config_converter(
+            &SubTreeNode::static_type(),
+            Box::new(DefaultConfigConverter::<SubTreeNodeConfig>::new()),
+        )
+        .expect("cannot fail, key was added line above");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tree_support::test_tree_support;
+    use betula_core::basic::BasicBlackboard;
+    use betula_core::NodeId;
+    use betula_std::nodes::{IfTimeExceedsNode, StatusWriteNode};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_subtree() -> Result<(), BetulaError> {
+        // Create the tree that is to be used as subtree; status(t1 >= t2).
+        let tree_support = test_tree_support();
+        let mut inner = BasicTree::new();
+        let status =
+            inner.add_node_boxed(NodeId(Uuid::new_v4()), Box::new(StatusWriteNode::new()))?;
+        let exceeds = inner.add_node_boxed(
+            NodeId(Uuid::new_v4()),
+            Box::new(IfTimeExceedsNode::default()),
+        )?;
+        inner.set_children(status, &[exceeds])?;
+        inner.set_roots(&[status])?;
+        let bb = inner.add_blackboard_boxed(
+            BlackboardId(Uuid::new_v4()),
+            Box::new(BasicBlackboard::default()),
+        )?;
+        inner.set_blackboard_name(bb, Some("inner"))?;
+        {
+            let inner_bb = inner.blackboard_mut(bb).ok_or("missing blackboard")?;
+            inner_bb.set(&"t1".into(), Box::new(0.0f64))?;
+            inner_bb.set(&"t2".into(), Box::new(5.0f64))?;
+        }
+        for port in inner.node_ports(exceeds)? {
+            inner.connect_port_to_blackboard(&port, bb)?;
+        }
+        inner.connect_port_to_blackboard(&inner.node_ports(status)?[0], bb)?;
+
+        let directory = std::env::temp_dir().join(format!("betula_subtree_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&directory)?;
+        let config = tree_support.export_tree_config(&inner)?;
+        std::fs::write(
+            directory.join("inner.json"),
+            serde_json::to_string(&config)?,
+        )?;
+
+        // Now create the outer tree with the subtree node.
+        let mut tree_support = test_tree_support();
+        tree_support.add_node_subtree(Rc::new(test_tree_support));
+        let mut node = tree_support.create_node(&SubTreeNode::static_type())?;
+        node.set_config(&SubTreeNodeConfig {
+            file: Some("inner.json".to_owned()),
+            ports: vec![
+                SubTreePort {
+                    blackboard: "inner".to_owned(),
+                    name: "t1".into(),
+                    direction: PortDirection::Input,
+                },
+                SubTreePort {
+                    blackboard: "inner".to_owned(),
+                    name: "status".into(),
+                    direction: PortDirection::Output,
+                },
+            ],
+        })?;
+
+        let mut tree = BasicTree::new();
+        tree.set_directory(Some(&directory));
+        let subtree = tree.add_node_boxed(NodeId(Uuid::new_v4()), node)?;
+        let ports = tree.node_ports(subtree)?;
+        assert_eq!(ports.len(), 2);
+
+        let outer = tree.add_blackboard_boxed(
+            BlackboardId(Uuid::new_v4()),
+            Box::new(BasicBlackboard::default()),
+        )?;
+        tree.blackboard_mut(outer)
+            .ok_or("missing blackboard")?
+            .set(&"t1".into(), Box::new(1.0f64))?;
+        for port in ports {
+            tree.connect_port_to_blackboard(&port, outer)?;
+        }
+
+        let read_status = |tree: &BasicTree| -> Result<ExecutionStatus, BetulaError> {
+            let value = tree
+                .blackboard_ref(outer)
+                .ok_or("missing blackboard")?
+                .borrow()
+                .get(&"status".into())
+                .ok_or("missing status")?;
+            use betula_core::as_any::AsAnyHelper;
+            Ok(*(*value)
+                .downcast_ref::<ExecutionStatus>()
+                .ok_or("wrong type")?)
+        };
+
+        assert_eq!(tree.execute(subtree)?, ExecutionStatus::Failure);
+        assert_eq!(read_status(&tree)?, ExecutionStatus::Failure);
+
+        tree.blackboard_mut(outer)
+            .ok_or("missing blackboard")?
+            .set(&"t1".into(), Box::new(10.0f64))?;
+        assert_eq!(tree.execute(subtree)?, ExecutionStatus::Success);
+        assert_eq!(read_status(&tree)?, ExecutionStatus::Success);
+
+        // Changing the ports doesn't load the file again, changing the file does.
+        std::fs::remove_file(directory.join("inner.json"))?;
+        let node = tree.node_mut(subtree).ok_or("missing node")?;
+        let mut config = SubTreeNodeConfig {
+            file: Some("inner.json".to_owned()),
+            ports: vec![SubTreePort {
+                blackboard: "inner".to_owned(),
+                name: "t2".into(),
+                direction: PortDirection::Input,
+            }],
+        };
+        node.set_config(&config)?;
+        assert_eq!(node.ports()?.len(), 1);
+        config.file = Some("missing.json".to_owned());
+        assert!(node.set_config(&config).is_err());
+        assert_eq!(node.ports()?.len(), 0);
+
+        std::fs::remove_dir_all(&directory)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_subtree_clock() -> Result<(), BetulaError> {
+        use betula_core::clock::ManualClock;
+        use betula_std::nodes::TimeNode;
+        // The subtree writes the time of its clock.
+        let tree_support = test_tree_support();
+        let mut inner = BasicTree::new();
+        let time = inner.add_node_boxed(NodeId(Uuid::new_v4()), Box::new(TimeNode::new()))?;
+        inner.set_roots(&[time])?;
+        let bb = inner.add_blackboard_boxed(
+            BlackboardId(Uuid::new_v4()),
+            Box::new(BasicBlackboard::default()),
+        )?;
+        inner.set_blackboard_name(bb, Some("inner"))?;
+        inner.connect_port_to_blackboard(&inner.node_ports(time)?[0], bb)?;
+
+        let directory = std::env::temp_dir().join(format!("betula_subtree_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&directory)?;
+        let config = tree_support.export_tree_config(&inner)?;
+        std::fs::write(
+            directory.join("inner.json"),
+            serde_json::to_string(&config)?,
+        )?;
+
+        let mut tree_support = test_tree_support();
+        tree_support.add_node_subtree(Rc::new(test_tree_support));
+        let mut node = tree_support.create_node(&SubTreeNode::static_type())?;
+        node.set_config(&SubTreeNodeConfig {
+            file: Some("inner.json".to_owned()),
+            ports: vec![SubTreePort {
+                blackboard: "inner".to_owned(),
+                name: "time".into(),
+                direction: PortDirection::Output,
+            }],
+        })?;
+
+        let clock = ManualClock::new(10.0);
+        let mut tree = BasicTree::new();
+        tree.set_clock(Box::new(clock.clone()));
+        tree.set_directory(Some(&directory));
+        let subtree = tree.add_node_boxed(NodeId(Uuid::new_v4()), node)?;
+        let outer = tree.add_blackboard_boxed(
+            BlackboardId(Uuid::new_v4()),
+            Box::new(BasicBlackboard::default()),
+        )?;
+        tree.connect_port_to_blackboard(&tree.node_ports(subtree)?[0], outer)?;
+
+        let read_time = |tree: &BasicTree| -> Result<f64, BetulaError> {
+            let value = tree
+                .blackboard_ref(outer)
+                .ok_or("missing blackboard")?
+                .borrow()
+                .get(&"time".into())
+                .ok_or("missing time")?;
+            use betula_core::as_any::AsAnyHelper;
+            Ok(*(*value).downcast_ref::<f64>().ok_or("wrong type")?)
+        };
+
+        assert_eq!(tree.execute(subtree)?, ExecutionStatus::Success);
+        assert_eq!(read_time(&tree)?, 10.0);
+        clock.advance(2.5);
+        assert_eq!(tree.execute(subtree)?, ExecutionStatus::Success);
+        assert_eq!(read_time(&tree)?, 12.5);
+
+        std::fs::remove_dir_all(&directory)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,576 @@
+//! Human-writable text format for trees.
+//!
+//! The text format holds the same information as a [`TreeConfig`], except for the node and
+//! blackboard ids, which are generated when the text is read. Nodes are nested by indentation,
+//! blackboards are referred to by their name:
+//! ```text
+//! # Comments start with a hash.
+//! blackboard globals
+//!   persistent counter
+//!   value counter f64 = 3.0
+//! blackboard @0
+//!   parent globals
+//! root sequence "main sequence"
+//!   node time
+//!     output time -> globals
+//!   node delay = {"interval":1.0}
+//!     input time <- globals now
+//! node success
+//! node move =2 {"speed":3.0}
+//! ```
+//! - `blackboard <ref>` declares a blackboard, the reference is its name. Blackboards without a
+//!   name, or whose name is not unique, use a reference starting with `@` and hold the name in a
+//!   `name` line. Below it are the `parent`, `persistent` ports and `value` lines, values hold the
+//!   type and the serialized value.
+//! - `root <type> [name] [= config]` declares a root node, `node` declares a node that is not a
+//!   root. The children of a node are the `node` lines below it, in order. Configs with a
+//!   version other than 0 write the version after the equals sign, like `=2 config`.
+//! - `input <port> <- <ref> [port]` and `output <port> -> <ref> [port]` below a node connect its
+//!   ports to a blackboard, the blackboard port defaults to the name of the node's port.
+//!
+//! Words that contain whitespace or could be mistaken for syntax are written as json strings,
+//! configs and values are json.
+use betula_core::blackboard::{BlackboardPort, NodePort, PortConnection, PortDirection, PortName};
+use betula_core::{BetulaError, BlackboardId, NodeId};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::tree_support::{v2, SerializedValue, TreeConfig};
+
+/// File extension of trees in the text format.
+pub const TEXT_EXTENSION: &str = "betula";
+
+/// Write a word, using a json string if it would not be read back as the same word.
+fn word(s: &str) -> String {
+    let plain = !s.is_empty()
+        && !s.contains(|c: char| c.is_whitespace() || c == '"')
+        && !s.starts_with(['@', '#', '='])
+        && !["<-", "->"].contains(&s);
+    if plain {
+        s.to_owned()
+    } else {
+        serde_json::to_string(s).expect("strings always serialize")
+    }
+}
+
+/// Convert a tree config to the text format.
+pub fn to_text(config: &TreeConfig) -> Result<String, BetulaError> {
+    let root = config.clone().into_latest();
+
+    // Named blackboards first, both sorted to keep the output stable.
+    let mut blackboards: Vec<&v2::Blackboard> = root.blackboards.iter().collect();
+    blackboards.sort_by(|a, b| (a.name.is_none(), &a.name).cmp(&(b.name.is_none(), &b.name)));
+    let mut name_count: HashMap<&str, usize> = HashMap::new();
+    for name in blackboards.iter().filter_map(|b| b.name.as_deref()) {
+        *name_count.entry(name).or_default() += 1;
+    }
+    let mut references: HashMap<BlackboardId, String> = HashMap::new();
+    let mut anonymous = 0;
+    for bb in blackboards.iter() {
+        let reference = match bb.name.as_deref() {
+            Some(name) if name_count[name] == 1 => word(name),
+            _ => {
+                anonymous += 1;
+                format!("@{}", anonymous - 1)
+            }
+        };
+        references.insert(bb.id, reference);
+    }
+    let reference = |id: &BlackboardId| {
+        references
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("could not find blackboard {id:?}"))
+    };
+
+    let mut out = String::new();
+    let mut connections: HashMap<NodeId, Vec<&PortConnection>> = HashMap::new();
+    for bb in blackboards.iter() {
+        let bb_ref = reference(&bb.id)?;
+        out += &format!("blackboard {bb_ref}\n");
+        if let Some(name) = bb.name.as_deref().filter(|_| bb_ref.starts_with('@')) {
+            out += &format!("  name {}\n", word(name));
+        }
+        if let Some(parent) = &bb.parent {
+            out += &format!("  parent {}\n", reference(parent)?);
+        }
+        if !bb.persistent.is_empty() {
+            let ports: Vec<String> = bb.persistent.iter().map(|p| word(p)).collect();
+            out += &format!("  persistent {}\n", ports.join(" "));
+        }
+        for (port, value) in bb.values.iter() {
+            out += &format!(
+                "  value {} {} = {}\n",
+                word(port),
+                word(&value.type_id),
+                serde_json::to_string(&value.data)?
+            );
+        }
+        for connection in bb.connections.iter() {
+            connections
+                .entry(connection.node.node())
+                .or_default()
+                .push(connection);
+        }
+    }
+
+    let nodes: HashMap<NodeId, &v2::TreeNode> = root.nodes.iter().map(|n| (n.id, n)).collect();
+    let children: HashSet<NodeId> = root.nodes.iter().flat_map(|n| n.children.clone()).collect();
+    let mut written = HashSet::new();
+    let mut write_node = |out: &mut String, id: NodeId, keyword: &str| {
+        let mut stack = vec![(id, 0, keyword)];
+        while let Some((id, depth, keyword)) = stack.pop() {
+            if !written.insert(id) {
+                return Err(format!("node {id:?} occurs more than once"));
+            }
+            let node = nodes
+                .get(&id)
+                .ok_or(format!("could not find node {id:?}"))?;
+            let indent = "  ".repeat(depth);
+            out.push_str(&format!("{indent}{keyword} {}", word(&node.node_type)));
+            if let Some(name) = &node.name {
+                out.push_str(&format!(" {}", word(name)));
+            }
+            if let Some(config) = &node.config {
+                let config = serde_json::to_string(config).map_err(|e| e.to_string())?;
+                match node.config_version {
+                    0 => out.push_str(&format!(" = {config}")),
+                    version => out.push_str(&format!(" ={version} {config}")),
+                }
+            }
+            out.push('\n');
+            for connection in connections.get(&id).into_iter().flatten() {
+                let (direction, arrow) = match connection.node.direction() {
+                    PortDirection::Input => ("input", "<-"),
+                    PortDirection::Output => ("output", "->"),
+                };
+                let port = connection.node.name();
+                let bb_port = connection.blackboard.name();
+                let bb_ref = references
+                    .get(&connection.blackboard_id())
+                    .ok_or(format!("could not find blackboard of {connection:?}"))?;
+                out.push_str(&format!(
+                    "{indent}  {direction} {} {arrow} {bb_ref}",
+                    word(&port)
+                ));
+                if bb_port != port {
+                    out.push_str(&format!(" {}", word(&bb_port)));
+                }
+                out.push('\n');
+            }
+            // Reversed, such that the first child is written first.
+            for child in node.children.iter().rev() {
+                stack.push((*child, depth + 1, "node"));
+            }
+        }
+        Ok(())
+    };
+    for id in root.tree_roots.iter() {
+        write_node(&mut out, *id, "root")?;
+    }
+    let mut detached: Vec<&v2::TreeNode> = root
+        .nodes
+        .iter()
+        .filter(|n| !root.tree_roots.contains(&n.id) && !children.contains(&n.id))
+        .collect();
+    detached.sort_by(|a, b| (&a.node_type, &a.name).cmp(&(&b.node_type, &b.name)));
+    for node in detached {
+        write_node(&mut out, node.id, "node")?;
+    }
+    if written.len() != root.nodes.len() {
+        return Err("nodes that are not reachable from a root or a detached node".into());
+    }
+    Ok(out)
+}
+
+/// A word on a line, quoted words are never syntax.
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    quoted: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    number: usize,
+    indent: usize,
+    tokens: Vec<Token>,
+    json: Option<serde_json::Value>,
+    /// Version written after the `=`, 0 if absent.
+    version: u32,
+}
+
+impl Line {
+    fn error(&self, msg: impl std::fmt::Display) -> BetulaError {
+        format!("line {}: {msg}", self.number).into()
+    }
+
+    fn token(&self, index: usize, what: &str) -> Result<&Token, BetulaError> {
+        self.tokens
+            .get(index)
+            .ok_or_else(|| self.error(format!("expected {what}")))
+    }
+
+    fn keyword(&self, index: usize, expected: &str) -> Result<(), BetulaError> {
+        match self.tokens.get(index) {
+            Some(t) if !t.quoted && t.text == expected => Ok(()),
+            _ => Err(self.error(format!("expected {expected:?}"))),
+        }
+    }
+
+    fn max_tokens(&self, count: usize) -> Result<(), BetulaError> {
+        if self.tokens.len() > count {
+            Err(self.error(format!("unexpected {:?}", self.tokens[count].text)))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Split a line into words, everything after a `=` is json.
+fn tokenize(line: &str, number: usize) -> Result<Line, BetulaError> {
+    if line.contains('\t') {
+        return Err(format!("line {number}: tabs are not supported").into());
+    }
+    let mut rest = line.trim_start();
+    let indent = line.len() - rest.len();
+    let mut tokens = vec![];
+    let mut json = None;
+    let mut version = 0;
+    while !rest.is_empty() {
+        if rest.starts_with('"') {
+            let mut stream = serde_json::Deserializer::from_str(rest).into_iter::<String>();
+            let text = stream
+                .next()
+                .ok_or("missing string")?
+                .map_err(|e| format!("line {number}: {e}"))?;
+            rest = &rest[stream.byte_offset()..];
+            tokens.push(Token { text, quoted: true });
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let text = &rest[..end];
+            rest = &rest[end..];
+            if let Some(v) = text.strip_prefix('=') {
+                if !v.is_empty() {
+                    version = v
+                        .parse()
+                        .map_err(|_| format!("line {number}: invalid version {v:?}"))?;
+                }
+                json = Some(serde_json::from_str(rest).map_err(|e| format!("line {number}: {e}"))?);
+                break;
+            }
+            tokens.push(Token {
+                text: text.to_owned(),
+                quoted: false,
+            });
+        }
+        rest = rest.trim_start();
+    }
+    Ok(Line {
+        number,
+        indent,
+        tokens,
+        json,
+        version,
+    })
+}
+
+#[derive(Debug)]
+struct Item {
+    line: Line,
+    children: Vec<Item>,
+}
+
+/// Group lines that are indented deeper than the line before them as its children.
+fn parse_block(lines: &[Line], pos: &mut usize, indent: usize) -> Result<Vec<Item>, BetulaError> {
+    let mut items = vec![];
+    while let Some(line) = lines.get(*pos) {
+        if line.indent < indent {
+            break;
+        }
+        if line.indent > indent {
+            return Err(line.error("unexpected indentation"));
+        }
+        *pos += 1;
+        let children = match lines.get(*pos) {
+            Some(next) if next.indent > indent => parse_block(lines, pos, next.indent)?,
+            _ => vec![],
+        };
+        items.push(Item {
+            line: line.clone(),
+            children,
+        });
+    }
+    Ok(items)
+}
+
+/// Blackboards are named by a quoted or plain word, anonymous ones by an unquoted `@` word.
+fn reference(token: &Token) -> (bool, String) {
+    (
+        !token.quoted && token.text.starts_with('@'),
+        token.text.clone(),
+    )
+}
+
+struct Reader {
+    blackboards: HashMap<(bool, String), BlackboardId>,
+    nodes: Vec<v2::TreeNode>,
+    connections: BTreeMap<BlackboardId, Vec<PortConnection>>,
+}
+
+impl Reader {
+    fn blackboard(&self, line: &Line, index: usize) -> Result<BlackboardId, BetulaError> {
+        let token = line.token(index, "blackboard")?;
+        self.blackboards
+            .get(&reference(token))
+            .copied()
+            .ok_or_else(|| line.error(format!("unknown blackboard {:?}", token.text)))
+    }
+
+    fn node(&mut self, item: &Item) -> Result<NodeId, BetulaError> {
+        let line = &item.line;
+        let id = NodeId(Uuid::new_v4());
+        let node_type = line.token(1, "node type")?.text.clone();
+        let name = line.tokens.get(2).map(|t| t.text.clone());
+        line.max_tokens(3)?;
+        let mut children = vec![];
+        for child in item.children.iter() {
+            let child_line = &child.line;
+            let keyword = child_line.token(0, "keyword")?;
+            let direction = match keyword.text.as_str() {
+                "node" if !keyword.quoted => {
+                    children.push(self.node(child)?);
+                    continue;
+                }
+                "input" if !keyword.quoted => {
+                    child_line.keyword(2, "<-")?;
+                    PortDirection::Input
+                }
+                "output" if !keyword.quoted => {
+                    child_line.keyword(2, "->")?;
+                    PortDirection::Output
+                }
+                _ => return Err(child_line.error("expected node, input or output")),
+            };
+            child_line.max_tokens(5)?;
+            if !child.children.is_empty() {
+                return Err(child_line.error("ports can't have children"));
+            }
+            let port: PortName = child_line.token(1, "port")?.text.as_str().into();
+            let blackboard = self.blackboard(child_line, 3)?;
+            let bb_port = child_line
+                .tokens
+                .get(4)
+                .map(|t| t.text.as_str().into())
+                .unwrap_or(port.clone());
+            self.connections
+                .entry(blackboard)
+                .or_default()
+                .push(PortConnection::new(
+                    NodePort::new(id, &port, direction),
+                    BlackboardPort::new(blackboard, &bb_port),
+                ));
+        }
+        self.nodes.push(v2::TreeNode {
+            id,
+            node_type,
+            config: line.json.clone(),
+            config_version: line.version,
+            children,
+            name,
+        });
+        Ok(id)
+    }
+}
+
+/// Read a tree config from the text format, with new ids for all nodes and blackboards.
+pub fn from_text(text: &str) -> Result<TreeConfig, BetulaError> {
+    let mut lines = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        lines.push(tokenize(line, index + 1)?);
+    }
+    let mut pos = 0;
+    let items = parse_block(&lines, &mut pos, 0)?;
+
+    // Blackboards first, nodes may refer to blackboards declared after them.
+    let mut reader = Reader {
+        blackboards: HashMap::new(),
+        nodes: vec![],
+        connections: BTreeMap::new(),
+    };
+    let mut blackboards = vec![];
+    for item in items.iter() {
+        let line = &item.line;
+        if line.keyword(0, "blackboard").is_err() {
+            continue;
+        }
+        line.max_tokens(2)?;
+        let token = line.token(1, "blackboard reference")?;
+        let (anonymous, text) = reference(token);
+        let id = BlackboardId(Uuid::new_v4());
+        if reader
+            .blackboards
+            .insert((anonymous, text.clone()), id)
+            .is_some()
+        {
+            return Err(line.error(format!("duplicate blackboard {text:?}")));
+        }
+        let name = if anonymous { None } else { Some(text) };
+        blackboards.push((
+            item,
+            v2::Blackboard {
+                id,
+                values: Default::default(),
+                connections: vec![],
+                name,
+                parent: None,
+                persistent: vec![],
+            },
+        ));
+    }
+    for (item, blackboard) in blackboards.iter_mut() {
+        for property in item.children.iter() {
+            let line = &property.line;
+            if !property.children.is_empty() {
+                return Err(line.error("blackboard properties can't have children"));
+            }
+            let keyword = line.token(0, "keyword")?;
+            match keyword.text.as_str() {
+                "name" if !keyword.quoted => {
+                    line.max_tokens(2)?;
+                    blackboard.name = Some(line.token(1, "name")?.text.clone());
+                }
+                "parent" if !keyword.quoted => {
+                    line.max_tokens(2)?;
+                    blackboard.parent = Some(reader.blackboard(line, 1)?);
+                }
+                "persistent" if !keyword.quoted => {
+                    let ports = line.tokens[1..].iter().map(|t| t.text.as_str().into());
+                    blackboard.persistent.extend(ports);
+                }
+                "value" if !keyword.quoted => {
+                    line.max_tokens(3)?;
+                    let port = line.token(1, "port")?.text.as_str().into();
+                    let type_id = line.token(2, "value type")?.text.clone();
+                    let data = line
+                        .json
+                        .clone()
+                        .ok_or_else(|| line.error("expected value"))?;
+                    if line.version != 0 {
+                        return Err(line.error("values have no version"));
+                    }
+                    blackboard
+                        .values
+                        .insert(port, SerializedValue { type_id, data });
+                }
+                _ => return Err(line.error("expected name, parent, persistent or value")),
+            }
+        }
+    }
+
+    let mut tree_roots = vec![];
+    for item in items.iter() {
+        let keyword = item.line.token(0, "keyword")?;
+        match keyword.text.as_str() {
+            "blackboard" if !keyword.quoted => {}
+            "root" if !keyword.quoted => tree_roots.push(reader.node(item)?),
+            "node" if !keyword.quoted => {
+                reader.node(item)?;
+            }
+            _ => return Err(item.line.error("expected blackboard, root or node")),
+        }
+    }
+
+    let blackboards = blackboards
+        .into_iter()
+        .map(|(_, mut blackboard)| {
+            blackboard.connections = reader
+                .connections
+                .remove(&blackboard.id)
+                .unwrap_or_default();
+            blackboard
+        })
+        .collect();
+    Ok(TreeConfig::V2(v2::Root {
+        nodes: reader.nodes,
+        blackboards,
+        tree_roots,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tree_support::test_tree_support;
+    use betula_core::basic::BasicTree;
+    use betula_core::Tree;
+
+    const TREE: &str = r#"blackboard @0
+  name dup
+blackboard @1
+  name dup
+blackboard "global values"
+  persistent counter
+  value counter f64 = 3.0
+  value now f64 = 0.0
+blackboard @2
+  parent "global values"
+  value time f64 = 0.0
+root sequence "main sequence" = {"memory":false,"retry":false}
+  node std_time
+    output time -> @2
+  node std_delay = {"interval":1.5}
+    input time <- @2
+  node std_time
+    output time -> "global values" now
+node success
+"#;
+
+    #[test]
+    fn test_text_roundtrip() -> Result<(), BetulaError> {
+        let tree_support = test_tree_support();
+        let mut tree = BasicTree::new();
+        tree_support.import_tree_text(&mut tree, TREE)?;
+        assert_eq!(tree.roots().len(), 1);
+        assert_eq!(tree.nodes().len(), 5);
+        assert_eq!(tree.blackboards().len(), 4);
+        assert_eq!(tree.port_connections().len(), 3);
+        assert_eq!(tree_support.export_tree_text(&tree)?, TREE);
+
+        // The json config is the same after a roundtrip through the text.
+        let config = tree_support.export_tree_config(&tree)?;
+        let mut new_tree = BasicTree::new();
+        tree_support.import_tree_text(&mut new_tree, &to_text(&config)?)?;
+        assert_eq!(tree_support.export_tree_text(&new_tree)?, TREE);
+        Ok(())
+    }
+
+    #[test]
+    fn test_text_errors() {
+        let errors = [
+            (
+                "root a\n    node b\n  node c\n",
+                "line 3: unexpected indentation",
+            ),
+            (
+                "root a\n  input x <- missing\n",
+                "line 2: unknown blackboard \"missing\"",
+            ),
+            (
+                "blackboard a\nblackboard \"a\"\n",
+                "line 2: duplicate blackboard \"a\"",
+            ),
+            ("root a\n\troot b\n", "line 2: tabs are not supported"),
+            ("sequence\n", "line 1: expected blackboard, root or node"),
+            ("node a =v1 {}\n", "line 1: invalid version \"v1\""),
+        ];
+        for (text, error) in errors {
+            let r = from_text(text);
+            assert_eq!(r.expect_err("invalid text").to_string(), error);
+        }
+    }
+}
@@ -0,0 +1,466 @@
+//! Recording of ticks and their deterministic replay.
+//!
+//! A [`TraceRecorder`] writes a trace, a file with one json [`TraceEntry`] per line. It holds the
+//! tree config whenever the tree is loaded or modified and, for every tick, the time, the status
+//! of the executed nodes and the blackboard values that changed in that tick.
+//!
+//! A [`TraceReplay`] loads the recorded tree and steps through the ticks. Leaf nodes are replaced
+//! by stubs that return their recorded status without performing any action, by default all of
+//! them, [`TraceReplay::set_stubbed`] selects the node types to stub such that for example
+//! conditions are evaluated again. Before each tick the recorded blackboard values are written
+//! back, such that the remaining nodes see the same inputs as during the recording. Ports written
+//! by stubs already hold the value they had at the end of the tick, other ports the value from the
+//! end of the previous tick. Nodes that end up with a different status than the recorded one are
+//! reported as diverged.
+use betula_core::basic::BasicTree;
+use betula_core::blackboard::{BlackboardPort, PortDirection};
+use betula_core::clock::ManualClock;
+use betula_core::error::{ErrorReport, ExecutionError};
+use betula_core::node_prelude::*;
+use betula_core::{BetulaError, NodeId, Tree};
+use serde::{Deserialize, Serialize};
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::control::{BlackboardValues, ExecutionResult};
+use crate::server_thread::{execute_tracked, set_tick};
+use crate::tree_support::{TreeConfig, TreeSupport};
+
+type Status = Result<ExecutionStatus, ErrorReport>;
+
+/// A line in the trace.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum TraceEntry {
+    /// The tree as used by the ticks that follow.
+    Tree(TreeConfig),
+    /// A single tick.
+    Tick(TickRecord),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TickRecord {
+    pub tick: u64,
+    /// The time of the tree's clock at the start of the tick.
+    pub time: f64,
+    pub result: ExecutionResult,
+    /// The blackboard values that changed since the previous tick.
+    pub values: BlackboardValues,
+}
+
+/// Writes the entries of a trace, see the [module](self) documentation.
+pub struct TraceRecorder {
+    writer: Box<dyn Write>,
+    previous: BlackboardValues,
+}
+
+impl std::fmt::Debug for TraceRecorder {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        fmt.debug_struct("TraceRecorder").finish_non_exhaustive()
+    }
+}
+
+impl TraceRecorder {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        TraceRecorder {
+            writer,
+            previous: Default::default(),
+        }
+    }
+
+    /// Create a recorder that writes to a new file at the provided path.
+    pub fn create(path: &Path) -> Result<Self, BetulaError> {
+        let file =
+            std::fs::File::create(path).map_err(|e| format!("failed to create {path:?}: {e}"))?;
+        Ok(Self::new(Box::new(std::io::BufWriter::new(file))))
+    }
+
+    fn write_entry(&mut self, entry: &TraceEntry) -> Result<(), BetulaError> {
+        serde_json::to_writer(&mut self.writer, entry)?;
+        self.writer.write_all(b"\n")?;
+        // Flush every entry, such that the trace is complete up to a crash.
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Record the tree, the next tick records all blackboard values again.
+    pub fn record_tree(&mut self, config: &TreeConfig) -> Result<(), BetulaError> {
+        self.previous = Default::default();
+        self.write_entry(&TraceEntry::Tree(config.clone()))
+    }
+
+    /// Record a tick, with the values of all blackboards at the end of the tick.
+    pub fn record_tick(
+        &mut self,
+        tick: u64,
+        time: f64,
+        result: &ExecutionResult,
+        values: &BlackboardValues,
+    ) -> Result<(), BetulaError> {
+        let mut changed = BlackboardValues::default();
+        for (blackboard_id, port_values) in values.blackboards.iter() {
+            let previous = self.previous.blackboards.get(blackboard_id);
+            let port_values: BTreeMap<_, _> = port_values
+                .iter()
+                .filter(|(port, value)| previous.and_then(|p| p.get(*port)) != Some(*value))
+                .map(|(port, value)| (port.clone(), value.clone()))
+                .collect();
+            if !port_values.is_empty() {
+                changed.blackboards.insert(*blackboard_id, port_values);
+            }
+        }
+        self.previous = values.clone();
+        self.write_entry(&TraceEntry::Tick(TickRecord {
+            tick,
+            time,
+            result: result.clone(),
+            values: changed,
+        }))
+    }
+}
+
+/// Read all entries of a trace.
+pub fn read_trace(path: &Path) -> Result<Vec<TraceEntry>, BetulaError> {
+    let file = std::fs::File::open(path).map_err(|e| format!("failed to open {path:?}: {e}"))?;
+    let mut entries = vec![];
+    for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        entries.push(serde_json::from_str(&line).map_err(|e| format!("line {}: {e}", i + 1))?);
+    }
+    Ok(entries)
+}
+
+/// Stands in for a leaf node during replay and returns its recorded status.
+#[derive(Debug)]
+struct ReplayNode {
+    id: NodeId,
+    node_type: NodeType,
+    recorded: Rc<RefCell<HashMap<NodeId, Status>>>,
+}
+
+impl Node for ReplayNode {
+    fn execute(&mut self, _ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+        match self.recorded.borrow().get(&self.id) {
+            Some(Ok(status)) => Ok(*status),
+            Some(Err(report)) => {
+                Err(ExecutionError::new(report.kind, report.message.clone()).into())
+            }
+            None => Err(format!("node {:?} was not executed in the recording", self.id).into()),
+        }
+    }
+
+    fn static_type() -> NodeType
+    where
+        Self: Sized,
+    {
+        "replay".into()
+    }
+
+    fn node_type(&self) -> NodeType {
+        self.node_type.clone()
+    }
+}
+
+/// The outcome of replaying a tick.
+#[derive(Debug, Clone)]
+pub struct ReplayStep {
+    pub tick: u64,
+    pub time: f64,
+    pub recorded: ExecutionResult,
+    pub replayed: ExecutionResult,
+    /// Nodes of which the replayed status differs from the recorded status.
+    pub diverged: Vec<NodeId>,
+}
+
+/// Steps through the ticks of a trace, see the [module](self) documentation.
+pub struct TraceReplay<'a> {
+    tree_support: &'a TreeSupport,
+    entries: std::vec::IntoIter<TraceEntry>,
+    tree: BasicTree,
+    clock: ManualClock,
+    recorded: Rc<RefCell<HashMap<NodeId, Status>>>,
+    values: BlackboardValues,
+    stubbed: Box<dyn Fn(&NodeType) -> bool>,
+    stub_outputs: HashSet<BlackboardPort>,
+}
+
+impl<'a> TraceReplay<'a> {
+    /// Create a replay, the tree support must support all types of the recorded tree, except for
+    /// leaf nodes if it has [`TreeSupport::add_node_placeholder`] enabled.
+    pub fn new(tree_support: &'a TreeSupport, entries: Vec<TraceEntry>) -> Self {
+        TraceReplay {
+            tree_support,
+            entries: entries.into_iter(),
+            tree: BasicTree::new(),
+            clock: ManualClock::default(),
+            recorded: Default::default(),
+            values: Default::default(),
+            stubbed: Box::new(|_| true),
+            stub_outputs: Default::default(),
+        }
+    }
+
+    /// Select the types of the leaf nodes that are replaced by stubs, for trees loaded by the
+    /// following steps. Placeholders are always stubbed, as they can't execute.
+    pub fn set_stubbed(&mut self, stubbed: impl Fn(&NodeType) -> bool + 'static) {
+        self.stubbed = Box::new(stubbed);
+    }
+
+    /// The tree as of the last replayed tick.
+    pub fn tree(&self) -> &dyn Tree {
+        &self.tree
+    }
+
+    /// The recorded blackboard values as of the last replayed tick.
+    pub fn values(&self) -> &BlackboardValues {
+        &self.values
+    }
+
+    /// Replay the next tick, `None` at the end of the trace.
+    pub fn step(&mut self) -> Result<Option<ReplayStep>, BetulaError> {
+        while let Some(entry) = self.entries.next() {
+            match entry {
+                TraceEntry::Tree(config) => self.load(&config)?,
+                TraceEntry::Tick(record) => return self.replay(record).map(Some),
+            }
+        }
+        Ok(None)
+    }
+
+    fn load(&mut self, config: &TreeConfig) -> Result<(), BetulaError> {
+        let mut tree = BasicTree::new();
+        tree.set_conversions(self.tree_support.conversions().clone())?;
+        self.tree_support.import_tree_config(&mut tree, config)?;
+        tree.set_clock(Box::new(self.clock.clone()));
+        let mut stubs = HashSet::new();
+        for id in tree.nodes() {
+            if !tree.children(id)?.is_empty() {
+                continue;
+            }
+            let node = tree.node_ref(id).ok_or(format!("cannot find {id:?}"))?;
+            let node_type = node.borrow().node_type();
+            if !(self.stubbed)(&node_type) && !self.tree_support.is_placeholder(&node_type) {
+                continue;
+            }
+            stubs.insert(id);
+            *node.try_borrow_mut()? = Box::new(ReplayNode {
+                id,
+                node_type,
+                recorded: Rc::clone(&self.recorded),
+            });
+        }
+        self.stub_outputs = tree
+            .blackboards()
+            .into_iter()
+            .flat_map(|id| tree.blackboard_connections(id))
+            .filter(|c| {
+                c.node.direction() == PortDirection::Output && stubs.contains(&c.node.node())
+            })
+            .map(|c| c.blackboard)
+            .collect();
+        self.tree = tree;
+        self.values = Default::default();
+        Ok(())
+    }
+
+    /// Write the recorded values into the blackboards.
+    fn restore_values(&mut self, values: &BlackboardValues) -> Result<(), BetulaError> {
+        for (blackboard_id, port_values) in values.blackboards.iter() {
+            let bb = self
+                .tree
+                .blackboard_mut(*blackboard_id)
+                .ok_or(format!("cannot find {blackboard_id:?}"))?;
+            for (port, value) in port_values.iter() {
+                let value = self.tree_support.value_deserialize(value.clone())?;
+                bb.set(port, value)
+                    .map_err(|e| format!("failed to restore {port:?}: {e}"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn replay(&mut self, record: TickRecord) -> Result<ReplayStep, BetulaError> {
+        // The inputs of this tick are the values at the end of the previous tick, except for the
+        // values written by the stubs during this tick.
+        let mut written = BlackboardValues::default();
+        for (blackboard_id, port_values) in record.values.blackboards.iter() {
+            let port_values: BTreeMap<_, _> = port_values
+                .iter()
+                .filter(|(port, _)| {
+                    let port = BlackboardPort::new(*blackboard_id, port);
+                    self.stub_outputs.contains(&port)
+                })
+                .map(|(port, value)| (port.clone(), value.clone()))
+                .collect();
+            written.blackboards.insert(*blackboard_id, port_values);
+        }
+        let previous = std::mem::take(&mut self.values);
+        self.restore_values(&previous)?;
+        self.values = previous;
+        self.restore_values(&written)?;
+        self.clock.set(record.time);
+        set_tick(&self.tree, record.tick)?;
+
+        let recorded: HashMap<NodeId, Status> = record
+            .result
+            .node_status
+            .iter()
+            .map(|s| (s.node, s.status.clone()))
+            .collect();
+        *self.recorded.try_borrow_mut()? = recorded.clone();
+
+        // Execute the nodes that were executed directly, in the order they finished.
+        let mut children = BTreeSet::new();
+        for id in recorded.keys() {
+            children.extend(self.tree.children(*id)?);
+        }
+        let mut executed: Vec<NodeId> = vec![];
+        for status in record.result.node_status.iter().rev() {
+            if !children.contains(&status.node) && !executed.contains(&status.node) {
+                executed.push(status.node);
+            }
+        }
+        let mut node_status = vec![];
+        for id in executed.iter().rev() {
+            let (_this_node, all_nodes) = execute_tracked(&self.tree, *id)?;
+            node_status.extend(all_nodes);
+        }
+        let replayed = ExecutionResult { node_status };
+
+        let replayed_status: HashMap<NodeId, &Status> = replayed
+            .node_status
+            .iter()
+            .map(|s| (s.node, &s.status))
+            .collect();
+        let mut diverged: BTreeSet<NodeId> = BTreeSet::new();
+        for (id, status) in recorded.iter() {
+            if replayed_status.get(id) != Some(&status) {
+                diverged.insert(*id);
+            }
+        }
+        diverged.extend(
+            replayed_status
+                .keys()
+                .filter(|id| !recorded.contains_key(id)),
+        );
+
+        for (blackboard_id, port_values) in record.values.blackboards {
+            self.values
+                .blackboards
+                .entry(blackboard_id)
+                .or_default()
+                .extend(port_values);
+        }
+
+        Ok(ReplayStep {
+            tick: record.tick,
+            time: record.time,
+            recorded: record.result,
+            replayed,
+            diverged: diverged.into_iter().collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tree_support::test_tree_support;
+
+    #[test]
+    fn test_record_replay() -> Result<(), BetulaError> {
+        let tree_support = test_tree_support();
+        let text = r#"blackboard globals
+root sequence = {"memory":false,"retry":false}
+  node std_time
+    output time -> globals
+  node std_delay = {"interval":1.0}
+    input time <- globals
+    node success
+"#;
+        let mut tree = BasicTree::new();
+        tree_support.import_tree_text(&mut tree, text)?;
+        let clock = ManualClock::new(10.0);
+        tree.set_clock(Box::new(clock.clone()));
+        let root = tree.roots()[0];
+
+        let path = std::env::temp_dir().join(format!("betula_trace_{}.jsonl", std::process::id()));
+        let mut recorder = TraceRecorder::create(&path)?;
+        recorder.record_tree(&tree_support.export_tree_config(&tree)?)?;
+        let mut recorded = vec![];
+        for tick in 1..=4 {
+            let time = tree.clock().now()?;
+            set_tick(&tree, tick)?;
+            let (status, all_nodes) = execute_tracked(&tree, root)?;
+            recorded.push(status?);
+            let result = ExecutionResult {
+                node_status: all_nodes,
+            };
+            let values = BlackboardValues::from_tree(&tree_support, &tree)?;
+            recorder.record_tick(tick, time, &result, &values)?;
+            clock.advance(0.5);
+        }
+        drop(recorder);
+        use ExecutionStatus::*;
+        assert_eq!(recorded, vec![Success, Running, Success, Running]);
+
+        let entries = read_trace(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(entries.len(), 5);
+        // Ticks hold the values that changed, which is the time on the globals.
+        let TraceEntry::Tick(first) = &entries[1] else {
+            panic!("expected a tick");
+        };
+        assert_eq!(first.values.blackboards.len(), 1);
+
+        // The delay node is replayed with the recorded time, while the time node is stubbed.
+        let mut replay = TraceReplay::new(&tree_support, entries.clone());
+        let mut replayed = vec![];
+        while let Some(step) = replay.step()? {
+            assert_eq!(step.diverged, vec![]);
+            replayed.push(step.replayed.node_status.last().unwrap().status.clone());
+        }
+        assert_eq!(
+            replayed,
+            vec![Ok(Success), Ok(Running), Ok(Success), Ok(Running)]
+        );
+        let node = replay.tree().node_ref(replay.tree().children(root)?[0]);
+        assert_eq!(node.unwrap().borrow().node_type(), "std_time".into());
+
+        // The time node executes with the clock at the recorded time when it isn't stubbed.
+        let mut replay = TraceReplay::new(&tree_support, entries.clone());
+        replay.set_stubbed(|node_type| *node_type != "std_time".into());
+        let mut replayed = vec![];
+        while let Some(step) = replay.step()? {
+            assert_eq!(step.diverged, vec![]);
+            replayed.push(step.replayed.node_status.last().unwrap().status.clone());
+        }
+        assert_eq!(
+            replayed,
+            vec![Ok(Success), Ok(Running), Ok(Success), Ok(Running)]
+        );
+
+        // A shorter delay runs the stubbed child in ticks it wasn't recorded for.
+        let mut entries = entries;
+        let TraceEntry::Tree(config) = &entries[0] else {
+            panic!("expected a tree");
+        };
+        let mut root = config.clone().into_latest();
+        let delay = root.nodes.iter_mut().find(|n| n.node_type == "std_delay");
+        delay.unwrap().config = Some(serde_json::json!({"interval": 0.5}));
+        entries[0] = TraceEntry::Tree(TreeConfig::V2(root));
+        let mut replay = TraceReplay::new(&tree_support, entries);
+        let mut diverged = vec![];
+        while let Some(step) = replay.step()? {
+            diverged.push(step.diverged.len());
+        }
+        assert_eq!(diverged, vec![0, 3, 0, 3]);
+        Ok(())
+    }
+}
@@ -1,10 +1,15 @@
+use betula_core::as_any::AsAnyHelper;
 use betula_core::prelude::*;
 use betula_core::{
     blackboard::{Chalkable, PortConnection, PortName},
+    conversion::Conversions,
+    sync::SharedBlackboards,
     BetulaError, Blackboard, BlackboardId, Node, NodeConfig, NodeType,
 };
 use serde::{Deserialize, Serialize};
 
+use crate::placeholder_node::{PlaceholderNode, PlaceholderNodeConfig, PlaceholderPort};
+use crate::subtree_node::SubTreeSupportCreator;
 use crate::type_support::{
     // Config support.
     ConfigConverter,
@@ -18,10 +23,15 @@ use crate::type_support::{
     DefaultValueRequirements,
     NodeFactory,
     ValueConverter,
+    VersionedConfigConverter,
+    VersionedConfigRequirements,
 };
 
 pub type BlackboardFactory = Box<dyn Fn() -> Box<dyn Blackboard>>;
 
+/// Converts the serialized config of an aliased node type to that of the new node type.
+pub type ConfigTransform = Box<dyn Fn(serde_json::Value) -> Result<serde_json::Value, BetulaError>>;
+
 type SerializableHolder = serde_json::Value;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -39,7 +49,7 @@ pub struct SerializedValue {
 use std::collections::BTreeMap;
 pub type SerializedBlackboardValues = BTreeMap<PortName, SerializedValue>;
 
-mod v1 {
+pub(crate) mod v1 {
     use super::{SerializableHolder, SerializedValue};
     use betula_core::{
         blackboard::{PortConnection, PortName},
@@ -64,6 +74,10 @@ mod v1 {
         pub values: BTreeMap<PortName, SerializedValue>,
         pub connections: Vec<PortConnection>,
         pub name: Option<String>,
+        #[serde(default)]
+        pub parent: Option<BlackboardId>,
+        #[serde(default)]
+        pub persistent: Vec<PortName>,
     }
 
     #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -74,9 +88,71 @@ mod v1 {
     }
 }
 
+pub(crate) mod v2 {
+    //! Node configs hold the version of their [`crate::type_support::ConfigConverter`].
+    use super::{v1, SerializableHolder};
+    use betula_core::NodeId;
+    use serde::{Deserialize, Serialize};
+
+    pub use v1::Blackboard;
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    pub struct TreeNode {
+        pub id: NodeId,
+        pub node_type: String,
+        pub config: Option<SerializableHolder>,
+        #[serde(default)]
+        pub config_version: u32,
+        pub children: Vec<NodeId>,
+        #[serde(default)]
+        pub name: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    pub struct Root {
+        pub nodes: Vec<TreeNode>,
+        pub blackboards: Vec<Blackboard>,
+        pub tree_roots: Vec<NodeId>,
+    }
+
+    /// Configs of the first version didn't have a version, they are version 0.
+    pub fn from_v1(root: v1::Root) -> Root {
+        let nodes = root
+            .nodes
+            .into_iter()
+            .map(|n| TreeNode {
+                id: n.id,
+                node_type: n.node_type,
+                config: n.config,
+                config_version: 0,
+                children: n.children,
+                name: n.name,
+            })
+            .collect();
+        Root {
+            nodes,
+            blackboards: root.blackboards,
+            tree_roots: root.tree_roots,
+        }
+    }
+}
+
+/// The serialized tree, [`TreeSupport::export_tree_config`] always creates the latest version.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum TreeConfig {
     V1(v1::Root),
+    V2(v2::Root),
+}
+
+impl TreeConfig {
+    /// Migrate the config to the latest version, node configs are not upgraded, that requires
+    /// [`TreeSupport::upgrade_tree_config`].
+    pub(crate) fn into_latest(self) -> v2::Root {
+        match self {
+            TreeConfig::V1(root) => v2::from_v1(root),
+            TreeConfig::V2(root) => root,
+        }
+    }
 }
 
 use std::collections::HashMap;
@@ -92,14 +168,33 @@ struct ValueTypeSupport {
     value_converter: Box<dyn ValueConverter>,
 }
 
+struct NodeAlias {
+    node_type: NodeType,
+    transform: Option<ConfigTransform>,
+}
+
+impl std::fmt::Debug for NodeAlias {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let transform_string = self.transform.as_ref().map(|_| "Transform");
+        fmt.debug_struct("NodeAlias")
+            .field("node_type", &self.node_type)
+            .field("transform", &transform_string)
+            .finish()
+    }
+}
+
 #[derive(Default)]
 pub struct TreeSupport {
     node_support: HashMap<NodeType, NodeTypeSupport>,
+    node_aliases: HashMap<NodeType, NodeAlias>,
+    node_placeholder: bool,
     // technically, value_support should index based on the name.
     // but we more often serialize than deserialize, so lets keep this
     // as is for now.
     value_support: HashMap<std::any::TypeId, ValueTypeSupport>,
     blackboard_factory: Option<BlackboardFactory>,
+    shared_blackboards: SharedBlackboards,
+    conversions: Conversions,
 }
 
 impl std::fmt::Debug for TreeSupport {
@@ -107,8 +202,12 @@ impl std::fmt::Debug for TreeSupport {
         let factory_string = self.blackboard_factory.as_ref().map(|_| "Factory");
         fmt.debug_struct("TreeSupport")
             .field("node_support", &self.node_support)
+            .field("node_aliases", &self.node_aliases)
+            .field("node_placeholder", &self.node_placeholder)
             .field("value_support", &self.value_support)
             .field("blackboard_factory", &factory_string)
+            .field("shared_blackboards", &self.shared_blackboards)
+            .field("conversions", &self.conversions)
             .finish()
     }
 }
@@ -128,10 +227,35 @@ impl TreeSupport {
         self.blackboard_factory = Some(blackboard_factory);
     }
 
+    pub fn has_blackboard_factory(&self) -> bool {
+        self.blackboard_factory.is_some()
+    }
+
     pub fn create_blackboard(&self) -> Option<Box<dyn Blackboard>> {
         self.blackboard_factory.as_ref().map(|v| v())
     }
 
+    /// Share the blackboards with the names in the registry, across the trees it is set on.
+    ///
+    /// Blackboards are shared when a tree config is imported, other blackboards are created by the
+    /// blackboard factory. Renaming a blackboard in a loaded tree doesn't change whether it is
+    /// shared.
+    pub fn set_shared_blackboards(&mut self, shared_blackboards: SharedBlackboards) {
+        self.shared_blackboards = shared_blackboards;
+    }
+
+    pub fn shared_blackboards(&self) -> &SharedBlackboards {
+        &self.shared_blackboards
+    }
+
+    /// Create a blackboard, the shared one if the name is shared.
+    pub fn create_blackboard_named(&self, name: Option<&str>) -> Option<Box<dyn Blackboard>> {
+        match name.and_then(|name| self.shared_blackboards.get(name)) {
+            Some(shared) => Some(Box::new(shared)),
+            None => self.create_blackboard(),
+        }
+    }
+
     //fn get_node_types(&self) -> Vec<NodeType> {
     //    self.node_support.keys().cloned().collect()
     //}
@@ -143,7 +267,14 @@ impl TreeSupport {
     }
 
     pub fn create_node(&self, node_type: &NodeType) -> Result<Box<dyn Node>, BetulaError> {
-        let node_support = self.get_node_support(node_type)?;
+        let (node_type, _) = self.resolve_alias(node_type.clone(), None)?;
+        if self.is_placeholder(&node_type) {
+            return Ok(Box::new(PlaceholderNode::new(PlaceholderNodeConfig {
+                node_type,
+                ..Default::default()
+            })));
+        }
+        let node_support = self.get_node_support(&node_type)?;
 
         node_support.factory.create()
     }
@@ -152,7 +283,12 @@ impl TreeSupport {
         self.value_support.values().find(|&v| v.name == name)
     }
 
-    fn add_node_factory(&mut self, node_type: NodeType, factory: Box<dyn NodeFactory>) {
+    /// The name that values of this type are serialized with.
+    pub(crate) fn value_type_name(&self, type_id: std::any::TypeId) -> Option<&str> {
+        self.value_support.get(&type_id).map(|v| v.name.as_str())
+    }
+
+    pub(crate) fn add_node_factory(&mut self, node_type: NodeType, factory: Box<dyn NodeFactory>) {
         let entry = self.node_support.insert(
             node_type.clone(),
             NodeTypeSupport {
@@ -199,6 +335,95 @@ impl TreeSupport {
         .expect("cannot fail, key was added line above");
     }
 
+    /// Add a node with a config that is upgraded from older versions, see [`betula_core::VersionedConfig`].
+    pub fn add_node_default_with_versioned_config<
+        N: DefaultNodeFactoryRequirements,
+        C: VersionedConfigRequirements,
+    >(
+        &mut self,
+    ) {
+        self.add_node_factory(N::static_type(), Box::new(DefaultNodeFactory::<N>::new()));
+        self.add_config_converter(
+            &N::static_type(),
+            Box::new(VersionedConfigConverter::<C>::new()),
+        )
+        .expect("cannot fail, key was added line above");
+    }
+
+    /// Load nodes of type `old` as `new`, for node types that were renamed. The alias is only used
+    /// if `old` itself is not supported, configs are kept as is.
+    pub fn add_node_alias(&mut self, old: &NodeType, new: &NodeType) {
+        self.node_aliases.insert(
+            old.clone(),
+            NodeAlias {
+                node_type: new.clone(),
+                transform: None,
+            },
+        );
+    }
+
+    /// Load nodes of type `old` as `new`, converting their config with `transform`. The config
+    /// version is kept, the transform produces the config of `new` at that version.
+    pub fn add_node_alias_with_transform(
+        &mut self,
+        old: &NodeType,
+        new: &NodeType,
+        transform: ConfigTransform,
+    ) {
+        self.node_aliases.insert(
+            old.clone(),
+            NodeAlias {
+                node_type: new.clone(),
+                transform: Some(transform),
+            },
+        );
+    }
+
+    /// Follow the aliases of an unsupported node type, transforming the config along the way.
+    fn resolve_alias(
+        &self,
+        node_type: NodeType,
+        config: Option<SerializableHolder>,
+    ) -> Result<(NodeType, Option<SerializableHolder>), BetulaError> {
+        let mut node_type = node_type;
+        let mut config = config;
+        let mut seen = vec![];
+        while !self.node_support.contains_key(&node_type) {
+            let Some(alias) = self.node_aliases.get(&node_type) else {
+                break;
+            };
+            if seen.contains(&node_type) {
+                return Err(format!("node alias {node_type:?} is cyclic").into());
+            }
+            if let (Some(transform), Some(data)) = (&alias.transform, config.take()) {
+                config =
+                    Some(transform(data).map_err(|e| {
+                        format!("failed to transform config of {node_type:?}: {e}")
+                    })?);
+            }
+            seen.push(node_type);
+            node_type = alias.node_type.clone();
+        }
+        Ok((node_type, config))
+    }
+
+    /// Load nodes of unsupported types as a [`PlaceholderNode`] instead of failing, the
+    /// placeholders are exported as they were loaded.
+    pub fn add_node_placeholder(&mut self) {
+        self.node_placeholder = true;
+    }
+
+    /// Whether nodes of this type are loaded as a [`PlaceholderNode`].
+    pub fn is_placeholder(&self, node_type: &NodeType) -> bool {
+        self.node_placeholder && !self.node_support.contains_key(node_type)
+    }
+
+    /// Add support for the [`crate::subtree_node::SubTreeNode`], subtrees are loaded with tree
+    /// support from `creator`.
+    pub fn add_node_subtree(&mut self, creator: SubTreeSupportCreator) {
+        crate::subtree_node::add_subtree_support(self, creator, vec![]);
+    }
+
     pub fn add_value_default<V: DefaultValueRequirements>(&mut self) {
         let support = ValueTypeSupport {
             name: std::any::type_name::<V>().to_owned(),
@@ -208,9 +433,25 @@ impl TreeSupport {
             .insert(std::any::TypeId::of::<V>(), support);
     }
 
+    /// Add a conversion such that inputs of type `T` can read blackboard values of type `F`.
+    ///
+    /// Trees use these conversions when they are imported with this support, see
+    /// [`Tree::set_conversions`].
+    pub fn add_conversion<F: 'static + Chalkable, T: 'static + Chalkable>(
+        &mut self,
+        convert: impl Fn(&F) -> Result<T, BetulaError> + Send + Sync + 'static,
+    ) {
+        self.conversions.add::<F, T>(convert)
+    }
+
+    /// The conversions between value types, holds the built-in conversions by default.
+    pub fn conversions(&self) -> &Conversions {
+        &self.conversions
+    }
+
     pub fn export_tree_config(&self, tree: &dyn Tree) -> Result<TreeConfig, BetulaError> {
         let mut nodes = vec![];
-        use v1::*;
+        use v2::*;
 
         for id in tree.nodes() {
             let tree_node = tree.node_ref(id).ok_or(format!("could not get {id:?}"))?;
@@ -218,12 +459,20 @@ impl TreeSupport {
             let tree_node = tree_node.borrow();
             let config = tree_node.get_config()?;
             let node_type = tree_node.node_type();
-            let config: Option<SerializableHolder> = if let Some(config) = config {
+            let mut config_version = 0;
+            let placeholder = config
+                .as_ref()
+                .and_then(|c| (**c).downcast_ref::<PlaceholderNodeConfig>());
+            let config: Option<SerializableHolder> = if let Some(placeholder) = placeholder {
+                config_version = placeholder.config_version;
+                placeholder.config.clone()
+            } else if let Some(config) = config {
                 let converter = self.node_support.get(&node_type);
                 let converter = converter
                     .and_then(|v| v.config_converter.as_ref())
                     .ok_or(format!("could not get support for {node_type:?}"))?;
                 let serialize_erased = converter.config_serialize(&*config)?;
+                config_version = converter.config_version();
                 Some(
                     serde_json::to_value(serialize_erased)
                         .map_err(|e| format!("json serialize error {e:?}"))?,
@@ -237,6 +486,7 @@ impl TreeSupport {
                 id,
                 node_type: node_type.into(),
                 config,
+                config_version,
                 children,
                 name,
             };
@@ -247,6 +497,8 @@ impl TreeSupport {
         for id in tree.blackboards() {
             let connections = tree.blackboard_connections(id);
             let name = tree.blackboard_name(id)?;
+            let parent = tree.blackboard_parent(id)?;
+            let persistent = tree.blackboard_persistent_ports(id)?;
             let blackboard = tree
                 .blackboard_ref(id)
                 .ok_or(format!("could not get {id:?}"))?;
@@ -260,6 +512,8 @@ impl TreeSupport {
                 values,
                 connections,
                 name,
+                parent,
+                persistent,
             };
             blackboards.push(b);
         }
@@ -278,7 +532,35 @@ impl TreeSupport {
             blackboards,
             tree_roots,
         };
-        Ok(TreeConfig::V1(root))
+        Ok(TreeConfig::V2(root))
+    }
+
+    /// Export the tree in the [text format](crate::text_format).
+    pub fn export_tree_text(&self, tree: &dyn Tree) -> Result<String, BetulaError> {
+        crate::text_format::to_text(&self.export_tree_config(tree)?)
+    }
+
+    /// Import a tree from the [text format](crate::text_format), the ids are generated.
+    pub fn import_tree_text(&self, tree: &mut dyn Tree, text: &str) -> Result<(), BetulaError> {
+        self.import_tree_config(tree, &crate::text_format::from_text(text)?)
+    }
+
+    /// Export the tree as a Graphviz DOT [diagram](crate::diagram).
+    pub fn export_tree_dot(
+        &self,
+        tree: &dyn Tree,
+        result: Option<&crate::control::ExecutionResult>,
+    ) -> Result<String, BetulaError> {
+        crate::diagram::to_dot(&self.export_tree_config(tree)?, result)
+    }
+
+    /// Export the tree as a Mermaid [diagram](crate::diagram).
+    pub fn export_tree_mermaid(
+        &self,
+        tree: &dyn Tree,
+        result: Option<&crate::control::ExecutionResult>,
+    ) -> Result<String, BetulaError> {
+        crate::diagram::to_mermaid(&self.export_tree_config(tree)?, result)
     }
 
     pub fn tree_serialize<S: serde::Serializer>(
@@ -293,90 +575,199 @@ impl TreeSupport {
         config.serialize(serializer)
     }
 
+    /// Migrate the config to the latest version, replace aliased node types and upgrade the node
+    /// configs to the version of their [`ConfigConverter`].
+    pub fn upgrade_tree_config(&self, config: &TreeConfig) -> Result<TreeConfig, BetulaError> {
+        Ok(TreeConfig::V2(self.upgrade_root(config)?))
+    }
+
+    fn upgrade_root(&self, config: &TreeConfig) -> Result<v2::Root, BetulaError> {
+        let mut root = config.clone().into_latest();
+        for node in root.nodes.iter_mut() {
+            let (node_type, config) =
+                self.resolve_alias(node.node_type.clone().into(), node.config.take())?;
+            node.node_type = node_type.0.clone();
+            node.config = config;
+            // Unsupported nodes are left as is, creating them fails.
+            let supported = self
+                .node_support
+                .get(&node_type)
+                .is_some_and(|s| s.config_converter.is_some());
+            if !supported {
+                continue;
+            }
+            if let Some(config) = node.config.take() {
+                let (version, config) =
+                    self.upgrade_config(&node_type, node.config_version, config)?;
+                node.config = Some(config);
+                node.config_version = version;
+            }
+        }
+        Ok(root)
+    }
+
+    /// Upgrade a serialized node config from `version` to the version of its
+    /// [`ConfigConverter`], returns the new version and config.
+    pub fn upgrade_config(
+        &self,
+        node_type: &NodeType,
+        version: u32,
+        config: SerializableHolder,
+    ) -> Result<(u32, SerializableHolder), BetulaError> {
+        let converter = self
+            .get_node_support(node_type)?
+            .config_converter
+            .as_ref()
+            .ok_or(format!("no config support for {node_type:?}"))?;
+        let current = converter.config_version();
+        if version > current {
+            return Err(format!(
+                "config of {node_type:?} has version {version}, newer than {current}"
+            )
+            .into());
+        }
+        let mut config = config;
+        for version in version..current {
+            config = converter
+                .config_upgrade(version, config)
+                .map_err(|e| format!("failed to upgrade {node_type:?}: {e}"))?;
+        }
+        Ok((current, config))
+    }
+
     pub fn import_tree_config(
         &self,
         tree: &mut dyn Tree,
         config: &TreeConfig,
     ) -> Result<(), BetulaError> {
-        match config {
-            TreeConfig::V1(root) => {
-                let mut relations = vec![];
-                let mut new_nodes = vec![];
-
-                // First, deserialize everything.
-                for node in &root.nodes {
-                    let node_type = node.node_type.clone().into();
-
-                    let mut new_node = self.create_node(&node_type)?;
-
-                    if let Some(config) = &node.config {
-                        let node_support = self.get_node_support(&node_type)?;
-                        if let Some(config_support) = node_support.config_converter.as_ref() {
-                            let mut erased =
-                                Box::new(<dyn erased_serde::Deserializer>::erase(config));
-                            let new_config = config_support.config_deserialize(&mut erased)?;
-                            new_node.set_config(&*new_config)?;
-                        }
-                    }
-                    let name = node.name.clone();
-                    new_nodes.push((node.id, new_node, name));
-                    relations.push((node.id, node.children.clone()));
-                }
-                // deserialize the blackboards.
-                struct BlackboardDeserialized {
-                    pub id: BlackboardId,
-                    pub values: HashMap<PortName, Box<dyn Chalkable>>,
-                    pub connections: Vec<PortConnection>,
-                    pub name: Option<String>,
-                }
-                let mut blackboards: Vec<BlackboardDeserialized> = vec![];
-                for blackboard in &root.blackboards {
-                    let mut deserialized_bb = BlackboardDeserialized {
-                        id: blackboard.id,
-                        connections: blackboard.connections.clone(),
-                        values: Default::default(),
-                        name: blackboard.name.clone(),
+        let root = self.upgrade_root(config)?;
+        tree.set_conversions(self.conversions.clone())?;
+        let mut relations = vec![];
+        let mut new_nodes = vec![];
+
+        // First, deserialize everything.
+        for node in &root.nodes {
+            let node_type = node.node_type.clone().into();
+
+            if self.is_placeholder(&node_type) {
+                let connections = root.blackboards.iter().flat_map(|b| b.connections.iter());
+                let mut ports: Vec<PlaceholderPort> = vec![];
+                for connection in connections.filter(|c| c.node.node() == node.id) {
+                    let port = PlaceholderPort {
+                        name: connection.node.name(),
+                        direction: connection.node.direction(),
                     };
-                    for (k, v) in &blackboard.values {
-                        let boxed_value = self.value_deserialize(v.clone())?;
-                        deserialized_bb.values.insert(k.clone(), boxed_value);
+                    if !ports.contains(&port) {
+                        ports.push(port);
                     }
-                    blackboards.push(deserialized_bb);
                 }
+                let placeholder = PlaceholderNode::new(PlaceholderNodeConfig {
+                    node_type,
+                    config: node.config.clone(),
+                    config_version: node.config_version,
+                    ports,
+                });
+                new_nodes.push((
+                    node.id,
+                    Box::new(placeholder) as Box<dyn Node>,
+                    node.name.clone(),
+                ));
+                relations.push((node.id, node.children.clone()));
+                continue;
+            }
 
-                // Serialization is all done, now add the nodes to the tree.
-                for (node_id, node, name) in new_nodes {
-                    tree.add_node_boxed(node_id, node)?;
-                    tree.set_node_name(node_id, name.as_deref())?;
-                }
+            let mut new_node = self.create_node(&node_type)?;
 
-                // Create the connections.
-                for (parent, children) in relations {
-                    tree.set_children(parent, &children)?;
+            if let Some(config) = &node.config {
+                let node_support = self.get_node_support(&node_type)?;
+                if let Some(config_support) = node_support.config_converter.as_ref() {
+                    let mut erased = Box::new(<dyn erased_serde::Deserializer>::erase(config));
+                    let new_config = config_support.config_deserialize(&mut erased)?;
+                    new_node.set_config(&*new_config)?;
                 }
+            }
+            let name = node.name.clone();
+            new_nodes.push((node.id, new_node, name));
+            relations.push((node.id, node.children.clone()));
+        }
+        // deserialize the blackboards.
+        struct BlackboardDeserialized {
+            pub id: BlackboardId,
+            pub values: HashMap<PortName, Box<dyn Chalkable>>,
+            pub connections: Vec<PortConnection>,
+            pub name: Option<String>,
+            pub parent: Option<BlackboardId>,
+            pub persistent: Vec<PortName>,
+        }
+        let mut blackboards: Vec<BlackboardDeserialized> = vec![];
+        for blackboard in &root.blackboards {
+            let mut deserialized_bb = BlackboardDeserialized {
+                id: blackboard.id,
+                connections: blackboard.connections.clone(),
+                values: Default::default(),
+                name: blackboard.name.clone(),
+                parent: blackboard.parent,
+                persistent: blackboard.persistent.clone(),
+            };
+            for (k, v) in &blackboard.values {
+                let boxed_value = self.value_deserialize(v.clone())?;
+                deserialized_bb.values.insert(k.clone(), boxed_value);
+            }
+            blackboards.push(deserialized_bb);
+        }
 
-                // Add the blackboards
-                for blackboard in blackboards {
-                    let id = blackboard.id;
-                    let mut bb = self
-                        .create_blackboard()
-                        .ok_or::<BetulaError>("no blackboard factory function".into())?;
-                    for (k, v) in blackboard.values {
-                        bb.set(&k, v.clone())?;
-                    }
-                    tree.add_blackboard_boxed(id, bb)?;
-                    if let Some(name) = blackboard.name {
-                        tree.set_blackboard_name(id, Some(&name))?;
-                    }
-                    for connection in blackboard.connections {
-                        tree.connect_port(&connection)?;
-                    }
-                }
+        // Serialization is all done, now add the nodes to the tree.
+        for (node_id, node, name) in new_nodes {
+            tree.add_node_boxed(node_id, node)?;
+            tree.set_node_name(node_id, name.as_deref())?;
+        }
+
+        // Create the connections.
+        for (parent, children) in relations {
+            tree.set_children(parent, &children)?;
+        }
 
-                // And set the roots.
-                tree.set_roots(&root.tree_roots)?;
+        // Add the blackboards
+        for blackboard in blackboards.iter_mut() {
+            let id = blackboard.id;
+            let mut bb = self
+                .create_blackboard_named(blackboard.name.as_deref())
+                .ok_or::<BetulaError>("no blackboard factory function".into())?;
+            // A shared blackboard is in use elsewhere, only initialise the ports it doesn't have.
+            let existing = if blackboard
+                .name
+                .as_deref()
+                .is_some_and(|name| self.shared_blackboards.get(name).is_some())
+            {
+                bb.ports()
+            } else {
+                vec![]
+            };
+            for (k, v) in blackboard.values.drain() {
+                if !existing.contains(&k) {
+                    bb.set(&k, v)?;
+                }
+            }
+            tree.add_blackboard_boxed(id, bb)?;
+            if let Some(name) = &blackboard.name {
+                tree.set_blackboard_name(id, Some(name))?;
+            }
+            tree.set_blackboard_persistent_ports(id, &blackboard.persistent)?;
+        }
+        // Parents may refer to any blackboard, they must all exist before connecting.
+        for blackboard in blackboards.iter() {
+            if blackboard.parent.is_some() {
+                tree.set_blackboard_parent(blackboard.id, blackboard.parent)?;
             }
         }
+        for blackboard in blackboards {
+            for connection in blackboard.connections {
+                tree.connect_port(&connection)?;
+            }
+        }
+
+        // And set the roots.
+        tree.set_roots(&root.tree_roots)?;
 
         Ok(())
     }
@@ -398,6 +789,12 @@ impl TreeSupport {
         node_type: NodeType,
         config: &dyn NodeConfig,
     ) -> Result<SerializedConfig, BetulaError> {
+        if let Some(placeholder) = (*config).downcast_ref::<PlaceholderNodeConfig>() {
+            return Ok(SerializedConfig {
+                node_type,
+                data: serde_json::to_value(placeholder)?,
+            });
+        }
         let converter = self.node_support.get(&node_type);
         let converter = converter
             .map(|v| v.config_converter.as_ref())
@@ -420,6 +817,10 @@ impl TreeSupport {
         config: SerializedConfig,
     ) -> Result<Box<dyn NodeConfig>, BetulaError> {
         let node_type = &config.node_type;
+        if self.is_placeholder(node_type) {
+            let placeholder: PlaceholderNodeConfig = serde_json::from_value(config.data)?;
+            return Ok(Box::new(placeholder));
+        }
         let converter = self.node_support.get(node_type);
         let converter = converter
             .map(|v| v.config_converter.as_ref())
@@ -505,10 +906,32 @@ impl<'a, 'b> serde::Serialize for TreeSerializer<'a, 'b> {
     }
 }
 
+/// Tree support with the common nodes and values from betula_std, shared by the tests.
+#[cfg(test)]
+pub(crate) fn test_tree_support() -> TreeSupport {
+    use betula_core::basic::BasicBlackboard;
+    use betula_std::nodes::*;
+    let mut tree_support = TreeSupport::new();
+    tree_support.add_node_default_with_config::<SequenceNode, SequenceNodeConfig>();
+    tree_support.add_node_default_with_config::<SelectorNode, SelectorNodeConfig>();
+    tree_support.add_node_default_with_config::<DelayNode, DelayNodeConfig>();
+    tree_support.add_node_default_with_config::<IfTimeExceedsNode, IfTimeExceedsNodeConfig>();
+    tree_support.add_node_default::<TimeNode>();
+    tree_support.add_node_default::<SuccessNode>();
+    tree_support.add_node_default::<FailureNode>();
+    tree_support.add_node_default::<StatusWriteNode>();
+    tree_support.add_node_default::<StatusReadNode>();
+    tree_support.add_value_default::<f64>();
+    tree_support.add_value_default::<betula_core::ExecutionStatus>();
+    tree_support.set_blackboard_factory(Box::new(|| Box::new(BasicBlackboard::default())));
+    tree_support
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use betula_core::basic::{BasicBlackboard, BasicTree};
+    use betula_core::node_prelude::*;
     use betula_core::{as_any::AsAnyHelper, BlackboardId, NodeId};
     use betula_std::nodes::{FailureNode, SelectorNode, SuccessNode};
     use uuid::Uuid;
@@ -527,6 +950,135 @@ mod test {
         assert_eq!(interval, deserialized.interval);
         Ok(())
     }
+    #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+    struct WaitNodeConfig {
+        interval: f64,
+    }
+    impl IsNodeConfig for WaitNodeConfig {}
+    // Pretend the interval was called delay in version 0.
+    impl VersionedConfig for WaitNodeConfig {
+        const VERSION: u32 = 1;
+        fn upgrade(
+            version: u32,
+            mut config: serde_json::Value,
+        ) -> Result<serde_json::Value, BetulaError> {
+            match version {
+                0 => {
+                    config["interval"] = config["delay"].take();
+                    Ok(config)
+                }
+                _ => Err(format!("unknown version {version}").into()),
+            }
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct WaitNode {
+        config: WaitNodeConfig,
+    }
+    impl Node for WaitNode {
+        fn execute(&mut self, _: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+            Ok(ExecutionStatus::Success)
+        }
+        fn get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
+            Ok(Some(Box::new(self.config.clone())))
+        }
+        fn set_config(&mut self, config: &dyn NodeConfig) -> Result<(), NodeError> {
+            self.config.load_node_config(config)
+        }
+        fn static_type() -> NodeType {
+            "wait".into()
+        }
+        fn node_type(&self) -> NodeType {
+            Self::static_type()
+        }
+    }
+
+    #[test]
+    fn test_config_upgrade() -> Result<(), BetulaError> {
+        let mut tree_support = TreeSupport::new();
+        tree_support.add_node_default_with_versioned_config::<WaitNode, WaitNodeConfig>();
+        let id = NodeId(Uuid::new_v4());
+        let node = serde_json::json!({"id": id, "node_type": "wait",
+            "config": {"delay": 2.5}, "children": []});
+        let v1 =
+            serde_json::json!({"V1": {"nodes": [node], "blackboards": [], "tree_roots": [id]}});
+        let config: TreeConfig = serde_json::from_value(v1)?;
+
+        let mut tree = BasicTree::new();
+        tree_support.import_tree_config(&mut tree, &config)?;
+        let config = tree
+            .node_ref(id)
+            .ok_or("missing node")?
+            .borrow()
+            .get_config()?;
+        let config = config.ok_or("missing config")?;
+        let config = (*config)
+            .downcast_ref::<WaitNodeConfig>()
+            .ok_or("could not downcast")?;
+        assert_eq!(config.interval, 2.5);
+
+        // The export holds the current version, which is imported without upgrading.
+        let exported = tree_support.export_tree_config(&tree)?;
+        let TreeConfig::V2(root) = &exported else {
+            panic!("export is not the latest version");
+        };
+        assert_eq!(root.nodes[0].config_version, 1);
+        assert_eq!(tree_support.upgrade_tree_config(&exported)?, exported);
+        let mut new_tree = BasicTree::new();
+        tree_support.import_tree_config(&mut new_tree, &exported)?;
+
+        // The text format keeps the version.
+        let text = tree_support.export_tree_text(&tree)?;
+        assert_eq!(text, "root wait =1 {\"interval\":2.5}\n");
+        assert_eq!(
+            crate::text_format::from_text(&text)?.into_latest().nodes[0].config_version,
+            1
+        );
+
+        // Configs newer than the converter can't be read.
+        let mut root = exported.into_latest();
+        root.nodes[0].config_version = 2;
+        let r = tree_support.import_tree_config(&mut BasicTree::new(), &TreeConfig::V2(root));
+        assert!(r.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_node_alias() -> Result<(), BetulaError> {
+        use betula_std::nodes::{DelayNode, DelayNodeConfig};
+        let mut tree_support = TreeSupport::new();
+        tree_support.add_node_default_with_config::<DelayNode, DelayNodeConfig>();
+        tree_support.add_node_default::<SuccessNode>();
+        tree_support.add_node_alias(&"old_success".into(), &SuccessNode::static_type());
+        tree_support.add_node_alias(&"older_success".into(), &"old_success".into());
+        tree_support.add_node_alias_with_transform(
+            &"wait".into(),
+            &DelayNode::static_type(),
+            Box::new(|mut config| {
+                config["interval"] = config["duration"].take();
+                Ok(config)
+            }),
+        );
+        assert!(tree_support.create_node(&"old_success".into()).is_ok());
+
+        let text = "root older_success\nnode wait = {\"duration\":1.5}\n";
+        let mut tree = BasicTree::new();
+        tree_support.import_tree_text(&mut tree, text)?;
+        // Saved under the new names.
+        assert_eq!(
+            tree_support.export_tree_text(&tree)?,
+            "root success\nnode std_delay = {\"interval\":1.5}\n"
+        );
+
+        tree_support.add_node_alias(&"success".into(), &"older_success".into());
+        tree_support.add_node_alias(&"a".into(), &"b".into());
+        tree_support.add_node_alias(&"b".into(), &"a".into());
+        assert!(tree_support.create_node(&"success".into()).is_ok());
+        assert!(tree_support.create_node(&"a".into()).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_tree() -> Result<(), BetulaError> {
         let mut tree_support = TreeSupport::new();
@@ -610,6 +1162,12 @@ mod test {
 
         tree.set_blackboard_name(bb, Some("ThisOneIsGreenWithLines"))?;
         tree.set_node_name(delay_node, Some("LongDelayNode"))?;
+        let globals = tree.add_blackboard_boxed(
+            BlackboardId(Uuid::new_v4()),
+            Box::new(BasicBlackboard::default()),
+        )?;
+        tree.set_blackboard_parent(bb, Some(globals))?;
+        tree.set_blackboard_persistent_ports(bb, &["time".into()])?;
 
         let obj = TreeSerializer::new(&tree_support, &*tree);
         let config_json = serde_json::to_string(&obj)?;
@@ -626,7 +1184,56 @@ mod test {
         println!("new_tree: {new_tree:#?}");
         let and_back = tree_support.tree_serialize(&*new_tree, serde_json::value::Serializer)?;
         assert_eq!(and_back, json_value);
+        assert_eq!(new_tree.blackboard_parent(bb)?, Some(globals));
+        assert_eq!(
+            new_tree.blackboard_persistent_ports(bb)?,
+            vec!["time".into()]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_blackboard_import() -> Result<(), BetulaError> {
+        use crate::text_format::from_text;
+        use betula_core::sync::SharedBlackboards;
+        let mut tree_support = test_tree_support();
+        let shared = SharedBlackboards::new();
+        shared.add("shared");
+        tree_support.set_shared_blackboards(shared.clone());
+        let read = |port: &str| -> Result<f64, BetulaError> {
+            let value = shared
+                .get("shared")
+                .ok_or("not shared")?
+                .get(&port.into())
+                .ok_or("missing value")?;
+            Ok(*(*value).downcast_ref::<f64>().ok_or("wrong type")?)
+        };
 
+        let first = from_text("blackboard shared\n  value counter f64 = 3.0\n")?;
+        let mut first_tree = BasicTree::new();
+        tree_support.import_tree_config(&mut first_tree, &first)?;
+        assert_eq!(read("counter")?, 3.0);
+        shared
+            .get("shared")
+            .ok_or("not shared")?
+            .set(&"counter".into(), Box::new(4.0f64))?;
+
+        // The second tree shares the name, its values only fill in the ports that are missing,
+        // even if they are of another type.
+        let second = from_text(&format!(
+            "blackboard shared\n  value counter {} = \"Success\"\n  value other f64 = 1.0\n",
+            std::any::type_name::<betula_core::ExecutionStatus>()
+        ))?;
+        let mut second_tree = BasicTree::new();
+        tree_support.import_tree_config(&mut second_tree, &second)?;
+        assert_eq!(read("counter")?, 4.0);
+        assert_eq!(read("other")?, 1.0);
+
+        // Reloading the first tree doesn't reset the shared state either.
+        let mut first_tree = BasicTree::new();
+        tree_support.import_tree_config(&mut first_tree, &first)?;
+        assert_eq!(read("counter")?, 4.0);
         Ok(())
     }
 }
@@ -1,5 +1,5 @@
 use betula_core::BetulaError;
-use betula_core::{IsNodeConfig, Node, NodeConfig};
+use betula_core::{IsNodeConfig, Node, NodeConfig, VersionedConfig};
 use serde::Serialize;
 
 /// Trait to create nodes out of thin air.
@@ -49,6 +49,21 @@ pub trait ConfigConverter: std::fmt::Debug {
         &self,
         config: &mut dyn erased_serde::Deserializer,
     ) -> Result<Box<dyn NodeConfig>, BetulaError>;
+
+    /// The version of the serialized config, older configs are upgraded before deserialization.
+    fn config_version(&self) -> u32 {
+        0
+    }
+
+    /// Upgrade a serialized config from `version` to `version + 1`.
+    fn config_upgrade(
+        &self,
+        version: u32,
+        config: serde_json::Value,
+    ) -> Result<serde_json::Value, BetulaError> {
+        let _ = config;
+        Err(format!("no upgrade from config version {version}").into())
+    }
 }
 
 pub trait DefaultConfigRequirements:
@@ -109,6 +124,63 @@ impl<T: DefaultConfigRequirements> ConfigConverter for DefaultConfigConverter<T>
         Ok(Box::new(erased_serde::deserialize::<T>(config)?))
     }
 }
+
+pub trait VersionedConfigRequirements: DefaultConfigRequirements + VersionedConfig {}
+impl<T> VersionedConfigRequirements for T where T: DefaultConfigRequirements + VersionedConfig {}
+
+/// Config converter for configs that implement [`VersionedConfig`].
+pub struct VersionedConfigConverter<T: VersionedConfigRequirements> {
+    converter: DefaultConfigConverter<T>,
+}
+impl<T: VersionedConfigRequirements> std::fmt::Debug for VersionedConfigConverter<T> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            fmt,
+            "VersionedConfigConverter<{}, {}>",
+            std::any::type_name::<T>(),
+            T::VERSION
+        )
+    }
+}
+
+impl<T: VersionedConfigRequirements> Default for VersionedConfigConverter<T> {
+    fn default() -> Self {
+        Self {
+            converter: DefaultConfigConverter::new(),
+        }
+    }
+}
+
+impl<T: VersionedConfigRequirements> VersionedConfigConverter<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+impl<T: VersionedConfigRequirements> ConfigConverter for VersionedConfigConverter<T> {
+    fn config_serialize(
+        &self,
+        config: &dyn NodeConfig,
+    ) -> Result<Box<dyn erased_serde::Serialize>, BetulaError> {
+        self.converter.config_serialize(config)
+    }
+    fn config_deserialize(
+        &self,
+        config: &mut dyn erased_serde::Deserializer,
+    ) -> Result<Box<dyn NodeConfig>, BetulaError> {
+        self.converter.config_deserialize(config)
+    }
+    fn config_version(&self) -> u32 {
+        T::VERSION
+    }
+    fn config_upgrade(
+        &self,
+        version: u32,
+        config: serde_json::Value,
+    ) -> Result<serde_json::Value, BetulaError> {
+        T::upgrade(version, config)
+    }
+}
+
 use betula_core::blackboard::Chalkable;
 
 /// Trait to facilitate serialization and deserialization of blackboard values.
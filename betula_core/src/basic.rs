@@ -2,14 +2,34 @@
 use crate::prelude::*;
 use std::collections::HashMap;
 
+mod lazy;
+pub use lazy::LazyTree;
+use lazy::WriteTracker;
+
+use crate::clock::{Clock, SystemClock};
+use crate::conversion::Conversions;
+use crate::error::{ErrorKind, ExecutionError};
 use crate::{
     blackboard::{PortConnection, PortDirection, PortName},
-    BetulaError, Blackboard, BlackboardId, ExecutionStatus, Node, NodeError, NodeId, NodePort,
+    BetulaError, Blackboard, BlackboardId, ExecutionObserver, ExecutionStatus, Node, NodeError,
+    NodeId, NodePort,
 };
 
 struct TreeContext<'a> {
     this_node: NodeId,
     tree: &'a BasicTree,
+    observer: &'a dyn ExecutionObserver,
+    ran: RefCell<Vec<(NodeId, ExecutionStatus)>>,
+}
+impl<'a> TreeContext<'a> {
+    fn new(this_node: NodeId, tree: &'a BasicTree) -> Self {
+        TreeContext {
+            this_node,
+            tree,
+            observer: &(),
+            ran: Default::default(),
+        }
+    }
 }
 impl RunContext for TreeContext<'_> {
     fn children(&self) -> usize {
@@ -20,12 +40,17 @@ impl RunContext for TreeContext<'_> {
     }
     fn run(&self, index: usize) -> Result<ExecutionStatus, NodeError> {
         let ids = self.tree.children(self.this_node)?;
-        self.tree.execute(ids[index])
+        let status = self.tree.execute_observed(ids[index], self.observer)?;
+        self.ran.borrow_mut().push((ids[index], status));
+        Ok(status)
     }
     fn reset_recursive(&self, index: usize) -> Result<(), NodeError> {
         let ids = self.tree.children(self.this_node)?;
         self.tree.reset_recursive(ids[index])
     }
+    fn clock(&self) -> &dyn Clock {
+        self.tree.clock()
+    }
 }
 
 impl ResetContext for TreeContext<'_> {
@@ -41,8 +66,126 @@ impl ResetContext for TreeContext<'_> {
     }
 }
 
-use std::cell::RefCell;
-use std::collections::HashSet;
+impl HaltContext for TreeContext<'_> {
+    fn children(&self) -> usize {
+        self.tree
+            .children(self.this_node)
+            .expect("node must exist in tree")
+            .len()
+    }
+    fn halt_recursive(&self, index: usize) -> Result<(), NodeError> {
+        let ids = self.tree.children(self.this_node)?;
+        self.tree.halt_recursive(ids[index])
+    }
+}
+
+/// The children that returned [`ExecutionStatus::Running`] to each parent.
+///
+/// Used to halt children that are no longer executed by their parent, see [`Node::halt`].
+#[derive(Debug, Default)]
+struct RunningChildren(RefCell<HashMap<NodeId, Vec<NodeId>>>);
+
+impl RunningChildren {
+    /// Update after a node finished its execution, returns the children that must be halted.
+    ///
+    /// The `ran` children are those executed by the node, with their status.
+    fn update(
+        &self,
+        id: NodeId,
+        ran: &[(NodeId, ExecutionStatus)],
+        status: &Result<ExecutionStatus, NodeError>,
+    ) -> Vec<NodeId> {
+        let mut running = self.0.borrow_mut();
+        let previous = running.remove(&id).unwrap_or_default();
+        let mut stopped: Vec<NodeId> = previous
+            .into_iter()
+            .filter(|child| !ran.iter().any(|(ran_child, _)| ran_child == child))
+            .collect();
+        let still_running = ran
+            .iter()
+            .filter(|(_, status)| *status == ExecutionStatus::Running)
+            .map(|(child, _)| *child);
+        if matches!(status, Ok(ExecutionStatus::Running)) {
+            let mut children: Vec<NodeId> = still_running.collect();
+            children.sort();
+            children.dedup();
+            if !children.is_empty() {
+                running.insert(id, children);
+            }
+        } else {
+            // This node is done, so it won't execute its running children anymore.
+            stopped.extend(still_running);
+        }
+        stopped.sort();
+        stopped.dedup();
+        stopped
+    }
+
+    /// Forget the running children of a node.
+    fn remove(&self, id: NodeId) {
+        self.0.borrow_mut().remove(&id);
+    }
+
+    /// Forget a node entirely, as it is removed from the tree.
+    fn remove_node(&self, id: NodeId) {
+        let mut running = self.0.borrow_mut();
+        running.remove(&id);
+        for children in running.values_mut() {
+            children.retain(|&child| child != id);
+        }
+    }
+}
+
+/// The default values of the optional input ports of a node.
+fn port_defaults(node: &dyn Node) -> Result<HashMap<PortName, Value>, NodeError> {
+    Ok(node
+        .ports()?
+        .into_iter()
+        .filter(|p| p.direction() == PortDirection::Input)
+        .filter_map(|p| Some((p.name(), p.default_value()?)))
+        .collect())
+}
+
+/// Reader for an input port that is not connected, optional ports read their default value.
+fn disconnected_reader(
+    id: &TypeId,
+    key: &PortName,
+    defaults: &HashMap<PortName, Value>,
+) -> Result<Read, NodeError> {
+    if let Some(default) = defaults.get(key) {
+        if (**default).as_any_type_id() != *id {
+            return Err(ExecutionError::new(
+                ErrorKind::TypeMismatch,
+                format!(
+                    "default for '{key:?}' has type {}, port is read as another type",
+                    (**default).as_any_type_name()
+                ),
+            )
+            .into());
+        }
+        let default = default.clone();
+        return Ok(Box::new(move || Ok(default.clone())));
+    }
+    Ok(Box::new(|| {
+        Err(ExecutionError::new(ErrorKind::MissingPort, "reading from disconnected port").into())
+    }))
+}
+
+/// Generation reader for an input port that is not connected, defaults never change.
+fn disconnected_generation_reader(
+    key: &PortName,
+    defaults: &HashMap<PortName, Value>,
+) -> Result<ReadGeneration, NodeError> {
+    if defaults.contains_key(key) {
+        return Ok(Box::new(|| Ok(PortGeneration::default())));
+    }
+    Ok(Box::new(|| {
+        Err(ExecutionError::new(ErrorKind::MissingPort, "reading from disconnected port").into())
+    }))
+}
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashSet};
 #[derive(Debug)]
 struct BasicTreeNode {
     node: RefCell<Box<dyn Node>>,
@@ -54,13 +197,23 @@ struct BasicBlackboardEntry {
     blackboard: RefCell<Box<dyn Blackboard>>,
     connections: HashSet<PortConnection>,
     name: Option<String>,
+    parent: Option<BlackboardId>,
+    persistent: BTreeSet<PortName>,
 }
+
 #[derive(Debug, Default)]
 pub struct BasicTree {
     nodes: HashMap<NodeId, BasicTreeNode>,
     blackboards: HashMap<BlackboardId, BasicBlackboardEntry>,
     tree_roots: Vec<NodeId>,
     directory: Option<std::path::PathBuf>,
+    /// Notified of writes that change blackboard values, used by the [`LazyTree`].
+    write_tracker: Option<Rc<WriteTracker>>,
+    running: RunningChildren,
+    clock: Box<dyn Clock>,
+    conversions: Conversions,
+    /// Set when a blackboard that may shadow a parent's port was modified.
+    ports_stale: Cell<bool>,
 }
 
 impl BasicTree {
@@ -70,6 +223,11 @@ impl BasicTree {
             blackboards: Default::default(),
             tree_roots: Default::default(),
             directory: Default::default(),
+            write_tracker: Default::default(),
+            running: Default::default(),
+            clock: Box::new(SystemClock),
+            conversions: Default::default(),
+            ports_stale: Default::default(),
         }
     }
 
@@ -154,7 +312,8 @@ impl BasicTree {
 
         struct Remapper<'a, 'b> {
             by_portname: &'a HashMap<PortName, Vec<PortConnection>>,
-            blackboards: &'b HashMap<BlackboardId, BasicBlackboardEntry>,
+            tree: &'b BasicTree,
+            write_tracker: Option<&'b Rc<WriteTracker>>,
         }
         impl<'a, 'b> BlackboardOutputInterface for Remapper<'a, 'b> {
             fn writer(
@@ -168,13 +327,27 @@ impl BasicTree {
                     // Collect the writers from all the blackboards.
                     let mut writers = vec![];
                     for connection in connections {
-                        let blackboard_id = connection.blackboard.blackboard();
-                        let blackboard_name = connection.blackboard.name();
-                        let blackboard = self.blackboards.get(&blackboard_id).ok_or_else(|| {
-                            format!("blackboard {blackboard_id:?} does not exist").to_string()
-                        })?;
+                        let port = self.tree.resolve_blackboard_port(&connection.blackboard);
+                        let blackboard_id = port.blackboard();
+                        let blackboard_name = port.name();
+                        let blackboard =
+                            self.tree.blackboards.get(&blackboard_id).ok_or_else(|| {
+                                format!("blackboard {blackboard_id:?} does not exist").to_string()
+                            })?;
                         let mut blackboard_mut = blackboard.blackboard.try_borrow_mut()?;
-                        writers.push(blackboard_mut.writer(id, &blackboard_name, default)?);
+                        let writer = blackboard_mut.writer(id, &blackboard_name, default)?;
+                        if let Some(write_tracker) = self.write_tracker {
+                            // Compare against the current value to only track actual changes.
+                            let reader = blackboard_mut.reader(&id, &blackboard_name)?;
+                            writers.push(write_tracker.track(
+                                blackboard_id,
+                                blackboard_name,
+                                reader,
+                                writer,
+                            ));
+                        } else {
+                            writers.push(writer);
+                        }
                     }
                     let one_setter: Write = Box::new(move |v| {
                         for w in &writers {
@@ -184,7 +357,13 @@ impl BasicTree {
                     });
                     Ok(one_setter)
                 } else {
-                    let v = |_| Err("writing to disconnected port".to_string().into());
+                    let v = |_| {
+                        Err(ExecutionError::new(
+                            ErrorKind::MissingPort,
+                            "writing to disconnected port",
+                        )
+                        .into())
+                    };
                     Ok(Box::new(v))
                 }
             }
@@ -192,7 +371,8 @@ impl BasicTree {
 
         let mut remapped_interface = Remapper {
             by_portname: &by_portname,
-            blackboards: &self.blackboards,
+            tree: self,
+            write_tracker: self.write_tracker.as_ref(),
         };
 
         let node = self
@@ -233,36 +413,52 @@ impl BasicTree {
 
         struct Remapper<'a, 'b> {
             by_portname: &'a HashMap<PortName, Option<PortConnection>>,
-            blackboards: &'b HashMap<BlackboardId, BasicBlackboardEntry>,
+            tree: &'b BasicTree,
+            defaults: &'a HashMap<PortName, Value>,
+            conversions: &'b Conversions,
         }
         impl<'a, 'b> BlackboardInputInterface for Remapper<'a, 'b> {
             fn reader(&mut self, id: &TypeId, key: &PortName) -> Result<Read, NodeError> {
-                let input_connection = self.by_portname.get(key);
-                if let Some(found_entry) = input_connection {
-                    if let Some(connection) = found_entry {
-                        // Collect the writers from all the blackboards.
-                        let blackboard_id = connection.blackboard.blackboard();
-                        let blackboard_name = connection.blackboard.name();
-                        let blackboard = self.blackboards.get(&blackboard_id).ok_or_else(|| {
+                if let Some(Some(connection)) = self.by_portname.get(key) {
+                    let port = self.tree.resolve_blackboard_port(&connection.blackboard);
+                    let blackboard_id = port.blackboard();
+                    let blackboard_name = port.name();
+                    let blackboard =
+                        self.tree.blackboards.get(&blackboard_id).ok_or_else(|| {
                             format!("blackboard {blackboard_id:?} does not exist").to_string()
                         })?;
-                        let mut blackboard_mut = blackboard.blackboard.try_borrow_mut()?;
-                        blackboard_mut.reader(id, &blackboard_name)
-                    } else {
-                        let v = || Err("reading from disconnected port".to_string().into());
-                        Ok(Box::new(v))
+                    let mut blackboard_mut = blackboard.blackboard.try_borrow_mut()?;
+                    // Read values of another type through a conversion, if there is one.
+                    let conversion = blackboard_mut
+                        .get(&blackboard_name)
+                        .map(|v| (*v).as_any_type_id())
+                        .filter(|from| from != id)
+                        .and_then(|from| Some((from, self.conversions.get(from, *id)?)));
+                    if let Some((from, convert)) = conversion {
+                        let reader = blackboard_mut.reader(&from, &blackboard_name)?;
+                        return Ok(Box::new(move || convert(&*reader()?)));
                     }
+                    blackboard_mut.reader(id, &blackboard_name)
                 } else {
-                    let v = || Err("reading from disconnected port".to_string().into());
-                    Ok(Box::new(v))
+                    disconnected_reader(id, key, self.defaults)
                 }
             }
-        }
 
-        let mut remapped_interface = Remapper {
-            by_portname: &by_portname,
-            blackboards: &self.blackboards,
-        };
+            fn generation_reader(&mut self, key: &PortName) -> Result<ReadGeneration, NodeError> {
+                if let Some(Some(connection)) = self.by_portname.get(key) {
+                    let port = self.tree.resolve_blackboard_port(&connection.blackboard);
+                    let blackboard_id = port.blackboard();
+                    let blackboard =
+                        self.tree.blackboards.get(&blackboard_id).ok_or_else(|| {
+                            format!("blackboard {blackboard_id:?} does not exist").to_string()
+                        })?;
+                    let mut blackboard_mut = blackboard.blackboard.try_borrow_mut()?;
+                    blackboard_mut.generation_reader(&port.name())
+                } else {
+                    disconnected_generation_reader(key, self.defaults)
+                }
+            }
+        }
 
         let node = self
             .nodes
@@ -270,11 +466,42 @@ impl BasicTree {
             .ok_or_else(|| format!("node {node:?} does not exist").to_string())?;
         let mut node_mut = node.node.try_borrow_mut()?;
 
+        let defaults = port_defaults(&**node_mut)?;
+        let mut remapped_interface = Remapper {
+            by_portname: &by_portname,
+            tree: self,
+            defaults: &defaults,
+            conversions: &self.conversions,
+        };
+
         node_mut.setup_inputs(&mut remapped_interface)?;
 
         Ok(())
     }
 
+    /// Setup the inputs and outputs of a node again, using its current connections.
+    fn setup_node_ports(&self, node: NodeId) -> Result<(), BetulaError> {
+        self.setup_node_inputs(node, &self.node_input_connections(node)?)?;
+        self.setup_node_outputs(node, &self.node_output_connections(node)?)
+    }
+
+    /// Setup the ports of all nodes again, for when the blackboard a port resolves to may change.
+    fn setup_all_ports(&self) {
+        for node in self.nodes() {
+            let _ = self.setup_node_ports(node);
+        }
+    }
+
+    /// Setup the ports of all nodes again if a blackboard with a parent may have gained or lost
+    /// a port since, returns whether the ports were setup again.
+    pub(crate) fn setup_stale_ports(&self) -> bool {
+        let stale = self.ports_stale.replace(false);
+        if stale {
+            self.setup_all_ports();
+        }
+        stale
+    }
+
     fn disconnect_node_ports(
         &self,
         node_id: &NodeId,
@@ -285,7 +512,9 @@ impl BasicTree {
             .get(node_id)
             .ok_or_else(|| format!("node {node_id:?} does not exist").to_string())?;
         let mut node_mut = node.node.try_borrow_mut()?;
-        struct Disconnecter {}
+        struct Disconnecter {
+            defaults: HashMap<PortName, Value>,
+        }
         impl BlackboardOutputInterface for Disconnecter {
             fn writer(
                 &mut self,
@@ -294,19 +523,28 @@ impl BasicTree {
                 default: &ValueCreator,
             ) -> Result<Write, NodeError> {
                 let _ = (id, key, default);
-                let v = |_| Err("writing to disconnected port".to_string().into());
+                let v = |_| {
+                    Err(
+                        ExecutionError::new(ErrorKind::MissingPort, "writing to disconnected port")
+                            .into(),
+                    )
+                };
                 Ok(Box::new(v))
             }
         }
         impl BlackboardInputInterface for Disconnecter {
             fn reader(&mut self, id: &TypeId, key: &PortName) -> Result<Read, NodeError> {
-                let _ = (id, key);
-                let v = || Err("reading from disconnected port".to_string().into());
-                Ok(Box::new(v))
+                disconnected_reader(id, key, &self.defaults)
+            }
+
+            fn generation_reader(&mut self, key: &PortName) -> Result<ReadGeneration, NodeError> {
+                disconnected_generation_reader(key, &self.defaults)
             }
         }
 
-        let mut remapped_interface = Disconnecter {};
+        let mut remapped_interface = Disconnecter {
+            defaults: port_defaults(&**node_mut)?,
+        };
         if direction == PortDirection::Input {
             node_mut.setup_inputs(&mut remapped_interface)?;
         } else {
@@ -338,6 +576,7 @@ impl Tree for BasicTree {
         }
 
         // then, actually discard this node.
+        self.running.remove_node(id);
         for (_k, v) in self.nodes.iter_mut() {
             v.children.retain(|&x| x != id);
         }
@@ -359,6 +598,8 @@ impl Tree for BasicTree {
                 name: None,
             },
         );
+        // Optional inputs provide their default value until they are connected.
+        let _ = self.setup_node_inputs(id, &[]);
 
         Ok(id)
     }
@@ -380,6 +621,15 @@ impl Tree for BasicTree {
     }
 
     fn execute(&self, id: NodeId) -> Result<ExecutionStatus, NodeError> {
+        self.execute_observed(id, &())
+    }
+
+    fn execute_observed(
+        &self,
+        id: NodeId,
+        observer: &dyn ExecutionObserver,
+    ) -> Result<ExecutionStatus, NodeError> {
+        self.setup_stale_ports();
         let mut n = self
             .nodes
             .get(&id)
@@ -387,11 +637,19 @@ impl Tree for BasicTree {
             .node
             .try_borrow_mut()?;
         let context = TreeContext {
-            this_node: id,
-            tree: self,
+            observer,
+            ..TreeContext::new(id, self)
         };
 
-        n.execute(&context)
+        observer.started(id);
+        let r = n
+            .execute(&context)
+            .map_err(|e| ExecutionError::returned_by(e, id));
+        observer.returned(id, &r);
+        for child in self.running.update(id, &context.ran.into_inner(), &r) {
+            self.halt_recursive(child)?;
+        }
+        r
     }
 
     fn reset_recursive(&self, id: NodeId) -> Result<(), NodeError> {
@@ -401,14 +659,24 @@ impl Tree for BasicTree {
             .ok_or_else(|| format!("node {id:?} does not exist").to_string())?
             .node
             .try_borrow_mut()?;
-        let context = TreeContext {
-            this_node: id,
-            tree: self,
-        };
+        let context = TreeContext::new(id, self);
 
         n.reset_recursive(&context)
     }
 
+    fn halt_recursive(&self, id: NodeId) -> Result<(), NodeError> {
+        self.running.remove(id);
+        let mut n = self
+            .nodes
+            .get(&id)
+            .ok_or_else(|| format!("node {id:?} does not exist").to_string())?
+            .node
+            .try_borrow_mut()?;
+        let context = TreeContext::new(id, self);
+
+        n.halt(&context)
+    }
+
     fn blackboards(&self) -> Vec<BlackboardId> {
         self.blackboards.keys().copied().collect()
     }
@@ -417,6 +685,10 @@ impl Tree for BasicTree {
         Some(&self.blackboards.get(&id)?.blackboard)
     }
     fn blackboard_mut(&mut self, id: BlackboardId) -> Option<&mut dyn Blackboard> {
+        // A port added to a blackboard with a parent shadows the parent's port of that name.
+        if self.blackboards.values().any(|b| b.parent.is_some()) {
+            self.ports_stale.set(true);
+        }
         let m = self.blackboards.get_mut(&id)?;
         Some(&mut **m.blackboard.get_mut())
     }
@@ -432,6 +704,8 @@ impl Tree for BasicTree {
                 blackboard: blackboard.into(),
                 connections: Default::default(),
                 name: None,
+                parent: None,
+                persistent: Default::default(),
             },
         );
         Ok(id)
@@ -447,11 +721,24 @@ impl Tree for BasicTree {
         for connection in connections.iter() {
             self.disconnect_port(connection)?;
         }
+        // Children of this blackboard no longer have a parent.
+        let mut orphaned = false;
+        for entry in self.blackboards.values_mut() {
+            if entry.parent == Some(id) {
+                entry.parent = None;
+                orphaned = true;
+            }
+        }
         // Then remove the blackboard and return.
-        self.blackboards
+        let blackboard = self
+            .blackboards
             .remove(&id)
             .map(|v| v.blackboard.into_inner())
-            .ok_or(format!("could not find blackboard {id:?}").into())
+            .ok_or(format!("could not find blackboard {id:?}"))?;
+        if orphaned {
+            self.setup_all_ports();
+        }
+        Ok(blackboard)
     }
 
     fn connect_port(&mut self, connection: &PortConnection) -> Result<(), BetulaError> {
@@ -485,6 +772,13 @@ impl Tree for BasicTree {
             .get_mut(&blackboard_id)
             .ok_or_else(|| format!("blackboard {blackboard_id:?} does not exist").to_string())?;
         blackboard.connections.insert(connection.clone());
+
+        // A new output may have added a value that shadows the value of a parent blackboard.
+        if connection.node.direction() == PortDirection::Output
+            && self.blackboards.values().any(|b| b.parent.is_some())
+        {
+            self.setup_all_ports();
+        }
         Ok(())
     }
 
@@ -561,6 +855,68 @@ impl Tree for BasicTree {
         Ok(blackboard.name.clone())
     }
 
+    fn set_blackboard_parent(
+        &mut self,
+        blackboard_id: BlackboardId,
+        parent: Option<BlackboardId>,
+    ) -> Result<(), BetulaError> {
+        if !self.blackboards.contains_key(&blackboard_id) {
+            return Err(format!("blackboard {blackboard_id:?} does not exist").into());
+        }
+        // The parent may not be a descendant of this blackboard.
+        let mut ancestor = parent;
+        while let Some(id) = ancestor {
+            if id == blackboard_id {
+                return Err(format!("parent {parent:?} of {blackboard_id:?} is a cycle").into());
+            }
+            ancestor = self
+                .blackboards
+                .get(&id)
+                .ok_or_else(|| format!("blackboard {id:?} does not exist").to_string())?
+                .parent;
+        }
+        if let Some(blackboard) = self.blackboards.get_mut(&blackboard_id) {
+            blackboard.parent = parent;
+        }
+        self.setup_all_ports();
+        Ok(())
+    }
+
+    fn blackboard_parent(
+        &self,
+        blackboard_id: BlackboardId,
+    ) -> Result<Option<BlackboardId>, BetulaError> {
+        let blackboard = self
+            .blackboards
+            .get(&blackboard_id)
+            .ok_or_else(|| format!("blackboard {blackboard_id:?} does not exist").to_string())?;
+        Ok(blackboard.parent)
+    }
+
+    fn set_blackboard_persistent_ports(
+        &mut self,
+        blackboard_id: BlackboardId,
+        ports: &[PortName],
+    ) -> Result<(), BetulaError> {
+        let blackboard = self
+            .blackboards
+            .get_mut(&blackboard_id)
+            .ok_or_else(|| format!("blackboard {blackboard_id:?} does not exist").to_string())?;
+        blackboard.persistent = ports.iter().cloned().collect();
+        Ok(())
+    }
+
+    fn blackboard_persistent_ports(
+        &self,
+        blackboard_id: BlackboardId,
+    ) -> Result<Vec<PortName>, BetulaError> {
+        let blackboard = self
+            .blackboards
+            .get(&blackboard_id)
+            .ok_or_else(|| format!("blackboard {blackboard_id:?} does not exist").to_string())?;
+        Ok(blackboard.persistent.iter().cloned().collect())
+    }
+
     fn set_node_name(&mut self, id: NodeId, name: Option<&str>) -> Result<(), BetulaError> {
         let node = self
             .nodes
@@ -583,22 +939,81 @@ impl Tree for BasicTree {
         for (_k, v) in self.nodes.iter_mut() {
             v.node.get_mut().set_directory(self.directory.as_deref());
         }
+        // The ports of a node may depend on files in the directory, setup the connections again.
+        self.setup_all_ports();
+    }
+
+    fn directory(&self) -> Option<&std::path::Path> {
+        self.directory.as_deref()
+    }
+
+    fn clock(&self) -> &dyn Clock {
+        &*self.clock
+    }
+
+    fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    fn conversions(&self) -> &Conversions {
+        &self.conversions
+    }
+
+    fn set_conversions(&mut self, conversions: Conversions) -> Result<(), BetulaError> {
+        self.conversions = conversions;
+        // Readers of existing connections may convert differently now, inputs that can no longer
+        // be read are disconnected.
+        let mut failed = vec![];
+        for node in self.nodes() {
+            if let Err(e) = self.setup_node_ports(node) {
+                self.disconnect_node_ports(&node, PortDirection::Input)?;
+                failed.push(format!("{node:?}: {e}"));
+            }
+        }
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "disconnected the inputs that can't be read with the conversions, {}",
+                failed.join(", ")
+            )
+            .into())
+        }
     }
 }
 
-use std::any::Any;
 use std::rc::Rc;
 
 use crate::blackboard::{
-    BlackboardInputInterface, BlackboardOutputInterface, Read, Value, ValueCreator, Write,
+    BlackboardInputInterface, BlackboardOutputInterface, PortGeneration, Read, ReadGeneration,
+    Value, ValueCreator, Write,
 };
 
+/// A value on the [`BasicBlackboard`] and when it was written.
+#[derive(Debug)]
+struct BasicBlackboardValue {
+    value: Value,
+    generation: PortGeneration,
+}
+
 use std::any::TypeId;
 #[derive(Default, Debug)]
 pub struct BasicBlackboard {
-    values: HashMap<PortName, (TypeId, Rc<RefCell<Value>>)>,
+    values: HashMap<PortName, (TypeId, Rc<RefCell<BasicBlackboardValue>>)>,
+    /// The generation of the last write and the current tick, shared with the writers.
+    current: Rc<Cell<PortGeneration>>,
 }
-use crate::as_any::AsAny;
+
+impl BasicBlackboard {
+    /// Advance the generation for a new write.
+    fn next_generation(current: &Cell<PortGeneration>) -> PortGeneration {
+        let mut generation = current.get();
+        generation.generation += 1;
+        current.set(generation);
+        generation
+    }
+}
+
 impl BlackboardOutputInterface for BasicBlackboard {
     fn writer(
         &mut self,
@@ -606,10 +1021,17 @@ impl BlackboardOutputInterface for BasicBlackboard {
         key: &PortName,
         default: &ValueCreator,
     ) -> Result<Write, NodeError> {
+        let current = self.current.clone();
         let (typeid, rc) = self
             .values
             .entry(key.clone())
-            .or_insert_with(|| (id, Rc::new(RefCell::new(default()))))
+            .or_insert_with(|| {
+                let value = BasicBlackboardValue {
+                    value: default(),
+                    generation: Self::next_generation(&current),
+                };
+                (id, Rc::new(RefCell::new(value)))
+            })
             .clone();
         let temp_rc = rc.clone();
         let current_type = {
@@ -617,27 +1039,31 @@ impl BlackboardOutputInterface for BasicBlackboard {
                 .try_borrow_mut()
                 .map_err(|_| format!("{key:?} was already borrowed"))?;
 
-            (**z).as_any_type_name().to_string()
+            (*z.value).as_any_type_name().to_string()
         };
         let owned_key = key.to_string();
         if typeid != id {
-            Err(format!(
-                "new writer for '{key:?}', has wrong type: already got {}",
-                current_type
+            Err(ExecutionError::new(
+                ErrorKind::TypeMismatch,
+                format!("new writer for '{key:?}', has wrong type: already got {current_type}"),
             )
             .into())
         } else {
             Ok(Box::new(move |v: Value| {
                 let mut locked = rc.try_borrow_mut()?;
-                if (**locked).as_any_type_id() != (*v).as_any_type_id() {
-                    Err(format!(
-                        "assignment for '{owned_key:?}' is incorrect type {} expected {}",
-                        (**locked).as_any_type_name(),
-                        (*v).as_any_type_name()
+                if (*locked.value).as_any_type_id() != (*v).as_any_type_id() {
+                    Err(ExecutionError::new(
+                        ErrorKind::TypeMismatch,
+                        format!(
+                            "assignment for '{owned_key:?}' is incorrect type {} expected {}",
+                            (*locked.value).as_any_type_name(),
+                            (*v).as_any_type_name()
+                        ),
                     )
                     .into())
                 } else {
-                    *locked = v;
+                    locked.value = v;
+                    locked.generation = Self::next_generation(&current);
                     Ok(())
                 }
             }))
@@ -647,25 +1073,35 @@ impl BlackboardOutputInterface for BasicBlackboard {
 
 impl BlackboardInputInterface for BasicBlackboard {
     fn reader(&mut self, id: &TypeId, key: &PortName) -> Result<Read, NodeError> {
-        let (typeid, rc) = self
-            .values
-            .get(key)
-            .ok_or_else(|| format!("key '{key:?}' not found"))?;
+        let (typeid, rc) = self.values.get(key).ok_or_else(|| {
+            ExecutionError::new(ErrorKind::MissingPort, format!("key '{key:?}' not found"))
+        })?;
         let v = rc.clone();
         if typeid != id {
-            Err(format!(
-                "new reader for '{key:?}' mismatches type: already got {}",
-                rc.as_any_type_name()
+            Err(ExecutionError::new(
+                ErrorKind::TypeMismatch,
+                format!(
+                    "new reader for '{key:?}' mismatches type: already got {}",
+                    (*rc.borrow().value).as_any_type_name()
+                ),
             )
             .into())
         } else {
             Ok(Box::new(move || {
                 let locked = v.try_borrow_mut()?;
-                let cloned = (*locked).clone();
+                let cloned = locked.value.clone();
                 Ok(cloned)
             }))
         }
     }
+
+    fn generation_reader(&mut self, key: &PortName) -> Result<ReadGeneration, NodeError> {
+        let (_, rc) = self.values.get(key).ok_or_else(|| {
+            ExecutionError::new(ErrorKind::MissingPort, format!("key '{key:?}' not found"))
+        })?;
+        let v = rc.clone();
+        Ok(Box::new(move || Ok(v.try_borrow()?.generation)))
+    }
 }
 impl Blackboard for BasicBlackboard {
     fn new() -> Self {
@@ -680,22 +1116,38 @@ impl Blackboard for BasicBlackboard {
     }
 
     fn get(&self, port: &PortName) -> Option<Value> {
-        self.values.get(port).map(|x| x.1.borrow().clone_boxed())
+        self.values
+            .get(port)
+            .map(|x| x.1.borrow().value.clone_boxed())
+    }
+
+    fn generation(&self, port: &PortName) -> Option<PortGeneration> {
+        self.values.get(port).map(|x| x.1.borrow().generation)
+    }
+
+    fn set_tick(&mut self, tick: u64) {
+        let mut current = self.current.get();
+        current.tick = tick;
+        self.current.set(current);
     }
 
     fn set(&mut self, port: &PortName, value: Value) -> Result<(), BetulaError> {
         let new_value_type = (*value).as_any_type_id();
-        let old_value_type = self.values.get(port).map(|x| (*x).type_id());
+        let old_value_type = self.values.get(port).map(|x| x.0);
         if let Some(old_value_type) = old_value_type {
             if new_value_type != old_value_type {
                 return Err("different type already on blackboard".into());
             }
         }
+        let generation = Self::next_generation(&self.current);
         match self.values.entry(port.clone()) {
             std::collections::hash_map::Entry::Occupied(e) => {
-                *(e.get().1.try_borrow_mut()?) = value;
+                let mut entry = e.get().1.try_borrow_mut()?;
+                entry.value = value;
+                entry.generation = generation;
             }
             std::collections::hash_map::Entry::Vacant(v) => {
+                let value = BasicBlackboardValue { value, generation };
                 v.insert((new_value_type, Rc::new(RefCell::new(value))));
             }
         }
@@ -704,13 +1156,16 @@ impl Blackboard for BasicBlackboard {
     }
 
     fn remove(&mut self, port: &PortName) -> Option<Value> {
-        self.values.remove(port).map(|x| x.1.borrow().clone_boxed())
+        self.values
+            .remove(port)
+            .map(|x| x.1.borrow().value.clone_boxed())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::as_any::AsAny;
     use crate::Node;
 
     #[test]
@@ -746,6 +1201,36 @@ mod tests {
         println!("value: {:?}", z);
     }
 
+    #[test]
+    fn blackboard_generation() -> Result<(), NodeError> {
+        let mut bb = BasicBlackboard::default();
+        let output = bb.output("value", 1i64)?;
+        let input = bb.input::<i64>("value")?;
+        let created = input.generation()?;
+        assert!(!input.changed_since(created.generation)?);
+
+        bb.set_tick(3);
+        output.set(1)?;
+        let written = input.generation()?;
+        assert!(written.generation > created.generation);
+        assert_eq!(written.tick, 3);
+        assert!(input.changed_since(created.generation)?);
+        assert!(!input.changed_since(written.generation)?);
+
+        // Setting through the blackboard also increases the generation.
+        bb.set(&"value".into(), Box::new(2i64))?;
+        assert!(input.changed_since(written.generation)?);
+        assert_eq!(bb.generation(&"value".into()), Some(input.generation()?));
+        assert_eq!(bb.generation(&"missing".into()), None);
+
+        // Generations don't go back when a port is created again.
+        let removed = input.generation()?;
+        bb.remove(&"value".into());
+        bb.set(&"value".into(), Box::new(3i64))?;
+        assert!(bb.generation(&"value".into()).unwrap() > removed);
+        Ok(())
+    }
+
     use crate::{blackboard::Input, blackboard::Output, NodeType, Port};
 
     #[derive(Debug, Default)]
@@ -893,4 +1378,229 @@ mod tests {
         assert!(value.unwrap().is_equal(&*expected.unwrap()));
         Ok(())
     }
+
+    #[test]
+    fn test_blackboard_parent() -> Result<(), NodeError> {
+        let mut tree = BasicTree::new();
+        let output = tree.add_node_boxed(
+            NodeId(crate::Uuid::new_v4()),
+            Box::new(OutputNode::default()),
+        )?;
+        let input = tree.add_node_boxed(
+            NodeId(crate::Uuid::new_v4()),
+            Box::new(InputNode::default()),
+        )?;
+        let global = tree.add_blackboard_boxed(
+            BlackboardId(crate::Uuid::new_v4()),
+            Box::new(BasicBlackboard::default()),
+        )?;
+        let local = tree.add_blackboard_boxed(
+            BlackboardId(crate::Uuid::new_v4()),
+            Box::new(BasicBlackboard::default()),
+        )?;
+        tree.blackboard_mut(global)
+            .ok_or("missing blackboard")?
+            .set(&"a".into(), Box::new(0.0f64))?;
+        tree.set_blackboard_parent(local, Some(global))?;
+        assert_eq!(tree.blackboard_parent(local)?, Some(global));
+        assert!(tree.set_blackboard_parent(global, Some(local)).is_err());
+
+        // Both ports use the value of the parent, as the local blackboard doesn't hold it.
+        let output_port = tree.node_ports(output)?.remove(0);
+        let input_port = tree.node_ports(input)?.remove(0);
+        tree.connect_port_to_blackboard(&output_port, local)?;
+        tree.connect_port_to_blackboard(&input_port, local)?;
+        assert_eq!(tree.execute(input)?, ExecutionStatus::Failure);
+        tree.execute(output)?;
+        assert_eq!(tree.execute(input)?, ExecutionStatus::Success);
+        let value = tree
+            .blackboard_ref(global)
+            .ok_or("missing")?
+            .borrow()
+            .get(&"a".into());
+        assert!(value.ok_or("missing value")?.is_equal(&3.3f64));
+        assert!(tree
+            .blackboard_ref(local)
+            .ok_or("missing")?
+            .borrow()
+            .ports()
+            .is_empty());
+
+        // A local value shadows the parent's value.
+        tree.blackboard_mut(local)
+            .ok_or("missing blackboard")?
+            .set(&"a".into(), Box::new(0.0f64))?;
+        assert_eq!(tree.execute(input)?, ExecutionStatus::Failure);
+
+        // Removing the parent leaves the child without parent.
+        tree.remove_blackboard(global)?;
+        assert_eq!(tree.blackboard_parent(local)?, None);
+        Ok(())
+    }
+
+    /// Succeeds if its input exceeds the threshold, which is optional.
+    #[derive(Debug, Default)]
+    pub struct ThresholdNode {
+        value: Input<f64>,
+        threshold: Input<f64>,
+    }
+    impl Node for ThresholdNode {
+        fn execute(&mut self, _ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+            if self.value.get_or_default()? > self.threshold.get()? {
+                Ok(ExecutionStatus::Success)
+            } else {
+                Ok(ExecutionStatus::Failure)
+            }
+        }
+        fn ports(&self) -> Result<Vec<Port>, NodeError> {
+            Ok(vec![
+                Port::input::<f64>("value"),
+                Port::input_optional::<f64>("threshold", 0.5),
+            ])
+        }
+        fn setup_inputs(
+            &mut self,
+            interface: &mut dyn BlackboardInputInterface,
+        ) -> Result<(), NodeError> {
+            self.value = interface.input::<f64>("value")?;
+            self.threshold = interface.input::<f64>("threshold")?;
+            Ok(())
+        }
+        fn static_type() -> NodeType {
+            "threshold_node".into()
+        }
+        fn node_type(&self) -> NodeType {
+            Self::static_type()
+        }
+    }
+
+    #[test]
+    fn test_optional_input() -> Result<(), NodeError> {
+        let mut tree = BasicTree::new();
+        let node = tree.add_node_boxed(
+            NodeId(crate::Uuid::new_v4()),
+            Box::new(ThresholdNode::default()),
+        )?;
+        // Value is missing and read as zero, threshold is the default.
+        assert_eq!(tree.execute(node)?, ExecutionStatus::Failure);
+        assert_eq!(tree.validate().len(), 1);
+
+        let bb = tree.add_blackboard_boxed(
+            BlackboardId(crate::Uuid::new_v4()),
+            Box::new(BasicBlackboard::default()),
+        )?;
+        tree.blackboard_mut(bb)
+            .ok_or("missing blackboard")?
+            .set(&"value".into(), Box::new(1.0f64))?;
+        let ports = tree.node_ports(node)?;
+        tree.connect_port_to_blackboard(&ports[0], bb)?;
+        assert_eq!(tree.execute(node)?, ExecutionStatus::Success);
+        assert!(tree.validate().is_empty());
+
+        // Connected threshold takes precedence over the default.
+        tree.blackboard_mut(bb)
+            .ok_or("missing blackboard")?
+            .set(&"threshold".into(), Box::new(2.0f64))?;
+        tree.connect_port_to_blackboard(&ports[1], bb)?;
+        assert_eq!(tree.execute(node)?, ExecutionStatus::Failure);
+
+        // And the default is used again after disconnecting.
+        let threshold = tree
+            .port_connections()
+            .into_iter()
+            .find(|c| c.node.name() == "threshold".into())
+            .ok_or("missing connection")?;
+        tree.disconnect_port(&threshold)?;
+        assert_eq!(tree.execute(node)?, ExecutionStatus::Success);
+        Ok(())
+    }
+
+    /// Runs the child selected by `active`, succeeds if it is out of range.
+    #[derive(Debug)]
+    pub struct SwitchNode {
+        active: Rc<Cell<usize>>,
+    }
+    impl Node for SwitchNode {
+        fn execute(&mut self, ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+            if self.active.get() < ctx.children() {
+                ctx.run(self.active.get())
+            } else {
+                Ok(ExecutionStatus::Success)
+            }
+        }
+        fn static_type() -> NodeType {
+            "switch".into()
+        }
+        fn node_type(&self) -> NodeType {
+            Self::static_type()
+        }
+        fn always_execute(&self) -> bool {
+            true
+        }
+    }
+
+    /// Always running, counts how often it is halted.
+    #[derive(Debug)]
+    pub struct HaltCountNode {
+        halts: Rc<Cell<usize>>,
+    }
+    impl Node for HaltCountNode {
+        fn execute(&mut self, _ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+            Ok(ExecutionStatus::Running)
+        }
+        fn halt(&mut self, _ctx: &dyn HaltContext) -> Result<(), NodeError> {
+            self.halts.set(self.halts.get() + 1);
+            Ok(())
+        }
+        fn static_type() -> NodeType {
+            "halt_count".into()
+        }
+        fn node_type(&self) -> NodeType {
+            Self::static_type()
+        }
+    }
+
+    #[test]
+    fn test_halt() -> Result<(), NodeError> {
+        let trees: [Box<dyn Tree>; 2] = [Box::new(BasicTree::new()), Box::new(LazyTree::new())];
+        for mut tree in trees {
+            let active = Rc::new(Cell::new(0));
+            let halts = [Rc::new(Cell::new(0)), Rc::new(Cell::new(0))];
+            let root = tree.add_node_boxed(
+                NodeId(crate::Uuid::new_v4()),
+                Box::new(SwitchNode {
+                    active: active.clone(),
+                }),
+            )?;
+            let mut children = vec![];
+            for h in halts.iter() {
+                children.push(tree.add_node_boxed(
+                    NodeId(crate::Uuid::new_v4()),
+                    Box::new(HaltCountNode { halts: h.clone() }),
+                )?);
+            }
+            tree.set_children(root, &children)?;
+            let counts = || (halts[0].get(), halts[1].get());
+
+            assert_eq!(tree.execute(root)?, ExecutionStatus::Running);
+            assert_eq!(counts(), (0, 0));
+
+            // Switching to the other child halts the first.
+            active.set(1);
+            assert_eq!(tree.execute(root)?, ExecutionStatus::Running);
+            assert_eq!(counts(), (1, 0));
+            assert_eq!(tree.execute(root)?, ExecutionStatus::Running);
+            assert_eq!(counts(), (1, 0));
+
+            // Parent finishing halts the running child.
+            active.set(2);
+            assert_eq!(tree.execute(root)?, ExecutionStatus::Success);
+            assert_eq!(counts(), (1, 1));
+
+            // Halting explicitly halts the entire subtree.
+            tree.halt_recursive(root)?;
+            assert_eq!(counts(), (2, 2));
+        }
+        Ok(())
+    }
 }
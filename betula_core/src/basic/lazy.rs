@@ -0,0 +1,686 @@
+//! A tree that only re-evaluates the parts that may have changed.
+use super::BasicTree;
+use crate::clock::Clock;
+use crate::conversion::Conversions;
+use crate::error::ExecutionError;
+use crate::prelude::*;
+use crate::{
+    blackboard::{PortConnection, PortDirection, PortName, Read, Value, Write},
+    BetulaError, Blackboard, BlackboardId, ExecutionObserver, ExecutionStatus, Node, NodeError,
+    NodeId,
+};
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Keeps track of when blackboard values last changed.
+///
+/// Changes are recorded as a generation, which is incremented on every change.
+#[derive(Debug, Default)]
+pub(super) struct WriteTracker {
+    generation: Cell<u64>,
+    ports: RefCell<HashMap<(BlackboardId, PortName), u64>>,
+    blackboards: RefCell<HashMap<BlackboardId, u64>>,
+}
+
+impl WriteTracker {
+    /// The current generation.
+    fn generation(&self) -> u64 {
+        self.generation.get()
+    }
+
+    fn next_generation(&self) -> u64 {
+        self.generation.set(self.generation.get() + 1);
+        self.generation.get()
+    }
+
+    /// Record that a port on a blackboard changed.
+    fn port_changed(&self, blackboard: BlackboardId, name: &PortName) {
+        let generation = self.next_generation();
+        self.ports
+            .borrow_mut()
+            .insert((blackboard, name.clone()), generation);
+    }
+
+    /// Record that any value on a blackboard may have changed.
+    fn blackboard_changed(&self, blackboard: BlackboardId) {
+        let generation = self.next_generation();
+        self.blackboards.borrow_mut().insert(blackboard, generation);
+    }
+
+    /// Whether a port changed after the provided generation.
+    fn changed_since(&self, blackboard: BlackboardId, name: &PortName, generation: u64) -> bool {
+        let port = self
+            .ports
+            .borrow()
+            .get(&(blackboard, name.clone()))
+            .copied()
+            .unwrap_or(0);
+        let blackboard = self
+            .blackboards
+            .borrow()
+            .get(&blackboard)
+            .copied()
+            .unwrap_or(0);
+        port > generation || blackboard > generation
+    }
+
+    /// Wrap a writer such that writes that change the value are recorded.
+    pub(super) fn track(
+        self: &Rc<Self>,
+        blackboard: BlackboardId,
+        name: PortName,
+        reader: Read,
+        writer: Write,
+    ) -> Write {
+        let tracker = Rc::clone(self);
+        Box::new(move |v: Value| {
+            let changed = reader()
+                .map(|current| !(*current).is_equal(&*v))
+                .unwrap_or(true);
+            writer(v)?;
+            if changed {
+                tracker.port_changed(blackboard, &name);
+            }
+            Ok(())
+        })
+    }
+}
+
+/// The result of the last execution of a node.
+#[derive(Debug)]
+struct LazyNodeState {
+    /// Status returned by the last execution.
+    status: ExecutionStatus,
+    /// The generation at which the execution started.
+    generation: u64,
+    /// The input ports of the node at the time of execution.
+    inputs: Vec<(BlackboardId, PortName)>,
+    /// The children the node ran during its execution, in order.
+    children: Vec<NodeId>,
+}
+
+struct LazyTreeContext<'a> {
+    this_node: NodeId,
+    tree: &'a LazyTree,
+    observer: &'a dyn ExecutionObserver,
+    /// The children ran by the node, with their status if they didn't return an error.
+    ran: RefCell<Vec<(NodeId, Option<ExecutionStatus>)>>,
+}
+impl RunContext for LazyTreeContext<'_> {
+    fn children(&self) -> usize {
+        self.tree
+            .children(self.this_node)
+            .expect("node must exist in tree")
+            .len()
+    }
+    fn run(&self, index: usize) -> Result<ExecutionStatus, NodeError> {
+        let ids = self.tree.children(self.this_node)?;
+        let r = self.tree.execute_observed(ids[index], self.observer);
+        self.ran
+            .borrow_mut()
+            .push((ids[index], r.as_ref().ok().copied()));
+        r
+    }
+    fn reset_recursive(&self, index: usize) -> Result<(), NodeError> {
+        let ids = self.tree.children(self.this_node)?;
+        self.tree.reset_recursive(ids[index])
+    }
+    fn clock(&self) -> &dyn Clock {
+        self.tree.clock()
+    }
+}
+
+impl ResetContext for LazyTreeContext<'_> {
+    fn children(&self) -> usize {
+        self.tree
+            .children(self.this_node)
+            .expect("node must exist in tree")
+            .len()
+    }
+    fn reset_recursive(&self, index: usize) -> Result<(), NodeError> {
+        let ids = self.tree.children(self.this_node)?;
+        self.tree.reset_recursive(ids[index])
+    }
+}
+
+impl HaltContext for LazyTreeContext<'_> {
+    fn children(&self) -> usize {
+        self.tree
+            .children(self.this_node)
+            .expect("node must exist in tree")
+            .len()
+    }
+    fn halt_recursive(&self, index: usize) -> Result<(), NodeError> {
+        let ids = self.tree.children(self.this_node)?;
+        self.tree.halt_recursive(ids[index])
+    }
+}
+
+/// A tree that skips execution of subtrees that cannot have changed.
+///
+/// Nodes are considered pure functions of their inputs and children. A node is only executed if
+/// one of its input ports changed since its previous execution, or if any of the children it ran
+/// during that execution needs to be executed. Otherwise the status of the previous execution is
+/// returned. Writes to the blackboards are tracked through the writers handed to the nodes, writes
+/// that do not change the value don't cause re-evaluation.
+///
+/// Nodes that return true from [`Node::always_execute`] are always executed, and so are their
+/// ancestors. Resetting, reconfiguring, or reconnecting a node discards its previous result.
+/// Changes made through [`Tree::blackboard_mut`] cause re-evaluation of all nodes reading from
+/// that blackboard.
+#[derive(Debug)]
+pub struct LazyTree {
+    tree: BasicTree,
+    write_tracker: Rc<WriteTracker>,
+    states: RefCell<HashMap<NodeId, LazyNodeState>>,
+}
+
+impl Default for LazyTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LazyTree {
+    pub fn new() -> Self {
+        let write_tracker: Rc<WriteTracker> = Default::default();
+        let mut tree = BasicTree::new();
+        tree.write_tracker = Some(Rc::clone(&write_tracker));
+        LazyTree {
+            tree,
+            write_tracker,
+            states: Default::default(),
+        }
+    }
+
+    /// Discard the previous result of a node.
+    fn invalidate(&self, id: NodeId) {
+        self.states.borrow_mut().remove(&id);
+    }
+
+    /// Discard the previous results of all nodes.
+    fn invalidate_all(&self) {
+        self.states.borrow_mut().clear();
+    }
+
+    /// Whether the node must be executed, or the status of its previous execution if not.
+    fn previous_status(&self, id: NodeId) -> Result<Option<ExecutionStatus>, NodeError> {
+        let (status, children) = {
+            let states = self.states.borrow();
+            let Some(state) = states.get(&id) else {
+                return Ok(None);
+            };
+            let changed = state.inputs.iter().any(|(blackboard, name)| {
+                self.write_tracker
+                    .changed_since(*blackboard, name, state.generation)
+            });
+            if changed {
+                return Ok(None);
+            }
+            (state.status, state.children.clone())
+        };
+        let node = self
+            .tree
+            .node_ref(id)
+            .ok_or_else(|| format!("node {id:?} does not exist"))?;
+        if node.try_borrow()?.always_execute() {
+            return Ok(None);
+        }
+        for child in children {
+            if self.previous_status(child)?.is_none() {
+                return Ok(None);
+            }
+        }
+        Ok(Some(status))
+    }
+
+    fn node_inputs(&self, id: NodeId) -> Result<Vec<(BlackboardId, PortName)>, BetulaError> {
+        Ok(self
+            .tree
+            .node_input_connections(id)?
+            .iter()
+            .map(|c| self.tree.resolve_blackboard_port(&c.blackboard))
+            .map(|p| (p.blackboard(), p.name()))
+            .collect())
+    }
+}
+
+impl Tree for LazyTree {
+    fn new() -> Self {
+        LazyTree::new()
+    }
+
+    fn nodes(&self) -> Vec<NodeId> {
+        self.tree.nodes()
+    }
+    fn node_ref(&self, id: NodeId) -> Option<&RefCell<Box<dyn Node>>> {
+        self.tree.node_ref(id)
+    }
+    fn node_mut(&mut self, id: NodeId) -> Option<&mut dyn Node> {
+        // The node may be modified, so its previous result can't be used.
+        self.invalidate(id);
+        self.tree.node_mut(id)
+    }
+    fn remove_node(&mut self, id: NodeId) -> Result<Box<dyn Node>, BetulaError> {
+        self.invalidate_all();
+        self.tree.remove_node(id)
+    }
+
+    fn add_node_boxed(&mut self, id: NodeId, node: Box<dyn Node>) -> Result<NodeId, BetulaError> {
+        self.tree.add_node_boxed(id, node)
+    }
+
+    fn children(&self, id: NodeId) -> Result<Vec<NodeId>, BetulaError> {
+        self.tree.children(id)
+    }
+
+    fn set_children(&mut self, parent: NodeId, children: &[NodeId]) -> Result<(), BetulaError> {
+        self.invalidate(parent);
+        self.tree.set_children(parent, children)
+    }
+
+    fn execute(&self, id: NodeId) -> Result<ExecutionStatus, NodeError> {
+        self.execute_observed(id, &())
+    }
+
+    fn execute_observed(
+        &self,
+        id: NodeId,
+        observer: &dyn ExecutionObserver,
+    ) -> Result<ExecutionStatus, NodeError> {
+        if self.tree.setup_stale_ports() {
+            // Nodes may read from different blackboards now.
+            self.invalidate_all();
+        }
+        if let Some(status) = self.previous_status(id)? {
+            observer.returned(id, &Ok(status));
+            return Ok(status);
+        }
+        self.invalidate(id);
+
+        let generation = self.write_tracker.generation();
+        let inputs = self.node_inputs(id)?;
+        let mut n = self
+            .tree
+            .node_ref(id)
+            .ok_or_else(|| format!("node {id:?} does not exist"))?
+            .try_borrow_mut()?;
+        let context = LazyTreeContext {
+            this_node: id,
+            tree: self,
+            observer,
+            ran: Default::default(),
+        };
+        observer.started(id);
+        let r = n
+            .execute(&context)
+            .map_err(|e| ExecutionError::returned_by(e, id));
+        observer.returned(id, &r);
+        let ran = context.ran.into_inner();
+        let results: Vec<(NodeId, ExecutionStatus)> = ran
+            .iter()
+            .filter_map(|(child, status)| Some((*child, (*status)?)))
+            .collect();
+        for child in self.tree.running.update(id, &results, &r) {
+            self.halt_recursive(child)?;
+        }
+        let status = r?;
+
+        self.states.borrow_mut().insert(
+            id,
+            LazyNodeState {
+                status,
+                generation,
+                inputs,
+                children: ran.into_iter().map(|(child, _)| child).collect(),
+            },
+        );
+        Ok(status)
+    }
+
+    fn reset_recursive(&self, id: NodeId) -> Result<(), NodeError> {
+        self.invalidate(id);
+        let mut n = self
+            .tree
+            .node_ref(id)
+            .ok_or_else(|| format!("node {id:?} does not exist"))?
+            .try_borrow_mut()?;
+        let context = LazyTreeContext {
+            this_node: id,
+            tree: self,
+            observer: &(),
+            ran: Default::default(),
+        };
+
+        n.reset_recursive(&context)
+    }
+
+    fn halt_recursive(&self, id: NodeId) -> Result<(), NodeError> {
+        // The node stops what it was doing, so its previous result can't be used.
+        self.invalidate(id);
+        self.tree.running.remove(id);
+        let mut n = self
+            .tree
+            .node_ref(id)
+            .ok_or_else(|| format!("node {id:?} does not exist"))?
+            .try_borrow_mut()?;
+        let context = LazyTreeContext {
+            this_node: id,
+            tree: self,
+            observer: &(),
+            ran: Default::default(),
+        };
+
+        n.halt(&context)
+    }
+
+    fn blackboards(&self) -> Vec<BlackboardId> {
+        self.tree.blackboards()
+    }
+
+    fn blackboard_ref(&self, id: BlackboardId) -> Option<&RefCell<Box<dyn Blackboard>>> {
+        self.tree.blackboard_ref(id)
+    }
+    fn blackboard_mut(&mut self, id: BlackboardId) -> Option<&mut dyn Blackboard> {
+        // Any value may be changed, we can't track what happens to it.
+        self.write_tracker.blackboard_changed(id);
+        self.tree.blackboard_mut(id)
+    }
+
+    fn add_blackboard_boxed(
+        &mut self,
+        id: BlackboardId,
+        blackboard: Box<dyn Blackboard>,
+    ) -> Result<BlackboardId, BetulaError> {
+        self.tree.add_blackboard_boxed(id, blackboard)
+    }
+
+    fn remove_blackboard(&mut self, id: BlackboardId) -> Result<Box<dyn Blackboard>, BetulaError> {
+        self.invalidate_all();
+        self.tree.remove_blackboard(id)
+    }
+
+    fn connect_port(&mut self, connection: &PortConnection) -> Result<(), BetulaError> {
+        self.invalidate(connection.node.node());
+        if connection.node.direction() == PortDirection::Output {
+            // The output may have created the value, or be written to from now on.
+            let port = self.tree.resolve_blackboard_port(&connection.blackboard);
+            self.write_tracker
+                .port_changed(port.blackboard(), &port.name());
+            // A created value may shadow the value of a parent blackboard that other nodes read.
+            if self.tree.blackboards.values().any(|b| b.parent.is_some()) {
+                self.invalidate_all();
+            }
+        }
+        self.tree.connect_port(connection)
+    }
+
+    fn disconnect_port(&mut self, connection: &PortConnection) -> Result<(), BetulaError> {
+        self.invalidate(connection.node.node());
+        self.tree.disconnect_port(connection)
+    }
+
+    fn blackboard_connections(&self, id: BlackboardId) -> Vec<PortConnection> {
+        self.tree.blackboard_connections(id)
+    }
+
+    fn roots(&self) -> Vec<NodeId> {
+        self.tree.roots()
+    }
+
+    fn set_roots(&mut self, new_roots: &[NodeId]) -> Result<(), BetulaError> {
+        self.tree.set_roots(new_roots)
+    }
+
+    fn set_blackboard_name(
+        &mut self,
+        blackboard_id: BlackboardId,
+        name: Option<&str>,
+    ) -> Result<(), BetulaError> {
+        self.tree.set_blackboard_name(blackboard_id, name)
+    }
+
+    fn blackboard_name(&self, blackboard_id: BlackboardId) -> Result<Option<String>, BetulaError> {
+        self.tree.blackboard_name(blackboard_id)
+    }
+
+    fn set_blackboard_parent(
+        &mut self,
+        blackboard_id: BlackboardId,
+        parent: Option<BlackboardId>,
+    ) -> Result<(), BetulaError> {
+        self.invalidate_all();
+        self.tree.set_blackboard_parent(blackboard_id, parent)
+    }
+
+    fn blackboard_parent(
+        &self,
+        blackboard_id: BlackboardId,
+    ) -> Result<Option<BlackboardId>, BetulaError> {
+        self.tree.blackboard_parent(blackboard_id)
+    }
+
+    fn set_blackboard_persistent_ports(
+        &mut self,
+        blackboard_id: BlackboardId,
+        ports: &[PortName],
+    ) -> Result<(), BetulaError> {
+        self.tree
+            .set_blackboard_persistent_ports(blackboard_id, ports)
+    }
+
+    fn blackboard_persistent_ports(
+        &self,
+        blackboard_id: BlackboardId,
+    ) -> Result<Vec<PortName>, BetulaError> {
+        self.tree.blackboard_persistent_ports(blackboard_id)
+    }
+
+    fn set_node_name(&mut self, id: NodeId, name: Option<&str>) -> Result<(), BetulaError> {
+        self.tree.set_node_name(id, name)
+    }
+
+    fn node_name(&self, id: NodeId) -> Result<Option<String>, BetulaError> {
+        self.tree.node_name(id)
+    }
+
+    fn set_directory(&mut self, directory: Option<&std::path::Path>) {
+        self.invalidate_all();
+        self.tree.set_directory(directory)
+    }
+
+    fn directory(&self) -> Option<&std::path::Path> {
+        self.tree.directory()
+    }
+
+    fn clock(&self) -> &dyn Clock {
+        self.tree.clock()
+    }
+
+    fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.invalidate_all();
+        self.tree.set_clock(clock)
+    }
+
+    fn conversions(&self) -> &Conversions {
+        self.tree.conversions()
+    }
+
+    fn set_conversions(&mut self, conversions: Conversions) -> Result<(), BetulaError> {
+        self.invalidate_all();
+        self.tree.set_conversions(conversions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::BasicBlackboard;
+    use crate::blackboard::{Input, Output};
+    use crate::node_prelude::*;
+    use uuid::Uuid;
+
+    /// Node that counts its executions and returns the status from the blackboard.
+    #[derive(Debug, Default)]
+    struct CountingNode {
+        executions: Rc<Cell<usize>>,
+        input: Input<ExecutionStatus>,
+        always: bool,
+    }
+    impl Node for CountingNode {
+        fn execute(&mut self, ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+            self.executions.set(self.executions.get() + 1);
+            for i in 0..ctx.children() {
+                ctx.run(i)?;
+            }
+            self.input.get()
+        }
+        fn ports(&self) -> Result<Vec<Port>, NodeError> {
+            Ok(vec![Port::input::<ExecutionStatus>("status")])
+        }
+        fn setup_inputs(
+            &mut self,
+            interface: &mut dyn BlackboardInputInterface,
+        ) -> Result<(), NodeError> {
+            self.input = interface.input::<ExecutionStatus>("status")?;
+            Ok(())
+        }
+        fn always_execute(&self) -> bool {
+            self.always
+        }
+        fn static_type() -> NodeType {
+            "counting".into()
+        }
+        fn node_type(&self) -> NodeType {
+            Self::static_type()
+        }
+    }
+
+    /// Node that writes a value it is given.
+    #[derive(Debug)]
+    struct WriteNode {
+        value: Rc<Cell<ExecutionStatus>>,
+        output: Output<ExecutionStatus>,
+    }
+    impl Node for WriteNode {
+        fn execute(&mut self, _ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+            self.output.set(self.value.get())?;
+            Ok(ExecutionStatus::Success)
+        }
+        fn ports(&self) -> Result<Vec<Port>, NodeError> {
+            Ok(vec![Port::output::<ExecutionStatus>("status")])
+        }
+        fn setup_outputs(
+            &mut self,
+            interface: &mut dyn BlackboardOutputInterface,
+        ) -> Result<(), NodeError> {
+            self.output =
+                interface.output::<ExecutionStatus>("status", ExecutionStatus::Failure)?;
+            Ok(())
+        }
+        fn always_execute(&self) -> bool {
+            true
+        }
+        fn static_type() -> NodeType {
+            "write".into()
+        }
+        fn node_type(&self) -> NodeType {
+            Self::static_type()
+        }
+    }
+
+    #[test]
+    fn test_lazy_execution() -> Result<(), NodeError> {
+        let mut tree = LazyTree::new();
+        let bb = tree.add_blackboard_boxed(
+            BlackboardId(Uuid::new_v4()),
+            Box::new(BasicBlackboard::default()),
+        )?;
+
+        let value = Rc::new(Cell::new(ExecutionStatus::Running));
+        let write = tree.add_node_boxed(
+            NodeId(Uuid::new_v4()),
+            Box::new(WriteNode {
+                value: value.clone(),
+                output: Default::default(),
+            }),
+        )?;
+        tree.connect_port_to_blackboard(&tree.node_ports(write)?[0], bb)?;
+
+        let parent_count = Rc::new(Cell::new(0));
+        let parent = tree.add_node_boxed(
+            NodeId(Uuid::new_v4()),
+            Box::new(CountingNode {
+                executions: parent_count.clone(),
+                ..Default::default()
+            }),
+        )?;
+        let child_count = Rc::new(Cell::new(0));
+        let child = tree.add_node_boxed(
+            NodeId(Uuid::new_v4()),
+            Box::new(CountingNode {
+                executions: child_count.clone(),
+                ..Default::default()
+            }),
+        )?;
+        tree.set_children(parent, &[child])?;
+        tree.connect_port_to_blackboard(&tree.node_ports(parent)?[0], bb)?;
+        tree.connect_port_to_blackboard(&tree.node_ports(child)?[0], bb)?;
+
+        // First execution runs everything.
+        assert_eq!(tree.execute(write)?, ExecutionStatus::Success);
+        assert_eq!(tree.execute(parent)?, ExecutionStatus::Running);
+        assert_eq!((parent_count.get(), child_count.get()), (1, 1));
+
+        // Writing the same value doesn't cause re-evaluation.
+        assert_eq!(tree.execute(write)?, ExecutionStatus::Success);
+        assert_eq!(tree.execute(parent)?, ExecutionStatus::Running);
+        assert_eq!((parent_count.get(), child_count.get()), (1, 1));
+
+        // Changing the value does.
+        value.set(ExecutionStatus::Success);
+        assert_eq!(tree.execute(write)?, ExecutionStatus::Success);
+        assert_eq!(tree.execute(parent)?, ExecutionStatus::Success);
+        assert_eq!((parent_count.get(), child_count.get()), (2, 2));
+
+        // Disconnect the parent's input, now only the child determines re-evaluation.
+        let parent_port = tree.node_ports(parent)?[0].clone();
+        tree.disconnect_port(&PortConnection::new(
+            parent_port.clone(),
+            crate::blackboard::BlackboardPort::new(bb, &parent_port.name()),
+        ))?;
+        assert!(tree.execute(parent).is_err());
+        tree.connect_port_to_blackboard(&parent_port, bb)?;
+        assert_eq!(tree.execute(parent)?, ExecutionStatus::Success);
+        assert_eq!((parent_count.get(), child_count.get()), (4, 2));
+        assert_eq!(tree.execute(parent)?, ExecutionStatus::Success);
+        assert_eq!((parent_count.get(), child_count.get()), (4, 2));
+
+        // The child always executing means the parent executes as well.
+        tree.node_mut(child)
+            .ok_or("node not found")?
+            .as_any_mut()
+            .downcast_mut::<CountingNode>()
+            .ok_or("wrong type")?
+            .always = true;
+        assert_eq!(tree.execute(parent)?, ExecutionStatus::Success);
+        assert_eq!(tree.execute(parent)?, ExecutionStatus::Success);
+        assert_eq!((parent_count.get(), child_count.get()), (6, 4));
+
+        // Resetting discards the previous results.
+        tree.node_mut(child)
+            .ok_or("node not found")?
+            .as_any_mut()
+            .downcast_mut::<CountingNode>()
+            .ok_or("wrong type")?
+            .always = false;
+        assert_eq!(tree.execute(parent)?, ExecutionStatus::Success);
+        assert_eq!((parent_count.get(), child_count.get()), (7, 5));
+        tree.reset_recursive(parent)?;
+        assert_eq!(tree.execute(parent)?, ExecutionStatus::Success);
+        assert_eq!((parent_count.get(), child_count.get()), (8, 6));
+        Ok(())
+    }
+}
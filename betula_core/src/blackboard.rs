@@ -1,4 +1,5 @@
 use crate::as_any::{AsAny, AsAnyHelper};
+use crate::error::{ErrorKind, ExecutionError};
 use crate::{BetulaError, NodeError, NodeId, Uuid};
 use serde::{Deserialize, Serialize};
 use std::any::{Any, TypeId};
@@ -53,6 +54,16 @@ impl PortType {
             type_name: std::any::type_name::<T>(),
         }
     }
+    /// The port type of the value held in a box, as opposed to the box itself.
+    pub fn of_value(value: &dyn Chalkable) -> Self {
+        PortType {
+            id: value.as_any_type_id(),
+            type_name: value.as_any_type_name(),
+        }
+    }
+    pub fn type_id(&self) -> TypeId {
+        self.id
+    }
     pub fn type_name(&self) -> &'static str {
         self.type_name
     }
@@ -76,22 +87,75 @@ pub enum PortDirection {
     Output,
 }
 
+/// The default value of an optional input port.
+///
+/// Values can't be ordered or hashed, defaults are compared by their type and debug representation.
+#[derive(Clone, Debug)]
+pub struct PortDefault(Value);
+
+impl PortDefault {
+    pub fn new(value: Value) -> Self {
+        PortDefault(value)
+    }
+    pub fn value(&self) -> Value {
+        self.0.clone()
+    }
+    fn key(&self) -> (&'static str, String) {
+        ((*self.0).as_any_type_name(), format!("{:?}", self.0))
+    }
+}
+
+impl PartialEq for PortDefault {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+impl Eq for PortDefault {}
+
+impl Ord for PortDefault {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+impl PartialOrd for PortDefault {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::hash::Hash for PortDefault {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
+}
+
 /// A port for a node.
 ///
-/// Ports have a name, direction and type.
+/// Ports have a name, direction and type. Input ports may be optional, if they are not connected
+/// the tree provides their default value instead, see [`Port::input_optional`].
 #[derive(Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
 pub struct Port {
     port_type: PortType,
     direction: PortDirection,
     name: PortName,
+    default: Option<PortDefault>,
 }
 
 impl Port {
+    pub fn new(port_type: PortType, direction: PortDirection, name: impl Into<PortName>) -> Self {
+        Port {
+            port_type,
+            direction,
+            name: name.into(),
+            default: None,
+        }
+    }
     pub fn input<T: 'static>(name: impl Into<PortName>) -> Self {
         Port {
             port_type: PortType::new::<T>(),
             direction: PortDirection::Input,
             name: name.into(),
+            default: None,
         }
     }
     pub fn output<T: 'static>(name: impl Into<PortName>) -> Self {
@@ -99,9 +163,39 @@ impl Port {
             port_type: PortType::new::<T>(),
             direction: PortDirection::Output,
             name: name.into(),
+            default: None,
+        }
+    }
+
+    /// An input port that reads the default value if it is not connected.
+    ///
+    /// ```
+    /// # use betula_core::blackboard::Port;
+    /// let port = Port::input_optional::<f64>("threshold", 0.5);
+    /// assert!(port.is_optional());
+    /// ```
+    pub fn input_optional<T: 'static + Chalkable + Clone>(
+        name: impl Into<PortName>,
+        default: T,
+    ) -> Self {
+        Port {
+            port_type: PortType::new::<T>(),
+            direction: PortDirection::Input,
+            name: name.into(),
+            default: Some(PortDefault::new(Box::new(default))),
         }
     }
 
+    /// Whether this port is optional, optional ports don't have to be connected.
+    pub fn is_optional(&self) -> bool {
+        self.default.is_some()
+    }
+
+    /// The default value of an optional port.
+    pub fn default_value(&self) -> Option<Value> {
+        self.default.as_ref().map(|v| v.value())
+    }
+
     pub fn into_node_port(self, node: NodeId) -> NodePort {
         NodePort {
             node,
@@ -196,6 +290,37 @@ pub trait OutputTrait: std::fmt::Debug {
 pub trait InputTrait: std::fmt::Debug {
     type InputItem;
     fn get(&self) -> Result<Self::InputItem, NodeError>;
+
+    /// When the current value was written, see [`PortGeneration`].
+    fn generation(&self) -> Result<PortGeneration, NodeError> {
+        Err("input does not track generations".into())
+    }
+
+    /// Whether the value was written after the provided generation.
+    ///
+    /// Nodes can store [`PortGeneration::generation`] of the value they last processed and use
+    /// this to only process new values.
+    fn changed_since(&self, generation: u64) -> Result<bool, NodeError> {
+        Ok(self.generation()?.generation > generation)
+    }
+
+    /// Get the value, or the default of the type if the port is missing.
+    ///
+    /// Optional ports that aren't connected already provide their default through [`Self::get`],
+    /// this can be used for inputs that may not be on the blackboard at all.
+    fn get_or_default(&self) -> Result<Self::InputItem, NodeError>
+    where
+        Self::InputItem: Default,
+    {
+        match self.get() {
+            Err(e)
+                if ExecutionError::find(&e).map(|e| e.kind()) == Some(ErrorKind::MissingPort) =>
+            {
+                Ok(Default::default())
+            }
+            r => r,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -205,13 +330,13 @@ struct DefaultOutputInput<T> {
 impl<T: std::fmt::Debug + 'static> OutputTrait for DefaultOutputInput<T> {
     type OutputItem = T;
     fn set(&self, _v: Self::OutputItem) -> Result<(), NodeError> {
-        Err("output is not initialised".into())
+        Err(ExecutionError::new(ErrorKind::MissingPort, "output is not initialised").into())
     }
 }
 impl<T: std::fmt::Debug + 'static> InputTrait for DefaultOutputInput<T> {
     type InputItem = T;
     fn get(&self) -> Result<Self::InputItem, NodeError> {
-        Err("input is not initialised".into())
+        Err(ExecutionError::new(ErrorKind::MissingPort, "input is not initialised").into())
     }
 }
 
@@ -284,6 +409,20 @@ pub type ValueCreator = Box<dyn Fn() -> Value>;
 /// Boxed function to read values from the blackboard.
 pub type Read = Box<dyn Fn() -> Result<Value, NodeError>>;
 
+/// When a value on the blackboard was written.
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub struct PortGeneration {
+    /// Increases on every write, the generation of a port is always increasing.
+    pub generation: u64,
+    /// The tick of the blackboard at the time of the write, see [`Blackboard::set_tick`].
+    pub tick: u64,
+}
+
+/// Boxed function to read the generation of a value on the blackboard.
+pub type ReadGeneration = Box<dyn Fn() -> Result<PortGeneration, NodeError>>;
+
 /// Boxed function to write values to the blackboard. Deliberately does NOT
 /// return the previous value to ensure purity.
 pub type Write = Box<dyn Fn(Value) -> Result<(), NodeError>>;
@@ -305,6 +444,14 @@ pub trait BlackboardOutputInterface {
 /// Don't interact with this directly, do so through [`SetupInput`].
 pub trait BlackboardInputInterface {
     fn reader(&mut self, id: &TypeId, key: &PortName) -> Result<Read, NodeError>;
+
+    /// Provides the function to read the generation of the value, see [`InputTrait::generation`].
+    fn generation_reader(&mut self, key: &PortName) -> Result<ReadGeneration, NodeError> {
+        let _ = key;
+        Ok(Box::new(|| {
+            Err("blackboard does not track generations".into())
+        }))
+    }
 }
 
 /// Interface blackboards must provide.
@@ -325,6 +472,15 @@ pub trait Blackboard:
     /// Get the value for the provided port name.
     fn get(&self, port: &PortName) -> Option<Value>;
 
+    /// Get when the value for the provided port name was last written.
+    fn generation(&self, port: &PortName) -> Option<PortGeneration>;
+
+    /// Set the tick that is recorded with writes from now on.
+    ///
+    /// The tick is not used by the blackboard itself, it is usually the number of times the tree
+    /// was executed.
+    fn set_tick(&mut self, tick: u64);
+
     /// Remove a port from the blackboard.
     fn remove(&mut self, port: &PortName) -> Option<Value>;
 
@@ -398,26 +554,35 @@ pub trait SetupInput: BlackboardInputInterface {
     ) -> Result<Input<T>, NodeError> {
         let key: PortName = key.into();
         let reader = BlackboardInputInterface::reader(self, &TypeId::of::<T>(), &key)?;
+        let generation = BlackboardInputInterface::generation_reader(self, &key)?;
 
         struct InputFor<TT> {
             key: PortName,
             type_name: String,
             z: std::marker::PhantomData<TT>,
             reader: Read,
+            generation: ReadGeneration,
         }
         impl<TT: 'static + Chalkable + Clone> InputTrait for InputFor<TT> {
             type InputItem = TT;
             fn get(&self) -> Result<TT, NodeError> {
                 let boxed_value = (self.reader)()?;
                 let v = (*boxed_value).downcast_ref::<TT>().ok_or_else(|| {
-                    format!(
-                        "could not downcast {:?} to {:?}",
-                        (*boxed_value).as_any_type_name(),
-                        std::any::type_name::<TT>()
+                    ExecutionError::new(
+                        ErrorKind::TypeMismatch,
+                        format!(
+                            "could not downcast {:?} to {:?}",
+                            (*boxed_value).as_any_type_name(),
+                            std::any::type_name::<TT>()
+                        ),
                     )
                 })?;
                 Ok((*v).clone())
             }
+
+            fn generation(&self) -> Result<PortGeneration, NodeError> {
+                (self.generation)()
+            }
         }
 
         impl<TT: 'static + Chalkable + Clone> std::fmt::Debug for InputFor<TT> {
@@ -428,6 +593,7 @@ pub trait SetupInput: BlackboardInputInterface {
 
         Ok(Box::new(InputFor::<T> {
             reader,
+            generation,
             z: std::marker::PhantomData,
             type_name: std::any::type_name::<T>().to_string(),
             key: key.clone(),
@@ -0,0 +1,92 @@
+//! Clocks that provide the current time to nodes.
+//!
+//! Nodes obtain the time through [`crate::RunContext::clock`] instead of reading the system time,
+//! this allows running a tree on simulated time by providing a [`ManualClock`] to the tree:
+//! ```
+//! # use betula_core::clock::{Clock, ManualClock};
+//! # use betula_core::basic::BasicTree;
+//! # use betula_core::Tree;
+//! let clock = ManualClock::new(100.0);
+//! let mut tree = BasicTree::new();
+//! tree.set_clock(Box::new(clock.clone()));
+//! clock.advance(0.5);
+//! assert_eq!(tree.clock().now()?, 100.5);
+//! # Ok::<(), betula_core::NodeError>(())
+//! ```
+use crate::NodeError;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Trait for a source of time.
+pub trait Clock: std::fmt::Debug {
+    /// The current time in seconds since the unix epoch.
+    fn now(&self) -> Result<f64, NodeError>;
+
+    /// Clone the clock, the clone must report the same time, this allows nested trees to use
+    /// the clock of the tree they run in.
+    fn clone_boxed(&self) -> Box<dyn Clock>;
+}
+
+impl Default for Box<dyn Clock> {
+    fn default() -> Self {
+        Box::new(SystemClock)
+    }
+}
+
+/// Clock that reads the system time.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Result<f64, NodeError> {
+        use std::time::{SystemTime, UNIX_EPOCH};
+        let unix = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        Ok(unix.as_secs_f64())
+    }
+
+    fn clone_boxed(&self) -> Box<dyn Clock> {
+        Box::new(*self)
+    }
+}
+
+/// Clock that only advances when told to.
+///
+/// Clones share the same time, so a clone can be kept to advance the clock held by a tree.
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    time: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Create a clock starting at the provided time.
+    pub fn new(start: f64) -> Self {
+        ManualClock {
+            time: Arc::new(AtomicU64::new(start.to_bits())),
+        }
+    }
+
+    /// Set the current time.
+    pub fn set(&self, time: f64) {
+        self.time.store(time.to_bits(), Ordering::Relaxed);
+    }
+
+    /// The current time.
+    pub fn time(&self) -> f64 {
+        f64::from_bits(self.time.load(Ordering::Relaxed))
+    }
+
+    /// Advance the time by the provided duration in seconds.
+    pub fn advance(&self, duration: f64) {
+        self.set(self.time() + duration);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Result<f64, NodeError> {
+        Ok(self.time())
+    }
+
+    fn clone_boxed(&self) -> Box<dyn Clock> {
+        Box::new(self.clone())
+    }
+}
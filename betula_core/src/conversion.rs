@@ -0,0 +1,213 @@
+//! Conversions between the types of ports.
+//!
+//! Blackboards only provide values of the type they hold. When an input port is connected to a
+//! blackboard value of another type, the tree reads the blackboard's type and converts it with a
+//! conversion from [`Conversions`], if one exists:
+//! ```
+//! # use betula_core::conversion::Conversions;
+//! # use betula_core::blackboard::Value;
+//! # use std::any::TypeId;
+//! let mut conversions = Conversions::new();
+//! conversions.add::<u8, f64>(|v| Ok(*v as f64));
+//! let convert = conversions.get(TypeId::of::<u8>(), TypeId::of::<f64>()).unwrap();
+//! let value: Value = Box::new(3u8);
+//! assert!(convert(&*value).unwrap().is_equal(&3.0f64));
+//! ```
+use crate::as_any::AsAnyHelper;
+use crate::blackboard::{Chalkable, Value};
+use crate::error::{ErrorKind, ExecutionError};
+use crate::{ExecutionStatus, NodeError};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Function that converts a value into a value of another type.
+pub type Conversion = Arc<dyn Fn(&dyn Chalkable) -> Result<Value, NodeError> + Send + Sync>;
+
+#[derive(Clone)]
+struct ConversionEntry {
+    from: &'static str,
+    to: &'static str,
+    convert: Conversion,
+}
+
+/// Registry of conversions between types.
+///
+/// A new registry holds conversions between `f64` and `i64`, between those and `String` and
+/// between [`ExecutionStatus`] and `String`.
+#[derive(Clone)]
+pub struct Conversions {
+    conversions: HashMap<(TypeId, TypeId), ConversionEntry>,
+}
+
+impl Conversions {
+    /// Create a registry with the built-in conversions.
+    pub fn new() -> Self {
+        let mut conversions = Self::empty();
+        conversions.add::<i64, f64>(|v| Ok(*v as f64));
+        // Truncates towards zero, saturating at the bounds of i64.
+        conversions.add::<f64, i64>(|v| Ok(*v as i64));
+        conversions.add::<i64, String>(|v| Ok(v.to_string()));
+        conversions.add::<f64, String>(|v| Ok(v.to_string()));
+        conversions.add::<String, i64>(|v| Ok(v.trim().parse()?));
+        conversions.add::<String, f64>(|v| Ok(v.trim().parse()?));
+        conversions.add::<ExecutionStatus, String>(|v| Ok(format!("{v:?}")));
+        conversions.add::<String, ExecutionStatus>(|v| match v.trim() {
+            "Running" => Ok(ExecutionStatus::Running),
+            "Failure" => Ok(ExecutionStatus::Failure),
+            "Success" => Ok(ExecutionStatus::Success),
+            _ => Err(format!("{v:?} is not an execution status").into()),
+        });
+        conversions
+    }
+
+    /// Create a registry without any conversions.
+    pub fn empty() -> Self {
+        Conversions {
+            conversions: Default::default(),
+        }
+    }
+
+    /// Add a conversion from `F` to `T`, replacing any existing conversion between these types.
+    pub fn add<F: 'static + Chalkable, T: 'static + Chalkable>(
+        &mut self,
+        convert: impl Fn(&F) -> Result<T, NodeError> + Send + Sync + 'static,
+    ) {
+        let convert: Conversion = Arc::new(move |value: &dyn Chalkable| {
+            let value = value.downcast_ref::<F>().ok_or_else(|| {
+                ExecutionError::new(
+                    ErrorKind::TypeMismatch,
+                    format!(
+                        "conversion expected {} got {}",
+                        std::any::type_name::<F>(),
+                        value.as_any_type_name()
+                    ),
+                )
+            })?;
+            let converted: Value = Box::new(
+                convert(value)
+                    .map_err(|e| ExecutionError::new(ErrorKind::TypeMismatch, e.to_string()))?,
+            );
+            Ok(converted)
+        });
+        self.conversions.insert(
+            (TypeId::of::<F>(), TypeId::of::<T>()),
+            ConversionEntry {
+                from: std::any::type_name::<F>(),
+                to: std::any::type_name::<T>(),
+                convert,
+            },
+        );
+    }
+
+    /// Get the conversion between two types, if there is one.
+    pub fn get(&self, from: TypeId, to: TypeId) -> Option<Conversion> {
+        self.conversions
+            .get(&(from, to))
+            .map(|e| Arc::clone(&e.convert))
+    }
+
+    /// Whether values of type `from` can be converted to `to`.
+    pub fn can_convert(&self, from: TypeId, to: TypeId) -> bool {
+        from == to || self.conversions.contains_key(&(from, to))
+    }
+}
+
+impl Default for Conversions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for Conversions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names: Vec<_> = self
+            .conversions
+            .values()
+            .map(|e| format!("{} -> {}", e.from, e.to))
+            .collect();
+        names.sort();
+        f.debug_list().entries(names).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::{BasicBlackboard, BasicTree};
+    use crate::node_prelude::*;
+    use crate::{BlackboardId, NodeId, Tree};
+    use uuid::Uuid;
+
+    /// Succeeds if the input is positive.
+    #[derive(Debug, Default)]
+    struct PositiveNode {
+        input: Input<f64>,
+    }
+    impl Node for PositiveNode {
+        fn execute(&mut self, _ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+            if self.input.get()? > 0.0 {
+                Ok(ExecutionStatus::Success)
+            } else {
+                Ok(ExecutionStatus::Failure)
+            }
+        }
+        fn ports(&self) -> Result<Vec<Port>, NodeError> {
+            Ok(vec![Port::input::<f64>("value")])
+        }
+        fn setup_inputs(
+            &mut self,
+            interface: &mut dyn BlackboardInputInterface,
+        ) -> Result<(), NodeError> {
+            self.input = interface.input::<f64>("value")?;
+            Ok(())
+        }
+        fn static_type() -> NodeType {
+            "positive".into()
+        }
+        fn node_type(&self) -> NodeType {
+            Self::static_type()
+        }
+    }
+
+    #[test]
+    fn test_converting_reader() -> Result<(), NodeError> {
+        let mut tree = BasicTree::new();
+        let node =
+            tree.add_node_boxed(NodeId(Uuid::new_v4()), Box::new(PositiveNode::default()))?;
+        let bb = tree.add_blackboard_boxed(
+            BlackboardId(Uuid::new_v4()),
+            Box::new(BasicBlackboard::default()),
+        )?;
+        let blackboard = tree.blackboard_mut(bb).ok_or("missing blackboard")?;
+        blackboard.set(&"value".into(), Box::new(3i64))?;
+        let port = tree.node_ports(node)?.remove(0);
+        tree.connect_port_to_blackboard(&port, bb)?;
+        assert_eq!(tree.execute(node)?, ExecutionStatus::Success);
+        assert!(tree.validate().is_empty());
+
+        // The conversion reads the current value.
+        let blackboard = tree.blackboard_mut(bb).ok_or("missing blackboard")?;
+        blackboard.set(&"value".into(), Box::new(-3i64))?;
+        assert_eq!(tree.execute(node)?, ExecutionStatus::Failure);
+
+        // Without conversions, the types mismatch.
+        assert!(tree.set_conversions(Conversions::empty()).is_err());
+        let error = tree.execute(node).expect_err("no conversion");
+        let error = ExecutionError::find(&error).ok_or("not an execution error")?;
+        assert_eq!(error.kind(), ErrorKind::MissingPort);
+        assert_eq!(tree.validate().len(), 1);
+
+        // Strings that can't be parsed fail in the conversion, the connection is set up again.
+        let blackboard = tree.blackboard_mut(bb).ok_or("missing blackboard")?;
+        blackboard.remove(&"value".into());
+        blackboard.set(&"value".into(), Box::new("x".to_string()))?;
+        let mut conversions = Conversions::empty();
+        conversions.add::<String, f64>(|v| Ok(v.parse()?));
+        tree.set_conversions(conversions)?;
+        let error = tree.execute(node).expect_err("can't parse");
+        let error = ExecutionError::find(&error).ok_or("not an execution error")?;
+        assert_eq!(error.kind(), ErrorKind::TypeMismatch);
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+//! Support for nodes that derive their boilerplate with `betula_derive`.
+//!
+//! The `Node` derive of `betula_derive` implements [`DerivedNode`] from the attributes on the
+//! fields of a node, the `node_impl` attribute forwards the methods of [`crate::Node`] to it.
+use crate::blackboard::{BlackboardInputInterface, BlackboardOutputInterface, Port};
+use crate::{NodeConfig, NodeError, NodeType};
+
+/// Methods of [`crate::Node`] that are generated from the fields of a node.
+pub trait DerivedNode {
+    /// The value for [`crate::Node::static_type`].
+    fn derived_static_type() -> NodeType
+    where
+        Self: Sized;
+
+    /// The value for [`crate::Node::ports`].
+    fn derived_ports(&self) -> Result<Vec<Port>, NodeError>;
+
+    /// Implementation of [`crate::Node::setup_inputs`].
+    fn derived_setup_inputs(
+        &mut self,
+        interface: &mut dyn BlackboardInputInterface,
+    ) -> Result<(), NodeError>;
+
+    /// Implementation of [`crate::Node::setup_outputs`].
+    fn derived_setup_outputs(
+        &mut self,
+        interface: &mut dyn BlackboardOutputInterface,
+    ) -> Result<(), NodeError>;
+
+    /// Implementation of [`crate::Node::get_config`], nodes without config return `None`.
+    fn derived_get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
+        Ok(None)
+    }
+
+    /// Implementation of [`crate::Node::set_config`], nodes without config ignore it.
+    fn derived_set_config(&mut self, config: &dyn NodeConfig) -> Result<(), NodeError> {
+        let _ = config;
+        Ok(())
+    }
+}
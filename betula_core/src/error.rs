@@ -0,0 +1,214 @@
+//! Structured errors that record where in the tree they originated.
+//!
+//! A [`NodeError`] is a boxed error, such that nodes can return any error. When a node returns an
+//! error, the tree wraps it in an [`ExecutionError`] holding the id of that node. As the error is
+//! returned by the ancestors, their ids are appended, the origin of the error is therefore known
+//! even though every ancestor returns the same error.
+//!
+//! Errors can be created with a kind directly, the node id is filled in by the tree:
+//! ```
+//! # use betula_core::error::{ErrorKind, ExecutionError};
+//! # use betula_core::NodeError;
+//! let error: NodeError = ExecutionError::new(ErrorKind::Config, "speed must be positive").into();
+//! assert_eq!(ExecutionError::find(&error).unwrap().kind(), ErrorKind::Config);
+//! ```
+use crate::{BetulaError, NodeError, NodeId};
+use serde::{Deserialize, Serialize};
+
+/// The category of an error.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ErrorKind {
+    /// A port is not connected, or its value is not on the blackboard.
+    MissingPort,
+    /// A value has a different type than expected.
+    TypeMismatch,
+    /// The node has a number of children it can't handle.
+    ChildCount,
+    /// The configuration of the node is invalid.
+    Config,
+    /// Any other error, like IO errors or errors from external libraries.
+    External,
+}
+
+/// An error with the node it originated from and the ancestors it was returned through.
+#[derive(Debug)]
+pub struct ExecutionError {
+    kind: ErrorKind,
+    node: Option<NodeId>,
+    ancestors: Vec<NodeId>,
+    source: BetulaError,
+}
+
+impl ExecutionError {
+    /// Create a new error of a particular kind, the node is set when it is returned by a node.
+    pub fn new(kind: ErrorKind, source: impl Into<BetulaError>) -> Self {
+        ExecutionError {
+            kind,
+            node: None,
+            ancestors: vec![],
+            source: source.into(),
+        }
+    }
+
+    /// The kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The node that returned this error first.
+    pub fn node(&self) -> Option<NodeId> {
+        self.node
+    }
+
+    /// The ancestors the error was returned through, starting with the parent of [`Self::node`].
+    pub fn ancestors(&self) -> &[NodeId] {
+        &self.ancestors
+    }
+
+    /// Obtain the execution error held by a node error, if any.
+    pub fn find(error: &NodeError) -> Option<&ExecutionError> {
+        error.downcast_ref::<ExecutionError>()
+    }
+
+    /// Record that the error was returned by the node with this id.
+    ///
+    /// Errors that aren't an [`ExecutionError`] yet are wrapped as [`ErrorKind::External`].
+    pub fn returned_by(error: NodeError, id: NodeId) -> NodeError {
+        match error.downcast::<ExecutionError>() {
+            Ok(mut error) => {
+                if error.node.is_none() {
+                    error.node = Some(id);
+                } else {
+                    error.ancestors.push(id);
+                }
+                error
+            }
+            Err(error) => Box::new(ExecutionError {
+                kind: ErrorKind::External,
+                node: Some(id),
+                ancestors: vec![],
+                source: error,
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl std::error::Error for ExecutionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+/// Serializable description of an error, such that it can be sent to the editor.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ErrorReport {
+    /// The error message.
+    pub message: String,
+    /// The kind of error.
+    pub kind: ErrorKind,
+    /// The node the error originated from, if known.
+    pub node: Option<NodeId>,
+    /// The ancestors the error was returned through.
+    pub ancestors: Vec<NodeId>,
+}
+
+impl ErrorReport {
+    /// Describe an error, errors that aren't an [`ExecutionError`] are considered external.
+    pub fn from_error(error: &NodeError) -> Self {
+        match ExecutionError::find(error) {
+            Some(e) => ErrorReport {
+                message: e.to_string(),
+                kind: e.kind,
+                node: e.node,
+                ancestors: e.ancestors.clone(),
+            },
+            None => ErrorReport {
+                message: error.to_string(),
+                kind: ErrorKind::External,
+                node: None,
+                ancestors: vec![],
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::BasicTree;
+    use crate::node_prelude::*;
+    use crate::Tree;
+    use uuid::Uuid;
+
+    /// Runs its child, or fails with a child count error if it has none.
+    #[derive(Debug)]
+    struct RunChildNode;
+    impl Node for RunChildNode {
+        fn execute(&mut self, ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+            if ctx.children() == 0 {
+                return Err(ExecutionError::new(ErrorKind::ChildCount, "no children").into());
+            }
+            ctx.run(0)
+        }
+        fn static_type() -> NodeType {
+            "run_child".into()
+        }
+        fn node_type(&self) -> NodeType {
+            Self::static_type()
+        }
+    }
+
+    /// Fails with a plain io error.
+    #[derive(Debug)]
+    struct IoErrorNode;
+    impl Node for IoErrorNode {
+        fn execute(&mut self, _ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+            Err(std::io::Error::other("disk on fire").into())
+        }
+        fn static_type() -> NodeType {
+            "io_error".into()
+        }
+        fn node_type(&self) -> NodeType {
+            Self::static_type()
+        }
+    }
+
+    #[test]
+    fn test_error_origin() -> Result<(), NodeError> {
+        let mut tree = BasicTree::new();
+        let root = tree.add_node_boxed(NodeId(Uuid::new_v4()), Box::new(RunChildNode))?;
+        let middle = tree.add_node_boxed(NodeId(Uuid::new_v4()), Box::new(RunChildNode))?;
+        let leaf = tree.add_node_boxed(NodeId(Uuid::new_v4()), Box::new(IoErrorNode))?;
+        tree.set_children(root, &[middle])?;
+        tree.set_children(middle, &[leaf])?;
+
+        let error = tree.execute(root).expect_err("leaf fails");
+        let report = ErrorReport::from_error(&error);
+        assert_eq!(report.kind, ErrorKind::External);
+        assert_eq!(report.node, Some(leaf));
+        assert_eq!(report.ancestors, vec![middle, root]);
+        assert_eq!(report.message, "disk on fire");
+        assert!(std::error::Error::source(&*error).is_some());
+
+        // Error created with a kind gets the node that returned it.
+        tree.set_children(middle, &[])?;
+        let error = tree.execute(root).expect_err("middle fails");
+        let report = ErrorReport::from_error(&error);
+        assert_eq!(report.kind, ErrorKind::ChildCount);
+        assert_eq!(report.node, Some(middle));
+        assert_eq!(report.ancestors, vec![root]);
+        Ok(())
+    }
+}
@@ -61,13 +61,20 @@ On blackboards:
 
 pub mod basic;
 pub mod blackboard;
+pub mod clock;
+pub mod conversion;
+pub mod derived;
+pub mod error;
+pub mod sync;
+pub mod task;
+pub mod validate;
 
 /// Prelude with only traits.
 pub mod prelude {
     pub use crate::{
         blackboard::BlackboardInputInterface, blackboard::BlackboardOutputInterface,
-        blackboard::SetupInput, blackboard::SetupOutput, NodeConfigLoad, ResetContext, RunContext,
-        Tree,
+        blackboard::SetupInput, blackboard::SetupOutput, HaltContext, NodeConfigLoad, ResetContext,
+        RunContext, Tree,
     };
 }
 
@@ -84,6 +91,7 @@ pub mod node_prelude {
         NodeConfig,
         NodeError,
         NodeType,
+        VersionedConfig,
     };
 }
 
@@ -91,7 +99,7 @@ pub use blackboard::{Blackboard, BlackboardId};
 
 use blackboard::{
     BlackboardInputInterface, BlackboardOutputInterface, BlackboardPort, NodePort, Port,
-    PortConnection,
+    PortConnection, PortName,
 };
 
 pub mod as_any;
@@ -132,7 +140,11 @@ pub trait RunContext {
         } else if self.children() == 1 {
             self.run(0)
         } else {
-            Err("had more than one child, cannot decorate".into())
+            Err(error::ExecutionError::new(
+                error::ErrorKind::ChildCount,
+                "had more than one child, cannot decorate",
+            )
+            .into())
         }
     }
 
@@ -143,6 +155,11 @@ pub trait RunContext {
         }
         Ok(())
     }
+
+    /// The clock nodes should use to obtain the current time.
+    fn clock(&self) -> &dyn clock::Clock {
+        &clock::SystemClock
+    }
 }
 
 /// The context through which nodes are reset.
@@ -159,6 +176,25 @@ pub trait ResetContext {
     fn reset_recursive(&self, index: usize) -> Result<(), NodeError>;
 }
 
+/// The context through which nodes are halted.
+///
+/// See [`Node::halt`], halting a node also halts its children.
+pub trait HaltContext {
+    /// Get the number of immediate children.
+    fn children(&self) -> usize;
+
+    /// Halt a child node and its children.
+    fn halt_recursive(&self, index: usize) -> Result<(), NodeError>;
+
+    /// Halt all children associated to this context.
+    fn halt_children(&self) -> Result<(), NodeError> {
+        for i in 0..self.children() {
+            self.halt_recursive(i)?;
+        }
+        Ok(())
+    }
+}
+
 /// The error type.
 pub type BetulaError = Box<dyn std::error::Error + Send + Sync>;
 // Should this switch to [`thiserror`] and [`anyhow`]?
@@ -182,6 +218,43 @@ impl Clone for Box<dyn NodeConfig> {
     }
 }
 
+/// A config that changed over time, older serialized configs are upgraded to the current one.
+///
+/// Configs start at version 0, which doesn't need an upgrade. Increase the version when the
+/// config changes incompatibly, and handle the previous version in [`VersionedConfig::upgrade`]:
+/// ```
+/// # use betula_core::{BetulaError, IsNodeConfig, VersionedConfig};
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Debug, Clone, Serialize, Deserialize)]
+/// struct WaitConfig {
+///     // Version 0 called this field 'delay'.
+///     duration: f64,
+/// }
+/// impl IsNodeConfig for WaitConfig {}
+/// impl VersionedConfig for WaitConfig {
+///     const VERSION: u32 = 1;
+///     fn upgrade(version: u32, mut config: serde_json::Value) -> Result<serde_json::Value, BetulaError> {
+///         match version {
+///             0 => {
+///                 config["duration"] = config["delay"].take();
+///                 Ok(config)
+///             }
+///             _ => Err(format!("unknown version {version}").into()),
+///         }
+///     }
+/// }
+/// ```
+pub trait VersionedConfig: IsNodeConfig {
+    /// The current version of the config.
+    const VERSION: u32;
+
+    /// Upgrade a serialized config from `version` to `version + 1`.
+    fn upgrade(version: u32, config: serde_json::Value) -> Result<serde_json::Value, BetulaError> {
+        let _ = config;
+        Err(format!("no upgrade from config version {version}").into())
+    }
+}
+
 /// Helper trait to easily load types implementing clone, used in [`Node::set_config`]:
 /// ```ignore
 /// fn set_config(&mut self, config:  &dyn NodeConfig) -> Result<(), NodeError> {
@@ -195,10 +268,13 @@ pub trait NodeConfigLoad: NodeConfig {
     {
         use crate::as_any::AsAnyHelper;
         let v = (*v).downcast_ref::<Self>().ok_or_else(|| {
-            format!(
-                "could not downcast {:?} to {:?}",
-                (*v).as_any_type_name(),
-                std::any::type_name::<Self>()
+            error::ExecutionError::new(
+                error::ErrorKind::Config,
+                format!(
+                    "could not downcast {:?} to {:?}",
+                    (*v).as_any_type_name(),
+                    std::any::type_name::<Self>()
+                ),
             )
         })?;
         *self = v.clone();
@@ -306,6 +382,16 @@ pub trait Node: std::fmt::Debug + AsAny {
         Ok(())
     }
 
+    /// Halt this node, it was running but its parent stopped executing it.
+    ///
+    /// The tree calls this when a node returned [`ExecutionStatus::Running`] and its parent either
+    /// did not execute it during its next execution, or finished without it. Nodes that perform
+    /// an action over multiple executions should stop it here, like releasing held keys or
+    /// stopping motion. Halting applies to the entire subtree, the default halts all children.
+    fn halt(&mut self, ctx: &dyn HaltContext) -> Result<(), NodeError> {
+        ctx.halt_children()
+    }
+
     /// The human readable type of this node, must guarantee:
     /// ```ignore
     /// fn node_type(&self) -> NodeType {
@@ -340,6 +426,76 @@ pub trait Node: std::fmt::Debug + AsAny {
     /// configuration. So this can be done to ensure that configuration / setup that may happen on
     /// the first run is done again.
     fn reset(&mut self) {}
+
+    /// Whether this node must be executed every time it is reached.
+    ///
+    /// Trees like [`basic::LazyTree`] skip nodes whose inputs and children are unchanged since
+    /// their last execution, and return the previous status instead. Nodes that depend on state
+    /// outside of the tree, like the clock or the screen, or nodes that must perform their action
+    /// on each execution should return true.
+    fn always_execute(&self) -> bool {
+        false
+    }
+
+    /// The range of children this node may have.
+    ///
+    /// The start is the minimum number of children, the end the maximum number of children.
+    /// [`Tree::validate`] reports nodes with a number of children outside of this range.
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..usize::MAX
+    }
+
+    /// Whether this node stops [`Node::reset_recursive`] from reaching its children.
+    ///
+    /// A node with multiple parents is reset by each of them, [`Tree::validate`] reports this
+    /// unless the reset is blocked by a node returning true here.
+    fn blocks_reset(&self) -> bool {
+        false
+    }
+}
+
+/// Observes the nodes executed during [`Tree::execute_observed`].
+pub trait ExecutionObserver {
+    /// Called right before a node executes.
+    fn started(&self, node: NodeId) {
+        let _ = node;
+    }
+
+    /// Called with the result of each node as it returns.
+    fn returned(&self, node: NodeId, status: &Result<ExecutionStatus, NodeError>);
+}
+
+/// Observer that ignores all nodes, used by [`Tree::execute`].
+impl ExecutionObserver for () {
+    fn returned(&self, node: NodeId, status: &Result<ExecutionStatus, NodeError>) {
+        let _ = (node, status);
+    }
+}
+
+/// Context that executes children through [`Tree::execute_observed`].
+struct ObservedContext<'a, T: Tree + ?Sized> {
+    this_node: NodeId,
+    tree: &'a T,
+    observer: &'a dyn ExecutionObserver,
+}
+impl<T: Tree + ?Sized> RunContext for ObservedContext<'_, T> {
+    fn children(&self) -> usize {
+        self.tree
+            .children(self.this_node)
+            .expect("node must exist in tree")
+            .len()
+    }
+    fn run(&self, index: usize) -> Result<ExecutionStatus, NodeError> {
+        let ids = self.tree.children(self.this_node)?;
+        self.tree.execute_observed(ids[index], self.observer)
+    }
+    fn reset_recursive(&self, index: usize) -> Result<(), NodeError> {
+        let ids = self.tree.children(self.this_node)?;
+        self.tree.reset_recursive(ids[index])
+    }
+    fn clock(&self) -> &dyn clock::Clock {
+        self.tree.clock()
+    }
 }
 
 /// Node ids are represented as UUIDs.
@@ -399,9 +555,44 @@ pub trait Tree: std::fmt::Debug + AsAny {
     /// Execute a particular node, starting at the provided node.
     fn execute(&self, id: NodeId) -> Result<ExecutionStatus, NodeError>;
 
+    /// Execute a particular node like [`Tree::execute`], calling the observer for every node that
+    /// starts and returns.
+    ///
+    /// The observer is called as nodes return, so children are observed before their parents.
+    /// Nodes that return without executing, like those of a [`basic::LazyTree`] that didn't
+    /// change, are not observed as started.
+    fn execute_observed(
+        &self,
+        id: NodeId,
+        observer: &dyn ExecutionObserver,
+    ) -> Result<ExecutionStatus, NodeError> {
+        let r = {
+            let mut n = self
+                .node_ref(id)
+                .ok_or_else(|| format!("node {id:?} does not exist"))?
+                .try_borrow_mut()?;
+            let context = ObservedContext {
+                this_node: id,
+                tree: self,
+                observer,
+            };
+            observer.started(id);
+            n.execute(&context)
+                .map_err(|e| error::ExecutionError::returned_by(e, id))
+        };
+        observer.returned(id, &r);
+        r
+    }
+
     /// Reset a node and all its children
     fn reset_recursive(&self, id: NodeId) -> Result<(), NodeError>;
 
+    /// Halt a node and all its children, see [`Node::halt`].
+    ///
+    /// The tree halts children automatically during execution, this can be used to halt the roots
+    /// when the tree is no longer executed.
+    fn halt_recursive(&self, id: NodeId) -> Result<(), NodeError>;
+
     /// Set the name of a node.
     fn set_node_name(&mut self, id: NodeId, name: Option<&str>) -> Result<(), BetulaError>;
 
@@ -413,6 +604,24 @@ pub trait Tree: std::fmt::Debug + AsAny {
     /// New nodes added to the tree should have their [`Node::set_directory`] called appropriately.
     fn set_directory(&mut self, directory: Option<&std::path::Path>);
 
+    /// The directory set with [`Tree::set_directory`].
+    fn directory(&self) -> Option<&std::path::Path>;
+
+    /// The clock provided to nodes through [`RunContext::clock`].
+    fn clock(&self) -> &dyn clock::Clock;
+
+    /// Set the clock provided to nodes, trees use the [`clock::SystemClock`] by default.
+    fn set_clock(&mut self, clock: Box<dyn clock::Clock>);
+
+    /// The conversions used to read blackboard values of another type than the input port.
+    fn conversions(&self) -> &conversion::Conversions;
+
+    /// Set the conversions used for input ports, existing connections use them from now on.
+    ///
+    /// Inputs that can't be read with the new conversions are disconnected and reported in the
+    /// error.
+    fn set_conversions(&mut self, conversions: conversion::Conversions) -> Result<(), BetulaError>;
+
     /// Get a list of the blackboard ids.
     fn blackboards(&self) -> Vec<BlackboardId>;
 
@@ -445,6 +654,56 @@ pub trait Tree: std::fmt::Debug + AsAny {
     /// Get the name of a blackboard.
     fn blackboard_name(&self, blackboard_id: BlackboardId) -> Result<Option<String>, BetulaError>;
 
+    /// Set the parent of a blackboard.
+    ///
+    /// Ports connected to a blackboard that doesn't hold the port's name use the nearest ancestor
+    /// that does, for both reading and writing. Values on a blackboard therefore shadow the values
+    /// of its ancestors. Ports are resolved when they are connected, or when parents change.
+    fn set_blackboard_parent(
+        &mut self,
+        blackboard_id: BlackboardId,
+        parent: Option<BlackboardId>,
+    ) -> Result<(), BetulaError>;
+
+    /// Get the parent of a blackboard.
+    fn blackboard_parent(
+        &self,
+        blackboard_id: BlackboardId,
+    ) -> Result<Option<BlackboardId>, BetulaError>;
+
+    /// Set the ports of a blackboard whose values persist across runs.
+    ///
+    /// The tree itself doesn't persist anything, this only marks the values that should be stored
+    /// in a snapshot of the blackboard and restored from it.
+    fn set_blackboard_persistent_ports(
+        &mut self,
+        blackboard_id: BlackboardId,
+        ports: &[PortName],
+    ) -> Result<(), BetulaError>;
+
+    /// Get the ports of a blackboard whose values persist across runs, sorted by name.
+    fn blackboard_persistent_ports(
+        &self,
+        blackboard_id: BlackboardId,
+    ) -> Result<Vec<PortName>, BetulaError>;
+
+    /// The blackboard port a connection to this port uses, see [`Tree::set_blackboard_parent`].
+    fn resolve_blackboard_port(&self, port: &BlackboardPort) -> BlackboardPort {
+        let name = port.name();
+        let mut current = Some(port.blackboard());
+        while let Some(id) = current {
+            let holds_name = self
+                .blackboard_ref(id)
+                .and_then(|b| Some(b.try_borrow().ok()?.get(&name).is_some()))
+                .unwrap_or(false);
+            if holds_name {
+                return BlackboardPort::new(id, &name);
+            }
+            current = self.blackboard_parent(id).ok().flatten();
+        }
+        port.clone()
+    }
+
     /// Connect an input or an output port to a blackboard using the port's name.
     fn connect_port_to_blackboard(
         &mut self,
@@ -506,4 +765,11 @@ pub trait Tree: std::fmt::Debug + AsAny {
         }
         Ok(())
     }
+
+    /// Check the tree for problems, returning a diagnostic for each problem found.
+    ///
+    /// The default implementation runs the checks from [`validate::validate_tree`].
+    fn validate(&self) -> Vec<validate::Diagnostic> {
+        validate::validate_tree(self)
+    }
 }
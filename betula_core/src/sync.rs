@@ -0,0 +1,350 @@
+//! Thread safe implementations.
+use crate::blackboard::{
+    BlackboardInputInterface, BlackboardOutputInterface, PortGeneration, PortName, Read,
+    ReadGeneration, Value, ValueCreator, Write,
+};
+use crate::error::{ErrorKind, ExecutionError};
+use crate::{BetulaError, Blackboard, NodeError};
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Lock a mutex, a panic while holding the lock doesn't make the blackboard unusable.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A value on the [`SyncBlackboard`] and when it was written.
+#[derive(Debug)]
+struct SyncBlackboardValue {
+    value: Value,
+    generation: PortGeneration,
+}
+
+type SyncBlackboardEntry = (TypeId, Arc<Mutex<SyncBlackboardValue>>);
+
+#[derive(Debug, Default)]
+struct SyncBlackboardStorage {
+    values: Mutex<HashMap<PortName, SyncBlackboardEntry>>,
+    generation: AtomicU64,
+    tick: AtomicU64,
+}
+
+impl SyncBlackboardStorage {
+    /// Advance the generation for a new write.
+    fn next_generation(&self) -> PortGeneration {
+        PortGeneration {
+            generation: self.generation.fetch_add(1, Ordering::Relaxed) + 1,
+            tick: self.tick.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A blackboard that can be shared between threads.
+///
+/// Clones of a [`SyncBlackboard`] share the same values, so handing out clones allows trees on
+/// different threads to communicate through one blackboard:
+/// ```
+/// # use betula_core::sync::SyncBlackboard;
+/// # use betula_core::prelude::*;
+/// # fn main() -> Result<(), betula_core::BetulaError> {
+/// let mut blackboard = SyncBlackboard::new();
+/// let mut other = blackboard.clone();
+/// let handle = std::thread::spawn(move || -> Result<(), betula_core::BetulaError> {
+///     other.output::<f64>("value", 0.0)?.set(3.3)?;
+///     Ok(())
+/// });
+/// handle.join().unwrap()?;
+/// assert_eq!(blackboard.input::<f64>("value")?.get()?, 3.3);
+/// # Ok(())
+/// # }
+/// ```
+/// To share only some blackboards of a tree, register them by name in [`SharedBlackboards`].
+#[derive(Clone, Debug, Default)]
+pub struct SyncBlackboard {
+    storage: Arc<SyncBlackboardStorage>,
+}
+
+impl SyncBlackboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl BlackboardOutputInterface for SyncBlackboard {
+    fn writer(
+        &mut self,
+        id: TypeId,
+        key: &PortName,
+        default: &ValueCreator,
+    ) -> Result<Write, NodeError> {
+        let (typeid, value) = lock(&self.storage.values)
+            .entry(key.clone())
+            .or_insert_with(|| {
+                let value = SyncBlackboardValue {
+                    value: default(),
+                    generation: self.storage.next_generation(),
+                };
+                (id, Arc::new(Mutex::new(value)))
+            })
+            .clone();
+        if typeid != id {
+            return Err(ExecutionError::new(
+                ErrorKind::TypeMismatch,
+                format!(
+                    "new writer for '{key:?}', has wrong type: already got {}",
+                    (*lock(&value).value).as_any_type_name()
+                ),
+            )
+            .into());
+        }
+        let storage = Arc::clone(&self.storage);
+        let owned_key = key.to_string();
+        Ok(Box::new(move |v: Value| {
+            let mut locked = lock(&value);
+            if (*locked.value).as_any_type_id() != (*v).as_any_type_id() {
+                Err(ExecutionError::new(
+                    ErrorKind::TypeMismatch,
+                    format!(
+                        "assignment for '{owned_key:?}' is incorrect type {} expected {}",
+                        (*locked.value).as_any_type_name(),
+                        (*v).as_any_type_name()
+                    ),
+                )
+                .into())
+            } else {
+                locked.value = v;
+                locked.generation = storage.next_generation();
+                Ok(())
+            }
+        }))
+    }
+}
+
+impl BlackboardInputInterface for SyncBlackboard {
+    fn reader(&mut self, id: &TypeId, key: &PortName) -> Result<Read, NodeError> {
+        let (typeid, value) = lock(&self.storage.values)
+            .get(key)
+            .cloned()
+            .ok_or_else(|| {
+                ExecutionError::new(ErrorKind::MissingPort, format!("key '{key:?}' not found"))
+            })?;
+        if typeid != *id {
+            return Err(ExecutionError::new(
+                ErrorKind::TypeMismatch,
+                format!(
+                    "new reader for '{key:?}' mismatches type: already got {}",
+                    (*lock(&value).value).as_any_type_name()
+                ),
+            )
+            .into());
+        }
+        Ok(Box::new(move || Ok(lock(&value).value.clone())))
+    }
+
+    fn generation_reader(&mut self, key: &PortName) -> Result<ReadGeneration, NodeError> {
+        let (_, value) = lock(&self.storage.values)
+            .get(key)
+            .cloned()
+            .ok_or_else(|| {
+                ExecutionError::new(ErrorKind::MissingPort, format!("key '{key:?}' not found"))
+            })?;
+        Ok(Box::new(move || Ok(lock(&value).generation)))
+    }
+}
+
+impl Blackboard for SyncBlackboard {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn ports(&self) -> Vec<PortName> {
+        lock(&self.storage.values).keys().cloned().collect()
+    }
+
+    fn clear(&mut self) {
+        lock(&self.storage.values).clear()
+    }
+
+    fn get(&self, port: &PortName) -> Option<Value> {
+        let value = lock(&self.storage.values).get(port)?.1.clone();
+        let value = lock(&value).value.clone();
+        Some(value)
+    }
+
+    fn generation(&self, port: &PortName) -> Option<PortGeneration> {
+        let value = lock(&self.storage.values).get(port)?.1.clone();
+        let generation = lock(&value).generation;
+        Some(generation)
+    }
+
+    fn set_tick(&mut self, tick: u64) {
+        self.storage.tick.store(tick, Ordering::Relaxed);
+    }
+
+    fn set(&mut self, port: &PortName, value: Value) -> Result<(), BetulaError> {
+        let new_value_type = (*value).as_any_type_id();
+        let generation = self.storage.next_generation();
+        let mut values = lock(&self.storage.values);
+        match values.entry(port.clone()) {
+            std::collections::hash_map::Entry::Occupied(e) => {
+                if e.get().0 != new_value_type {
+                    return Err("different type already on blackboard".into());
+                }
+                let mut entry = lock(&e.get().1);
+                entry.value = value;
+                entry.generation = generation;
+            }
+            std::collections::hash_map::Entry::Vacant(v) => {
+                let value = SyncBlackboardValue { value, generation };
+                v.insert((new_value_type, Arc::new(Mutex::new(value))));
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, port: &PortName) -> Option<Value> {
+        let (_, value) = lock(&self.storage.values).remove(port)?;
+        let value = lock(&value).value.clone();
+        Some(value)
+    }
+}
+
+/// Blackboards that are shared by name, for example between trees on different threads.
+///
+/// Only the names that are added are shared, blackboards with other names stay independent.
+/// Clones of the registry refer to the same blackboards.
+/// ```
+/// # use betula_core::sync::SharedBlackboards;
+/// # use betula_core::prelude::*;
+/// # fn main() -> Result<(), betula_core::BetulaError> {
+/// let shared = SharedBlackboards::new();
+/// let mut blackboard = shared.add("world");
+/// let other = shared.clone();
+/// let handle = std::thread::spawn(move || -> Result<(), betula_core::BetulaError> {
+///     let mut blackboard = other.get("world").ok_or("not shared")?;
+///     blackboard.output::<f64>("value", 0.0)?.set(3.3)?;
+///     Ok(())
+/// });
+/// handle.join().unwrap()?;
+/// assert_eq!(blackboard.input::<f64>("value")?.get()?, 3.3);
+/// assert!(shared.get("local").is_none());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SharedBlackboards {
+    blackboards: Arc<Mutex<HashMap<String, SyncBlackboard>>>,
+}
+
+impl SharedBlackboards {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Share the blackboard with this name, returns the shared blackboard.
+    pub fn add(&self, name: &str) -> SyncBlackboard {
+        lock(&self.blackboards)
+            .entry(name.to_owned())
+            .or_default()
+            .clone()
+    }
+
+    /// The shared blackboard with this name, `None` if the name is not shared.
+    pub fn get(&self, name: &str) -> Option<SyncBlackboard> {
+        lock(&self.blackboards).get(name).cloned()
+    }
+
+    /// Stop sharing the blackboard with this name, blackboards already handed out keep sharing.
+    pub fn remove(&self, name: &str) -> Option<SyncBlackboard> {
+        lock(&self.blackboards).remove(name)
+    }
+
+    /// The names of the shared blackboards.
+    pub fn names(&self) -> Vec<String> {
+        lock(&self.blackboards).keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::BasicTree;
+    use crate::blackboard::{Input, Output};
+    use crate::node_prelude::*;
+    use crate::{BlackboardId, NodeId, Tree};
+    use uuid::Uuid;
+
+    /// Node that copies its input to its output.
+    #[derive(Debug, Default)]
+    struct CopyNode {
+        input: Input<i64>,
+        output: Output<i64>,
+    }
+    impl Node for CopyNode {
+        fn execute(&mut self, _ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+            self.output.set(self.input.get()?)?;
+            Ok(ExecutionStatus::Success)
+        }
+        fn ports(&self) -> Result<Vec<Port>, NodeError> {
+            Ok(vec![Port::input::<i64>("a"), Port::output::<i64>("b")])
+        }
+        fn setup_inputs(
+            &mut self,
+            interface: &mut dyn BlackboardInputInterface,
+        ) -> Result<(), NodeError> {
+            self.input = interface.input::<i64>("a")?;
+            Ok(())
+        }
+        fn setup_outputs(
+            &mut self,
+            interface: &mut dyn BlackboardOutputInterface,
+        ) -> Result<(), NodeError> {
+            self.output = interface.output::<i64>("b", 0)?;
+            Ok(())
+        }
+        fn static_type() -> NodeType {
+            "copy".into()
+        }
+        fn node_type(&self) -> NodeType {
+            Self::static_type()
+        }
+    }
+
+    #[test]
+    fn test_trees_on_threads() -> Result<(), NodeError> {
+        fn is_send_sync<T: Send + Sync>() {}
+        is_send_sync::<SyncBlackboard>();
+
+        let mut blackboard = SyncBlackboard::new();
+        blackboard.set(&"a".into(), Box::new(5i64))?;
+        let b_generation = {
+            let mut other = blackboard.clone();
+            other.output::<i64>("b", 0)?;
+            other.generation(&"b".into()).ok_or("missing b")?
+        };
+
+        // Run a tree on another thread, that copies a into b.
+        let shared = blackboard.clone();
+        let handle = std::thread::spawn(move || -> Result<(), BetulaError> {
+            let mut tree = BasicTree::new();
+            let bb = tree.add_blackboard_boxed(BlackboardId(Uuid::new_v4()), Box::new(shared))?;
+            let node =
+                tree.add_node_boxed(NodeId(Uuid::new_v4()), Box::new(CopyNode::default()))?;
+            for port in tree.node_ports(node)? {
+                tree.connect_port_to_blackboard(&port, bb)?;
+            }
+            tree.execute(node)?;
+            Ok(())
+        });
+        handle.join().map_err(|_| "thread panicked")??;
+
+        let b = blackboard.input::<i64>("b")?;
+        assert_eq!(b.get()?, 5);
+        assert!(b.changed_since(b_generation.generation)?);
+        assert_eq!(blackboard.ports().len(), 2);
+        Ok(())
+    }
+}
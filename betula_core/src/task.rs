@@ -0,0 +1,219 @@
+//! Helper to perform work of a node on a background thread.
+//!
+//! Tree execution is single threaded, work that takes longer than a single execution, like file
+//! io or waiting for a process, should not block the tree. A [`BackgroundTask`] starts the work on
+//! a thread during the first execution, the node returns [`ExecutionStatus::Running`] while it is
+//! pending and the result of the work once it is done:
+//! ```
+//! # use betula_core::node_prelude::*;
+//! # use betula_core::task::BackgroundTask;
+//! #[derive(Debug, Default)]
+//! struct SlowNode {
+//!     task: BackgroundTask<ExecutionStatus>,
+//! }
+//! impl Node for SlowNode {
+//!     fn execute(&mut self, _ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+//!         self.task.execute(|cancel| {
+//!             for _ in 0..10 {
+//!                 if cancel.is_cancelled() {
+//!                     break;
+//!                 }
+//!                 std::thread::sleep(std::time::Duration::from_millis(1));
+//!             }
+//!             Ok(ExecutionStatus::Success)
+//!         })
+//!     }
+//!     fn always_execute(&self) -> bool {
+//!         self.task.is_pending()
+//!     }
+//!     fn reset(&mut self) {
+//!         self.task.cancel();
+//!     }
+//!     fn halt(&mut self, _ctx: &dyn HaltContext) -> Result<(), NodeError> {
+//!         self.task.cancel();
+//!         Ok(())
+//!     }
+//!     // ...
+//! #   fn static_type() -> NodeType { "slow".into() }
+//! #   fn node_type(&self) -> NodeType { Self::static_type() }
+//! }
+//! ```
+use crate::{ExecutionStatus, NodeError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::Arc;
+
+/// Token to check whether the work of a [`BackgroundTask`] was cancelled.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// Whether the task was cancelled, long running work should check this and return early.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug)]
+struct PendingTask<T> {
+    receiver: Receiver<Result<T, NodeError>>,
+    cancel: CancelToken,
+}
+
+/// Work of a node that runs on a background thread.
+///
+/// The work is started by [`BackgroundTask::poll`], subsequent polls return the result once it is
+/// available, after which the next poll starts the work again. Cancelling the task, or dropping
+/// it, signals the [`CancelToken`] and discards the result. The thread is not joined, work that
+/// doesn't check the token runs to completion in the background.
+#[derive(Debug)]
+pub struct BackgroundTask<T> {
+    pending: Option<PendingTask<T>>,
+}
+
+impl<T> Default for BackgroundTask<T> {
+    fn default() -> Self {
+        BackgroundTask { pending: None }
+    }
+}
+
+impl<T: Send + 'static> BackgroundTask<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start the work if it isn't pending, returns the result if the work is done.
+    ///
+    /// The `work` is only used if no work is pending. Errors returned by the work, or a panic
+    /// in it, are returned as error.
+    pub fn poll<F>(&mut self, work: F) -> Result<Option<T>, NodeError>
+    where
+        F: FnOnce(CancelToken) -> Result<T, NodeError> + Send + 'static,
+    {
+        let pending = self.pending.get_or_insert_with(|| {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            let cancel = CancelToken::default();
+            let token = cancel.clone();
+            std::thread::spawn(move || {
+                // The receiver is gone if the task got cancelled.
+                let _ = sender.send(work(token));
+            });
+            PendingTask { receiver, cancel }
+        });
+        match pending.receiver.try_recv() {
+            Ok(result) => {
+                self.pending = None;
+                Ok(Some(result?))
+            }
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => {
+                self.pending = None;
+                Err("background task panicked".into())
+            }
+        }
+    }
+
+    /// Whether work was started and has not been polled to completion.
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Cancel pending work, its result is discarded.
+    pub fn cancel(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            pending.cancel.cancel();
+        }
+    }
+}
+
+impl BackgroundTask<ExecutionStatus> {
+    /// Poll work that determines the status of the node, [`ExecutionStatus::Running`] while pending.
+    pub fn execute<F>(&mut self, work: F) -> Result<ExecutionStatus, NodeError>
+    where
+        F: FnOnce(CancelToken) -> Result<ExecutionStatus, NodeError> + Send + 'static,
+    {
+        Ok(self.poll(work)?.unwrap_or(ExecutionStatus::Running))
+    }
+}
+
+impl<T> Drop for BackgroundTask<T> {
+    fn drop(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            pending.cancel.cancel();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+
+    /// Poll until the work is done.
+    fn wait<T: Send + 'static>(
+        task: &mut BackgroundTask<T>,
+        work: impl Fn(CancelToken) -> Result<T, NodeError> + Send + Clone + 'static,
+    ) -> Result<T, NodeError> {
+        loop {
+            if let Some(v) = task.poll(work.clone())? {
+                return Ok(v);
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_background_task() -> Result<(), NodeError> {
+        let mut task = BackgroundTask::<ExecutionStatus>::new();
+        let (sender, receiver) = channel::<ExecutionStatus>();
+        let receiver = Arc::new(std::sync::Mutex::new(receiver));
+        let work = move |_| Ok(receiver.lock().unwrap().recv()?);
+        assert_eq!(task.execute(work.clone())?, ExecutionStatus::Running);
+        assert!(task.is_pending());
+        assert_eq!(task.execute(work.clone())?, ExecutionStatus::Running);
+        sender.send(ExecutionStatus::Failure)?;
+        assert_eq!(wait(&mut task, work.clone())?, ExecutionStatus::Failure);
+        assert!(!task.is_pending());
+
+        // Polling again starts new work.
+        assert_eq!(task.execute(work.clone())?, ExecutionStatus::Running);
+        sender.send(ExecutionStatus::Success)?;
+        assert_eq!(wait(&mut task, work)?, ExecutionStatus::Success);
+
+        // Errors are returned.
+        let mut task = BackgroundTask::<i64>::new();
+        let r = wait(&mut task, |_| Err("failed".into()));
+        assert_eq!(r.expect_err("work failed").to_string(), "failed");
+        Ok(())
+    }
+
+    #[test]
+    fn test_background_task_cancel() -> Result<(), NodeError> {
+        let (sender, receiver) = channel::<bool>();
+        let work = move |cancel: CancelToken| {
+            while !cancel.is_cancelled() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            sender.send(true)?;
+            Ok(1)
+        };
+        let mut task = BackgroundTask::<i64>::new();
+        assert_eq!(task.poll(work.clone())?, None);
+        task.cancel();
+        assert!(!task.is_pending());
+        assert!(receiver.recv_timeout(Duration::from_secs(5))?);
+
+        // Dropping cancels as well.
+        assert_eq!(task.poll(work)?, None);
+        drop(task);
+        assert!(receiver.recv_timeout(Duration::from_secs(5))?);
+        Ok(())
+    }
+}
@@ -0,0 +1,396 @@
+//! Checks that find problems in a tree before it is executed.
+//!
+//! See [`Tree::validate`], the diagnostics can be shown to the user before the tree is ran.
+use crate::blackboard::{BlackboardPort, NodePort, PortConnection, PortDirection, PortType};
+use crate::{NodeId, Tree};
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A problem found in a tree by [`Tree::validate`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Diagnostic {
+    /// Following the children from the first node leads back to it, through these nodes.
+    Cycle(Vec<NodeId>),
+
+    /// A node is reset by multiple parents, without a node that blocks the reset in between.
+    MultipleParents { node: NodeId, parents: Vec<NodeId> },
+
+    /// An input port is not connected to any blackboard.
+    UnconnectedInput(NodePort),
+
+    /// Multiple output ports write to the same blackboard port.
+    MultipleWriters {
+        blackboard: BlackboardPort,
+        writers: Vec<NodePort>,
+    },
+
+    /// The port's type differs from the type of the value already on the blackboard.
+    TypeMismatch {
+        connection: PortConnection,
+        port_type: String,
+        blackboard_type: String,
+    },
+
+    /// The node has a number of children outside of its [`crate::Node::child_range`].
+    ChildCount {
+        node: NodeId,
+        children: usize,
+        range: std::ops::Range<usize>,
+    },
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Diagnostic::Cycle(nodes) => write!(f, "cycle through nodes {nodes:?}"),
+            Diagnostic::MultipleParents { node, parents } => write!(
+                f,
+                "node {node:?} is reset by multiple parents {parents:?}, consider a block reset node"
+            ),
+            Diagnostic::UnconnectedInput(port) => write!(
+                f,
+                "input {:?} of node {:?} is not connected",
+                port.name(),
+                port.node()
+            ),
+            Diagnostic::MultipleWriters {
+                blackboard,
+                writers,
+            } => write!(
+                f,
+                "port {:?} on blackboard {:?} has multiple writers {writers:?}",
+                blackboard.name(),
+                blackboard.blackboard()
+            ),
+            Diagnostic::TypeMismatch {
+                connection,
+                port_type,
+                blackboard_type,
+            } => write!(
+                f,
+                "port {:?} of node {:?} has type {port_type}, blackboard holds {blackboard_type}",
+                connection.node.name(),
+                connection.node.node()
+            ),
+            Diagnostic::ChildCount {
+                node,
+                children,
+                range,
+            } => write!(
+                f,
+                "node {node:?} has {children} children, expected {} up to {}",
+                range.start, range.end
+            ),
+        }
+    }
+}
+
+/// Run all checks against the tree, used by the default [`Tree::validate`].
+pub fn validate_tree<T: Tree + ?Sized>(tree: &T) -> Vec<Diagnostic> {
+    let mut nodes = tree.nodes();
+    nodes.sort();
+    let mut diagnostics = vec![];
+    diagnostics.extend(find_cycles(tree, &nodes));
+    diagnostics.extend(check_parents(tree, &nodes));
+    diagnostics.extend(check_child_counts(tree, &nodes));
+    diagnostics.extend(check_ports(tree, &nodes));
+    diagnostics
+}
+
+fn children_of<T: Tree + ?Sized>(tree: &T, id: NodeId) -> Vec<NodeId> {
+    tree.children(id).unwrap_or_default()
+}
+
+fn find_cycles<T: Tree + ?Sized>(tree: &T, nodes: &[NodeId]) -> Vec<Diagnostic> {
+    #[derive(Copy, Clone, PartialEq)]
+    enum Visit {
+        InProgress,
+        Done,
+    }
+
+    fn visit<T: Tree + ?Sized>(
+        tree: &T,
+        id: NodeId,
+        state: &mut HashMap<NodeId, Visit>,
+        path: &mut Vec<NodeId>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        match state.get(&id) {
+            Some(Visit::Done) => return,
+            Some(Visit::InProgress) => {
+                let start = path.iter().position(|z| *z == id).unwrap_or(0);
+                diagnostics.push(Diagnostic::Cycle(path[start..].to_vec()));
+                return;
+            }
+            None => {}
+        }
+        state.insert(id, Visit::InProgress);
+        path.push(id);
+        for child in children_of(tree, id) {
+            visit(tree, child, state, path, diagnostics);
+        }
+        path.pop();
+        state.insert(id, Visit::Done);
+    }
+
+    let mut state = HashMap::new();
+    let mut diagnostics = vec![];
+    for id in nodes {
+        visit(tree, *id, &mut state, &mut vec![], &mut diagnostics);
+    }
+    diagnostics
+}
+
+fn blocks_reset<T: Tree + ?Sized>(tree: &T, id: NodeId) -> bool {
+    tree.node_ref(id)
+        .and_then(|n| n.try_borrow().ok().map(|n| n.blocks_reset()))
+        .unwrap_or(false)
+}
+
+fn check_parents<T: Tree + ?Sized>(tree: &T, nodes: &[NodeId]) -> Vec<Diagnostic> {
+    let mut parents: BTreeMap<NodeId, Vec<NodeId>> = BTreeMap::new();
+    for id in nodes {
+        if blocks_reset(tree, *id) {
+            // Resets don't propagate through this parent.
+            continue;
+        }
+        for child in children_of(tree, *id) {
+            parents.entry(child).or_default().push(*id);
+        }
+    }
+    parents
+        .into_iter()
+        .filter(|(node, parents)| parents.len() > 1 && !blocks_reset(tree, *node))
+        .map(|(node, parents)| Diagnostic::MultipleParents { node, parents })
+        .collect()
+}
+
+fn check_child_counts<T: Tree + ?Sized>(tree: &T, nodes: &[NodeId]) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    for id in nodes {
+        let Some(range) = tree
+            .node_ref(*id)
+            .and_then(|n| n.try_borrow().ok().map(|n| n.child_range()))
+        else {
+            continue;
+        };
+        let children = children_of(tree, *id).len();
+        if children < range.start || children > range.end {
+            diagnostics.push(Diagnostic::ChildCount {
+                node: *id,
+                children,
+                range,
+            });
+        }
+    }
+    diagnostics
+}
+
+fn check_ports<T: Tree + ?Sized>(tree: &T, nodes: &[NodeId]) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut connections = tree.port_connections();
+    connections.sort();
+
+    // Types of all the ports, inputs without a connection are reported.
+    let mut port_types: HashMap<NodePort, PortType> = HashMap::new();
+    for id in nodes {
+        let Some(ports) = tree
+            .node_ref(*id)
+            .and_then(|n| n.try_borrow().ok().and_then(|n| n.ports().ok()))
+        else {
+            continue;
+        };
+        for port in ports {
+            let port_type = port.port_type();
+            let optional = port.is_optional();
+            let node_port = port.into_node_port(*id);
+            if node_port.direction() == PortDirection::Input
+                && !optional
+                && !connections.iter().any(|c| c.node == node_port)
+            {
+                diagnostics.push(Diagnostic::UnconnectedInput(node_port.clone()));
+            }
+            port_types.insert(node_port, port_type);
+        }
+    }
+
+    let mut writers: BTreeMap<BlackboardPort, Vec<NodePort>> = BTreeMap::new();
+    for connection in connections.iter() {
+        if connection.node.direction() == PortDirection::Output {
+            writers
+                .entry(connection.blackboard.clone())
+                .or_default()
+                .push(connection.node.clone());
+        }
+    }
+    diagnostics.extend(
+        writers
+            .into_iter()
+            .filter(|(_, writers)| writers.len() > 1)
+            .map(|(blackboard, writers)| Diagnostic::MultipleWriters {
+                blackboard,
+                writers,
+            }),
+    );
+
+    for connection in connections {
+        let Some(port_type) = port_types.get(&connection.node).copied() else {
+            continue;
+        };
+        let port = tree.resolve_blackboard_port(&connection.blackboard);
+        let Some(value) = tree
+            .blackboard_ref(port.blackboard())
+            .and_then(|b| b.try_borrow().ok()?.get(&port.name()))
+        else {
+            continue;
+        };
+        let blackboard_type = PortType::of_value(&*value);
+        let converts = connection.node.direction() == PortDirection::Input
+            && tree
+                .conversions()
+                .can_convert(blackboard_type.type_id(), port_type.type_id());
+        if blackboard_type != port_type && !converts {
+            diagnostics.push(Diagnostic::TypeMismatch {
+                connection,
+                port_type: port_type.type_name().to_owned(),
+                blackboard_type: blackboard_type.type_name().to_owned(),
+            });
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::{BasicBlackboard, BasicTree};
+    use crate::node_prelude::*;
+    use crate::{BlackboardId, Node};
+    use uuid::Uuid;
+
+    /// Node that only declares things for the validation.
+    #[derive(Debug)]
+    struct DeclareNode {
+        range: std::ops::Range<usize>,
+        ports: Vec<Port>,
+        blocks_reset: bool,
+    }
+    impl DeclareNode {
+        fn new(range: std::ops::Range<usize>, ports: Vec<Port>) -> Box<Self> {
+            Box::new(DeclareNode {
+                range,
+                ports,
+                blocks_reset: false,
+            })
+        }
+    }
+    impl Node for DeclareNode {
+        fn execute(&mut self, _ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+            Ok(ExecutionStatus::Success)
+        }
+        fn ports(&self) -> Result<Vec<Port>, NodeError> {
+            Ok(self.ports.clone())
+        }
+        fn static_type() -> NodeType {
+            "declare".into()
+        }
+        fn node_type(&self) -> NodeType {
+            Self::static_type()
+        }
+        fn child_range(&self) -> std::ops::Range<usize> {
+            self.range.clone()
+        }
+        fn blocks_reset(&self) -> bool {
+            self.blocks_reset
+        }
+    }
+
+    #[test]
+    fn test_validate() -> Result<(), NodeError> {
+        let mut tree = BasicTree::new();
+        let mut ids = (0..10).map(|_| NodeId(Uuid::new_v4())).collect::<Vec<_>>();
+        ids.sort();
+        let [n0, n1, n2, n3, n4, n5, n6, n7, n8, n9] = ids[..] else {
+            unreachable!()
+        };
+        tree.add_node_boxed(n0, DeclareNode::new(0..1, vec![]))?;
+        tree.add_node_boxed(n1, DeclareNode::new(0..1, vec![Port::input::<i64>("x")]))?;
+        tree.add_node_boxed(n2, DeclareNode::new(0..1, vec![Port::output::<i64>("y")]))?;
+        tree.add_node_boxed(n3, DeclareNode::new(0..0, vec![Port::output::<i64>("y")]))?;
+        let mut blocking = DeclareNode::new(1..1, vec![]);
+        blocking.blocks_reset = true;
+        tree.add_node_boxed(n4, blocking)?;
+        tree.add_node_boxed(n5, DeclareNode::new(1..1, vec![Port::input::<f64>("z")]))?;
+        tree.add_node_boxed(n6, DeclareNode::new(0..0, vec![]))?;
+        tree.add_node_boxed(n7, DeclareNode::new(0..1, vec![]))?;
+        tree.add_node_boxed(n8, DeclareNode::new(0..1, vec![]))?;
+        tree.add_node_boxed(n9, DeclareNode::new(0..1, vec![]))?;
+
+        // Too many children, and n3 is reset by both n1 and n2.
+        tree.set_children(n0, &[n1, n2])?;
+        tree.set_children(n1, &[n3])?;
+        tree.set_children(n2, &[n3])?;
+        // Shared child, but one parent blocks the reset.
+        tree.set_children(n4, &[n6])?;
+        tree.set_children(n5, &[n6])?;
+        // Cycle.
+        tree.set_children(n7, &[n8])?;
+        tree.set_children(n8, &[n7])?;
+
+        let bb = tree.add_blackboard_boxed(
+            BlackboardId(Uuid::new_v4()),
+            Box::new(BasicBlackboard::default()),
+        )?;
+        tree.blackboard_mut(bb)
+            .unwrap()
+            .set(&"z".into(), Box::new(1.0f64))?;
+        for node in [n2, n3, n5] {
+            for port in tree.node_ports(node)? {
+                tree.connect_port_to_blackboard(&port, bb)?;
+            }
+        }
+        // Change the type of the value underneath the connected input.
+        let blackboard = tree.blackboard_mut(bb).unwrap();
+        blackboard.remove(&"z".into());
+        blackboard.set(&"z".into(), Box::new(true))?;
+
+        let diagnostics = tree.validate();
+        let y = BlackboardPort::new(bb, &"y".into());
+        let z = PortConnection::new(
+            NodePort::new(n5, &"z".into(), PortDirection::Input),
+            BlackboardPort::new(bb, &"z".into()),
+        );
+        let expected = vec![
+            Diagnostic::Cycle(vec![n7, n8]),
+            Diagnostic::MultipleParents {
+                node: n3,
+                parents: vec![n1, n2],
+            },
+            Diagnostic::ChildCount {
+                node: n0,
+                children: 2,
+                range: 0..1,
+            },
+            Diagnostic::UnconnectedInput(NodePort::new(n1, &"x".into(), PortDirection::Input)),
+            Diagnostic::MultipleWriters {
+                blackboard: y,
+                writers: vec![
+                    NodePort::new(n2, &"y".into(), PortDirection::Output),
+                    NodePort::new(n3, &"y".into(), PortDirection::Output),
+                ],
+            },
+            Diagnostic::TypeMismatch {
+                connection: z,
+                port_type: "f64".to_owned(),
+                blackboard_type: "bool".to_owned(),
+            },
+        ];
+        assert_eq!(diagnostics, expected);
+        assert_eq!(
+            diagnostics[3].to_string(),
+            format!("input PortName(\"x\") of node {n1:?} is not connected")
+        );
+        Ok(())
+    }
+}
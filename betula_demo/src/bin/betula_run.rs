@@ -0,0 +1,468 @@
+//! Run a tree without the editor.
+//!
+//! The tree is loaded with the same nodes as the editor, registered without the editor such that
+//! this runner builds with `--no-default-features`. Its directory is set to that of the
+//! file and persistent blackboard values are restored, like the editor does when opening a
+//! tree. The roots are ticked in the background server thread until they all finish, or until
+//! Ctrl+C if `--forever` is passed. Overlays are not shown, there is no window to draw them.
+//!
+//! With `--record` the ticks are recorded to a trace, which can be stepped through offline with
+//! `--replay`, see [`betula_common::trace`], leaf nodes of the types passed with `--execute` are
+//! executed during the replay instead of stubbed. With `--profile` the nodes that took the most
+//! time are printed every number of ticks.
+//!
+//! With `--break` execution pauses before the node with that name or id executes, `--step` pauses
+//! before the first node. While paused the position is printed and commands are read from stdin to
+//! step into or over the node, or to continue until the next breakpoint. Ctrl+C while paused stops
+//! the runner as well.
+use betula_common::{
+    control::{
+        internal_server_client, Breakpoint, DebugCommand, DebugPosition, ExecutionProfile,
+        ExecutionResult, InteractionCommand, InteractionEvent, NodeInformation, NodeStatus,
+        RunSettings, TreeClient,
+    },
+    create_server_thread,
+    subtree_node::load_tree_config_file,
+    trace::{read_trace, TraceReplay},
+};
+use betula_core::basic::{BasicBlackboard, BasicTree};
+use betula_core::{BetulaError, ExecutionStatus, NodeId};
+use betula_demo::create_tree_support;
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+
+const USAGE: &str = "./betula_run path_to_tree.json [--interval <milliseconds>] [--forever] \
+                     [--record <trace.jsonl>] [--profile <ticks>] [--break <node name or id>]... \
+                     [--step]
+./betula_run --replay <trace.jsonl> [--execute <node type>]...";
+
+/// Exit code when interrupted while the roots were still running.
+const EXIT_INTERRUPTED: u8 = 130;
+
+/// Number of nodes printed for each profile.
+const PROFILE_NODES: usize = 10;
+
+/// Interval at which Ctrl+C is checked while waiting for input.
+const PROMPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+struct Options {
+    path: PathBuf,
+    interval: Duration,
+    forever: bool,
+    record: Option<PathBuf>,
+    replay: bool,
+    execute: Vec<String>,
+    profile: Option<u64>,
+    breakpoints: Vec<String>,
+    step: bool,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut path = None;
+    let mut interval = Duration::from_millis(10);
+    let mut forever = false;
+    let mut record = None;
+    let mut replay = false;
+    let mut execute = vec![];
+    let mut profile = None;
+    let mut breakpoints = vec![];
+    let mut step = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" => return Err(USAGE.to_owned()),
+            "--forever" => forever = true,
+            "--step" => step = true,
+            "--break" => {
+                let value = args.next().ok_or("--break requires a node name or id")?;
+                breakpoints.push(value);
+            }
+            "--interval" => {
+                let value = args.next().ok_or("--interval requires a value")?;
+                let millis: u64 = value
+                    .parse()
+                    .map_err(|_| format!("invalid interval {value:?}"))?;
+                interval = Duration::from_millis(millis);
+            }
+            "--record" => {
+                let value = args.next().ok_or("--record requires a path")?;
+                record = Some(PathBuf::from(value));
+            }
+            "--profile" => {
+                let value = args.next().ok_or("--profile requires a number of ticks")?;
+                let ticks: u64 = value
+                    .parse()
+                    .map_err(|_| format!("invalid number of ticks {value:?}"))?;
+                profile = Some(ticks);
+            }
+            "--replay" => {
+                let value = args.next().ok_or("--replay requires a path")?;
+                path = Some(PathBuf::from(value));
+                replay = true;
+            }
+            "--execute" => {
+                let value = args.next().ok_or("--execute requires a node type")?;
+                execute.push(value);
+            }
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unknown argument {arg:?}\n{USAGE}")),
+        }
+    }
+    let path = path.ok_or(USAGE)?;
+    if !path.is_file() {
+        return Err(format!("File path to {path:?} did not exist"));
+    }
+    Ok(Options {
+        path,
+        interval,
+        forever,
+        record,
+        replay,
+        execute,
+        profile,
+        breakpoints,
+        step,
+    })
+}
+
+/// The combined status of the roots in a tick, finished outcomes are ordered from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Outcome {
+    Success,
+    Failure,
+    Error,
+    Running,
+}
+
+impl Outcome {
+    /// The outcome of the roots, `None` if not all roots were executed.
+    fn from_result(roots: &[NodeId], result: &ExecutionResult) -> Option<Outcome> {
+        if roots.is_empty() {
+            return None;
+        }
+        let mut outcome = Outcome::Success;
+        let mut running = false;
+        for root in roots {
+            // The status of a node is reported after those of its children.
+            let status = result.node_status.iter().rev().find(|s| s.node == *root)?;
+            let root_outcome = match &status.status {
+                Ok(ExecutionStatus::Success) => Outcome::Success,
+                Ok(ExecutionStatus::Running) => {
+                    running = true;
+                    continue;
+                }
+                Ok(ExecutionStatus::Failure) => Outcome::Failure,
+                Err(e) => {
+                    eprintln!("root {root:?} failed: {e}");
+                    Outcome::Error
+                }
+            };
+            outcome = outcome.max(root_outcome);
+        }
+        Some(if running { Outcome::Running } else { outcome })
+    }
+
+    fn exit_code(&self) -> ExitCode {
+        ExitCode::from(match self {
+            Outcome::Success => 0,
+            Outcome::Failure => 1,
+            Outcome::Error => 2,
+            Outcome::Running => EXIT_INTERRUPTED,
+        })
+    }
+}
+
+fn run(options: &Options, stop: &AtomicBool) -> Result<Outcome, BetulaError> {
+    let path = options.path.canonicalize()?;
+    let config = load_tree_config_file(&path)?;
+
+    let (server, client) = internal_server_client();
+    let runner =
+        create_server_thread::<BasicTree, BasicBlackboard>(Box::new(create_tree_support), server);
+
+    client.send_command(InteractionCommand::load_tree_config(config))?;
+    client.send_command(InteractionCommand::set_directory(path.parent()))?;
+    client.send_command(InteractionCommand::restore_blackboards(&[]))?;
+    if let Some(record) = &options.record {
+        client.send_command(InteractionCommand::record_trace(Some(record)))?;
+    }
+    if options.profile.is_some() {
+        client.send_command(InteractionCommand::profile(options.profile))?;
+    }
+
+    let mut roots = vec![];
+    let mut labels = HashMap::new();
+    let mut lines = None;
+    let mut outcome = Outcome::Running;
+    while !stop.load(Ordering::Relaxed) {
+        let Some(event) = client.get_event()? else {
+            std::thread::sleep(Duration::from_millis(1));
+            continue;
+        };
+        match event {
+            InteractionEvent::CommandResult(result) => {
+                if let Some(error) = result.error {
+                    eprintln!("{:?} failed: {error}", result.command);
+                    outcome = Outcome::Error;
+                    break;
+                }
+            }
+            InteractionEvent::TreeState(state) => {
+                labels = state
+                    .nodes
+                    .iter()
+                    .map(|n| {
+                        let label = match &n.name {
+                            Some(name) => format!("{name} ({})", n.node_type.0),
+                            None => n.node_type.0.clone(),
+                        };
+                        (n.id, label)
+                    })
+                    .collect();
+                if roots.is_empty() {
+                    // Breakpoints refer to the nodes, start running once the tree is known.
+                    start(&client, options, &state.nodes)?;
+                }
+                roots = state.roots.roots;
+                if roots.is_empty() {
+                    return Err(format!("tree {path:?} has no roots").into());
+                }
+            }
+            InteractionEvent::DebugPaused(Some(position)) => {
+                let lines = lines.get_or_insert_with(stdin_lines);
+                let Some(command) = prompt(&position, &labels, lines, stop) else {
+                    // Nobody can step or Ctrl+C was pressed, run without pausing to finish the tick.
+                    let clear = DebugCommand::SetBreakpoints(vec![]);
+                    client.send_command(InteractionCommand::debug(clear))?;
+                    client.send_command(InteractionCommand::debug(DebugCommand::Continue))?;
+                    continue;
+                };
+                client.send_command(InteractionCommand::debug(command))?;
+            }
+            InteractionEvent::ExecutionResult(result) => {
+                if let Some(new_outcome) = Outcome::from_result(&roots, &result) {
+                    outcome = new_outcome;
+                }
+                if outcome != Outcome::Running && !options.forever {
+                    break;
+                }
+            }
+            InteractionEvent::ExecutionProfile(profile) => {
+                print_profile(&profile, &labels);
+            }
+            _ => {}
+        }
+    }
+
+    // Stop ticking, this halts the running nodes, then disconnect to end the server thread.
+    client.send_command(InteractionCommand::RunSettings(RunSettings {
+        roots: Some(false),
+        interval: None,
+        specific: vec![],
+    }))?;
+    // Errors in the server thread end it early and surface as a disconnect while running.
+    drop(client);
+    let _ = runner.join();
+    Ok(outcome)
+}
+
+/// Set the breakpoints and start running the roots.
+fn start(
+    client: &impl TreeClient,
+    options: &Options,
+    nodes: &[NodeInformation],
+) -> Result<(), BetulaError> {
+    let mut breakpoints = vec![];
+    for node in &options.breakpoints {
+        let found = nodes
+            .iter()
+            .find(|n| n.name.as_ref() == Some(node) || n.id.0.to_string() == *node)
+            .ok_or(format!("no node with name or id {node:?}"))?;
+        breakpoints.push(Breakpoint {
+            node: found.id,
+            condition: None,
+        });
+    }
+    if !breakpoints.is_empty() {
+        client.send_command(InteractionCommand::debug(DebugCommand::SetBreakpoints(
+            breakpoints,
+        )))?;
+    }
+    if options.step {
+        client.send_command(InteractionCommand::debug(DebugCommand::Pause))?;
+    }
+    client.send_command(InteractionCommand::RunSettings(RunSettings {
+        roots: Some(true),
+        interval: Some(options.interval),
+        specific: vec![],
+    }))
+}
+
+/// Read the lines from stdin on a separate thread, such that waiting for them can be interrupted.
+fn stdin_lines() -> Receiver<String> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Print the paused position and read what to do next from the lines of stdin, `None` if stdin is
+/// closed or when stopped.
+fn prompt(
+    position: &DebugPosition,
+    labels: &HashMap<NodeId, String>,
+    lines: &Receiver<String>,
+    stop: &AtomicBool,
+) -> Option<DebugCommand> {
+    let label = |id: &NodeId| labels.get(id).cloned().unwrap_or_else(|| format!("{id:?}"));
+    let path: Vec<String> = position.stack.iter().map(label).collect();
+    match &position.returned {
+        None => println!("tick {}: before {}", position.tick, label(&position.node)),
+        Some(Ok(status)) => println!(
+            "tick {}: {} returned {status:?}",
+            position.tick,
+            label(&position.node)
+        ),
+        Some(Err(report)) => println!(
+            "tick {}: {} returned error \"{}\"",
+            position.tick,
+            label(&position.node),
+            report.message
+        ),
+    }
+    if !path.is_empty() {
+        println!("  in {}", path.join(" > "));
+    }
+    loop {
+        print!("(s)tep into, (n)ext to step over, (c)ontinue: ");
+        let _ = std::io::stdout().flush();
+        let line = loop {
+            if stop.load(Ordering::Relaxed) {
+                println!();
+                return None;
+            }
+            match lines.recv_timeout(PROMPT_POLL_INTERVAL) {
+                Ok(line) => break line,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    println!();
+                    return None;
+                }
+            }
+        };
+        match line.trim() {
+            "s" => return Some(DebugCommand::StepInto),
+            "n" => return Some(DebugCommand::StepOver),
+            "c" => return Some(DebugCommand::Continue),
+            _ => {}
+        }
+    }
+}
+
+fn print_profile(profile: &ExecutionProfile, labels: &HashMap<NodeId, String>) {
+    println!("profile of {} ticks:", profile.ticks);
+    println!(
+        "  {:>10} {:>10} {:>10} {:>10} {:>6}  node",
+        "total", "mean", "min", "max", "count"
+    );
+    for (id, node) in profile.slowest().iter().take(PROFILE_NODES) {
+        let label = labels.get(id).cloned().unwrap_or_else(|| format!("{id:?}"));
+        println!(
+            "  {:>10.3?} {:>10.3?} {:>10.3?} {:>10.3?} {:>6}  {label}",
+            node.total,
+            node.mean(),
+            node.min,
+            node.max,
+            node.executions
+        );
+    }
+}
+
+fn describe(status: Option<&NodeStatus>) -> String {
+    match status.map(|s| &s.status) {
+        Some(Ok(status)) => format!("{status:?}"),
+        Some(Err(report)) => format!("error \"{}\"", report.message),
+        None => "not executed".to_owned(),
+    }
+}
+
+/// Step through a recorded trace, printing the nodes that diverge from the recording.
+fn replay(options: &Options) -> Result<Outcome, BetulaError> {
+    let tree_support = create_tree_support();
+    let mut replay = TraceReplay::new(&tree_support, read_trace(&options.path)?);
+    let execute = options.execute.clone();
+    replay.set_stubbed(move |node_type| !execute.contains(&node_type.0));
+    let mut outcome = Outcome::Success;
+    while let Some(step) = replay.step()? {
+        if step.diverged.is_empty() {
+            continue;
+        }
+        outcome = Outcome::Failure;
+        println!("tick {} at {:.3}:", step.tick, step.time);
+        for id in step.diverged {
+            let recorded = step.recorded.node_status.iter().rfind(|s| s.node == id);
+            let replayed = step.replayed.node_status.iter().rfind(|s| s.node == id);
+            let node_type = replay
+                .tree()
+                .node_ref(id)
+                .map(|n| n.borrow().node_type().0)
+                .unwrap_or_default();
+            println!(
+                "  {id:?} {node_type}: recorded {}, replayed {}",
+                describe(recorded),
+                describe(replayed)
+            );
+        }
+    }
+    Ok(outcome)
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let handler_stop = Arc::clone(&stop);
+    if let Err(e) = ctrlc::set_handler(move || handler_stop.store(true, Ordering::Relaxed)) {
+        eprintln!("failed to set the Ctrl+C handler: {e}");
+        return ExitCode::from(2);
+    }
+
+    if options.replay {
+        return match replay(&options) {
+            Ok(outcome) => outcome.exit_code(),
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::from(2)
+            }
+        };
+    }
+
+    match run(&options, &stop) {
+        Ok(outcome) => outcome.exit_code(),
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use betula_common::TreeSupport;
+#[cfg(feature = "editor")]
+use betula_editor::UiSupport;
+
+/// Factory function for the ui support, with all nodes in the workspace.
+#[cfg(feature = "editor")]
+pub fn create_ui_support() -> UiSupport {
+    let mut ui_support = UiSupport::new();
+    ui_support
+        .tree_support_mut()
+        .set_blackboard_factory(Box::new(|| {
+            Box::new(betula_core::basic::BasicBlackboard::default())
+        }));
+    betula_enigo::add_ui_support(&mut ui_support);
+    betula_std::add_ui_support(&mut ui_support);
+    betula_wm::add_ui_support(&mut ui_support);
+    betula_hotkey::add_ui_support(&mut ui_support);
+    betula_image::add_ui_support(&mut ui_support);
+    betula_overlay::add_ui_support(&mut ui_support);
+    ui_support.add_node_subtree(std::rc::Rc::new(|| create_ui_support().into_tree_support()));
+    ui_support.add_node_placeholder();
+    ui_support
+}
+
+/// Factory function for the tree support, with all nodes in the workspace but without the editor.
+pub fn create_tree_support() -> TreeSupport {
+    let mut tree_support = TreeSupport::new();
+    tree_support.set_blackboard_factory(Box::new(|| {
+        Box::new(betula_core::basic::BasicBlackboard::default())
+    }));
+    betula_enigo::add_tree_support(&mut tree_support);
+    betula_std::add_tree_support(&mut tree_support);
+    betula_wm::add_tree_support(&mut tree_support);
+    betula_hotkey::add_tree_support(&mut tree_support);
+    betula_image::add_tree_support(&mut tree_support);
+    betula_overlay::add_tree_support(&mut tree_support);
+    tree_support.add_node_subtree(std::rc::Rc::new(create_tree_support));
+    tree_support.add_node_placeholder();
+    tree_support
+}
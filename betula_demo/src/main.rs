@@ -1,23 +1,7 @@
 use betula_common::{control::internal_server_client, create_server_thread};
 use betula_core::basic::{BasicBlackboard, BasicTree};
-use betula_editor::{editor::BetulaEditor, UiSupport};
-
-// Factory function for the ui support.
-fn create_ui_support() -> UiSupport {
-    let mut ui_support = UiSupport::new();
-    ui_support
-        .tree_support_mut()
-        .set_blackboard_factory(Box::new(|| {
-            Box::new(betula_core::basic::BasicBlackboard::default())
-        }));
-    betula_enigo::add_ui_support(&mut ui_support);
-    betula_std::add_ui_support(&mut ui_support);
-    betula_wm::add_ui_support(&mut ui_support);
-    betula_hotkey::add_ui_support(&mut ui_support);
-    betula_image::add_ui_support(&mut ui_support);
-    betula_overlay::add_ui_support(&mut ui_support);
-    ui_support
-}
+use betula_demo::create_ui_support;
+use betula_editor::editor::BetulaEditor;
 
 fn service_overlays(editor: &mut BetulaEditor, ui: &mut egui::Ui, frame: &mut eframe::Frame) {
     let _ = (editor, frame);
@@ -0,0 +1,442 @@
+/*!
+Derive macros to remove the boilerplate of implementing [`Node`](../betula_core/trait.Node.html).
+
+The [`Node`](derive@Node) derive generates the port, configuration and type methods from the
+fields of a struct. The [`node_impl`](macro@node_impl) attribute adds those methods to the
+`impl Node` block, methods that are written out in the block take precedence:
+```
+use betula_core::node_prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ScaleNodeConfig {
+    pub factor: f64,
+}
+impl IsNodeConfig for ScaleNodeConfig {}
+
+#[derive(Debug, Default, betula_derive::Node)]
+#[node(type = "scale", ui(title = "scale", category = "provider/math/scale"))]
+pub struct ScaleNode {
+    #[input("value")]
+    input: Input<f64>,
+    #[input("offset", default = 0.0)]
+    offset: Input<f64>,
+    #[output("scaled", default = 1.0)]
+    output: Output<f64>,
+    #[config]
+    config: ScaleNodeConfig,
+}
+
+#[betula_derive::node_impl]
+impl Node for ScaleNode {
+    fn execute(&mut self, _ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+        let value = self.input.get()? * self.config.factor + self.offset.get()?;
+        self.output.set(value)?;
+        Ok(ExecutionStatus::Success)
+    }
+}
+
+let node = ScaleNode::default();
+assert_eq!(ScaleNode::static_type(), "scale".into());
+assert_eq!(node.ports().unwrap().len(), 3);
+assert!(node.get_config().unwrap().is_some());
+```
+
+Attributes on the struct, through `#[node(...)]`:
+- `type = "..."` the [`NodeType`](../betula_core/struct.NodeType.html) of the node, required.
+- `ui(title = "...", category = "folder/name", group = "...")` implements `UiNode` if the
+  `betula_editor` feature of the crate using the derive is enabled. The last segment of the
+  category is the name, the others are folders. All entries are optional.
+
+Attributes on the fields:
+- `#[input("name")]` on an `Input<T>`, an optional input with `#[input("name", default = expr)]`.
+- `#[output("name")]` on an `Output<T>`, initialised with `T::default()` unless
+  `#[output("name", default = expr)]` is used.
+- `#[config]` on the configuration of the node, it must implement `Clone` and `IsNodeConfig`.
+*/
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse::ParseStream;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Expr, Fields, GenericArgument, Ident, ImplItem, ItemImpl,
+    LitStr, PathArguments, Token, Type,
+};
+
+/// A port declared by a field.
+struct PortField {
+    field: Ident,
+    name: LitStr,
+    value_type: Type,
+    default: Option<Expr>,
+}
+
+/// The user interface declared by `#[node(ui(...))]`.
+#[derive(Default)]
+struct UiAttributes {
+    title: Option<LitStr>,
+    category: Option<LitStr>,
+    group: Option<LitStr>,
+}
+
+/// Obtain `T` from a field of type `Input<T>` or `Output<T>`.
+fn port_value_type(ty: &Type, wrapper: &str) -> syn::Result<Type> {
+    let value_type = match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .filter(|segment| segment.ident == wrapper)
+            .and_then(|segment| match &segment.arguments {
+                PathArguments::AngleBracketed(args) => args.args.first(),
+                _ => None,
+            })
+            .and_then(|arg| match arg {
+                GenericArgument::Type(value_type) => Some(value_type.clone()),
+                _ => None,
+            }),
+        _ => None,
+    };
+    value_type.ok_or_else(|| {
+        syn::Error::new_spanned(ty, format!("expected a field of type {wrapper}<T>"))
+    })
+}
+
+/// Parse the arguments of `#[input("name", default = expr)]` and `#[output(...)]`.
+fn port_arguments(input: ParseStream) -> syn::Result<(LitStr, Option<Expr>)> {
+    let name: LitStr = input.parse()?;
+    let mut default = None;
+    if input.peek(Token![,]) {
+        input.parse::<Token![,]>()?;
+        let key: Ident = input.parse()?;
+        if key != "default" {
+            return Err(syn::Error::new_spanned(key, "expected `default = ...`"));
+        }
+        input.parse::<Token![=]>()?;
+        default = Some(input.parse()?);
+    }
+    Ok((name, default))
+}
+
+fn derive_node_impl(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let mut node_type: Option<LitStr> = None;
+    let mut ui: Option<UiAttributes> = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("node")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("type") {
+                node_type = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("ui") {
+                let ui = ui.get_or_insert_with(Default::default);
+                meta.parse_nested_meta(|meta| {
+                    let value = Some(meta.value()?.parse()?);
+                    if meta.path.is_ident("title") {
+                        ui.title = value;
+                    } else if meta.path.is_ident("category") {
+                        ui.category = value;
+                    } else if meta.path.is_ident("group") {
+                        ui.group = value;
+                    } else {
+                        return Err(meta.error("expected `title`, `category` or `group`"));
+                    }
+                    Ok(())
+                })
+            } else {
+                Err(meta.error("expected `type` or `ui`"))
+            }
+        })?;
+    }
+    let node_type = node_type.ok_or_else(|| {
+        syn::Error::new_spanned(ident, "missing `#[node(type = \"...\")]` attribute")
+    })?;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "can only derive Node for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "can only derive Node for named fields",
+        ));
+    };
+
+    let mut inputs = vec![];
+    let mut outputs = vec![];
+    let mut config = None;
+    for field in fields.named.iter() {
+        let field_ident = field.ident.clone().expect("fields are named");
+        for attr in field.attrs.iter() {
+            if attr.path().is_ident("input") || attr.path().is_ident("output") {
+                let is_input = attr.path().is_ident("input");
+                let wrapper = if is_input { "Input" } else { "Output" };
+                let (name, default) = attr.parse_args_with(port_arguments)?;
+                let port = PortField {
+                    field: field_ident.clone(),
+                    name,
+                    value_type: port_value_type(&field.ty, wrapper)?,
+                    default,
+                };
+                if is_input {
+                    inputs.push(port);
+                } else {
+                    outputs.push(port);
+                }
+            } else if attr.path().is_ident("config") {
+                if config.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "only one field can be config",
+                    ));
+                }
+                config = Some(field_ident.clone());
+            }
+        }
+    }
+
+    let input_ports = inputs.iter().map(|p| {
+        let PortField {
+            name, value_type, ..
+        } = p;
+        match &p.default {
+            Some(default) => quote! {
+                ::betula_core::blackboard::Port::input_optional::<#value_type>(#name, #default)
+            },
+            None => quote! { ::betula_core::blackboard::Port::input::<#value_type>(#name) },
+        }
+    });
+    let output_ports = outputs.iter().map(|p| {
+        let PortField {
+            name, value_type, ..
+        } = p;
+        quote! { ::betula_core::blackboard::Port::output::<#value_type>(#name) }
+    });
+    let setup_inputs = inputs.iter().map(|p| {
+        let PortField {
+            field,
+            name,
+            value_type,
+            ..
+        } = p;
+        quote! { self.#field = interface.input::<#value_type>(#name)?; }
+    });
+    let setup_outputs = outputs.iter().map(|p| {
+        let PortField {
+            field,
+            name,
+            value_type,
+            ..
+        } = p;
+        let default = match &p.default {
+            Some(default) => quote! { #default },
+            None => quote! { ::std::default::Default::default() },
+        };
+        quote! { self.#field = interface.output::<#value_type>(#name, #default)?; }
+    });
+    let config_methods = config.map(|config| {
+        quote! {
+            fn derived_get_config(
+                &self,
+            ) -> ::std::result::Result<
+                ::std::option::Option<::std::boxed::Box<dyn ::betula_core::NodeConfig>>,
+                ::betula_core::NodeError,
+            > {
+                Ok(Some(::std::boxed::Box::new(self.#config.clone())))
+            }
+            fn derived_set_config(
+                &mut self,
+                config: &dyn ::betula_core::NodeConfig,
+            ) -> ::std::result::Result<(), ::betula_core::NodeError> {
+                use ::betula_core::NodeConfigLoad;
+                self.#config.load_node_config(config)
+            }
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let ui_impl = ui.map(|ui| {
+        let title = ui.title.map(|title| {
+            quote! {
+                fn ui_title(&self) -> ::std::string::String {
+                    #title.to_owned()
+                }
+            }
+        });
+        let category = ui.category.map(|category| {
+            let value = category.value();
+            let mut segments: Vec<&str> = value.split('/').collect();
+            let name = segments.pop().unwrap_or_default();
+            let group = ui.group.iter();
+            quote! {
+                fn ui_category() -> ::std::vec::Vec<::betula_editor::UiNodeCategory> {
+                    vec![
+                        #(::betula_editor::UiNodeCategory::Folder(#segments.to_owned()),)*
+                        #(::betula_editor::UiNodeCategory::Group(#group.to_owned()),)*
+                        ::betula_editor::UiNodeCategory::Name(#name.to_owned()),
+                    ]
+                }
+            }
+        });
+        quote! {
+            #[cfg(feature = "betula_editor")]
+            impl #impl_generics ::betula_editor::UiNode for #ident #ty_generics #where_clause {
+                #title
+                #category
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::betula_core::derived::DerivedNode for #ident #ty_generics #where_clause {
+            fn derived_static_type() -> ::betula_core::NodeType {
+                #node_type.into()
+            }
+            fn derived_ports(
+                &self,
+            ) -> ::std::result::Result<
+                ::std::vec::Vec<::betula_core::blackboard::Port>,
+                ::betula_core::NodeError,
+            > {
+                Ok(vec![#(#input_ports,)* #(#output_ports,)*])
+            }
+            fn derived_setup_inputs(
+                &mut self,
+                interface: &mut dyn ::betula_core::blackboard::BlackboardInputInterface,
+            ) -> ::std::result::Result<(), ::betula_core::NodeError> {
+                use ::betula_core::blackboard::SetupInput;
+                let _ = &interface;
+                #(#setup_inputs)*
+                Ok(())
+            }
+            fn derived_setup_outputs(
+                &mut self,
+                interface: &mut dyn ::betula_core::blackboard::BlackboardOutputInterface,
+            ) -> ::std::result::Result<(), ::betula_core::NodeError> {
+                use ::betula_core::blackboard::SetupOutput;
+                let _ = &interface;
+                #(#setup_outputs)*
+                Ok(())
+            }
+            #config_methods
+        }
+        #ui_impl
+    })
+}
+
+/// Derive `betula_core::derived::DerivedNode` from the attributes on the struct and its fields.
+///
+/// See the [crate documentation](crate) for the attributes.
+#[proc_macro_derive(Node, attributes(node, input, output, config))]
+pub fn derive_node(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive_node_impl(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Add the methods derived by [`Node`](derive@Node) to an `impl Node` block.
+///
+/// Adds `ports`, `setup_inputs`, `setup_outputs`, `static_type`, `node_type`, `get_config` and
+/// `set_config`, unless they are already implemented in the block.
+#[proc_macro_attribute]
+pub fn node_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let _ = attr;
+    let mut item_impl = parse_macro_input!(item as ItemImpl);
+    let existing: Vec<String> = item_impl
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Fn(f) => Some(f.sig.ident.to_string()),
+            _ => None,
+        })
+        .collect();
+
+    let methods: [(&str, TokenStream2); 7] = [
+        (
+            "ports",
+            quote! {
+                fn ports(
+                    &self,
+                ) -> ::std::result::Result<
+                    ::std::vec::Vec<::betula_core::blackboard::Port>,
+                    ::betula_core::NodeError,
+                > {
+                    <Self as ::betula_core::derived::DerivedNode>::derived_ports(self)
+                }
+            },
+        ),
+        (
+            "setup_inputs",
+            quote! {
+                fn setup_inputs(
+                    &mut self,
+                    interface: &mut dyn ::betula_core::blackboard::BlackboardInputInterface,
+                ) -> ::std::result::Result<(), ::betula_core::NodeError> {
+                    <Self as ::betula_core::derived::DerivedNode>::derived_setup_inputs(self, interface)
+                }
+            },
+        ),
+        (
+            "setup_outputs",
+            quote! {
+                fn setup_outputs(
+                    &mut self,
+                    interface: &mut dyn ::betula_core::blackboard::BlackboardOutputInterface,
+                ) -> ::std::result::Result<(), ::betula_core::NodeError> {
+                    <Self as ::betula_core::derived::DerivedNode>::derived_setup_outputs(self, interface)
+                }
+            },
+        ),
+        (
+            "static_type",
+            quote! {
+                fn static_type() -> ::betula_core::NodeType {
+                    <Self as ::betula_core::derived::DerivedNode>::derived_static_type()
+                }
+            },
+        ),
+        (
+            "node_type",
+            quote! {
+                fn node_type(&self) -> ::betula_core::NodeType {
+                    <Self as ::betula_core::derived::DerivedNode>::derived_static_type()
+                }
+            },
+        ),
+        (
+            "get_config",
+            quote! {
+                fn get_config(
+                    &self,
+                ) -> ::std::result::Result<
+                    ::std::option::Option<::std::boxed::Box<dyn ::betula_core::NodeConfig>>,
+                    ::betula_core::NodeError,
+                > {
+                    <Self as ::betula_core::derived::DerivedNode>::derived_get_config(self)
+                }
+            },
+        ),
+        (
+            "set_config",
+            quote! {
+                fn set_config(
+                    &mut self,
+                    config: &dyn ::betula_core::NodeConfig,
+                ) -> ::std::result::Result<(), ::betula_core::NodeError> {
+                    <Self as ::betula_core::derived::DerivedNode>::derived_set_config(self, config)
+                }
+            },
+        ),
+    ];
+    for (name, method) in methods {
+        if !existing.iter().any(|e| e == name) {
+            item_impl
+                .items
+                .push(syn::parse2(method).expect("generated method should parse"));
+        }
+    }
+    quote! { #item_impl }.into()
+}
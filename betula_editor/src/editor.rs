@@ -194,6 +194,16 @@ impl BetulaEditor {
         self.client.send_command(cmd)
     }
 
+    fn send_snapshot_blackboards(&self) -> Result<(), BetulaError> {
+        let cmd = InteractionCommand::snapshot_blackboards(&[]);
+        self.client.send_command(cmd)
+    }
+
+    fn send_restore_blackboards(&self) -> Result<(), BetulaError> {
+        let cmd = InteractionCommand::restore_blackboards(&[]);
+        self.client.send_command(cmd)
+    }
+
     fn save_tree_config(&mut self, tree: TreeConfig) -> Result<(), BetulaError> {
         // Two options, one is we have a save_path, otherwise it is a request for a prompt.
         let editor = EditorState {
@@ -212,6 +222,7 @@ impl BetulaEditor {
                 self.set_project_path(Some(destination.clone()));
                 let dir = destination.parent();
                 self.send_set_directory(dir)?;
+                self.send_snapshot_blackboards()?;
             }
         } else {
             let send_channel = self.tree_config_save_channel.0.clone();
@@ -316,12 +327,16 @@ impl BetulaEditor {
             // Also call set directory for this new directory.
             let dir = dir_path.parent();
             self.send_set_directory(dir)?;
+
+            // Persistent blackboard values are restored from the snapshot in this directory.
+            self.send_restore_blackboards()?;
         }
         if let Ok(new_path) = self.tree_config_save_channel.1.try_recv() {
             // Save as happened, set the new path and use it as the new directory.
             self.set_project_path(Some(new_path.clone()));
             let dir = new_path.parent();
             self.send_set_directory(dir)?;
+            self.send_snapshot_blackboards()?;
         }
 
         loop {
@@ -410,6 +425,36 @@ impl BetulaEditor {
                                 println!("Failed to request config: {e:?}");
                             }
                         }
+                        ui.separator();
+                        if ui
+                            .add_enabled(
+                                self.path.is_some(),
+                                egui::Button::new("Snapshot blackboards"),
+                            )
+                            .on_hover_text(
+                                "Store persistent blackboard values in the project directory.",
+                            )
+                            .clicked()
+                        {
+                            if let Err(e) = self.send_snapshot_blackboards() {
+                                println!("Failed to snapshot blackboards: {e:?}");
+                            }
+                        }
+                        if ui
+                            .add_enabled(
+                                self.path.is_some(),
+                                egui::Button::new("Restore blackboards"),
+                            )
+                            .on_hover_text(
+                                "Restore persistent blackboard values from the project directory.",
+                            )
+                            .clicked()
+                        {
+                            if let Err(e) = self.send_restore_blackboards() {
+                                println!("Failed to restore blackboards: {e:?}");
+                            }
+                        }
+                        ui.separator();
                         if ui.button("Quit").clicked() {
                             ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close)
                         }
@@ -4,6 +4,9 @@
 mod ui;
 pub use ui::{UiConfigResponse, UiNode, UiNodeCategory, UiNodeContext, UiSupport, UiValue};
 
+mod placeholder_node;
+mod subtree_node;
+
 mod viewer;
 pub use viewer::{BetulaViewer, BetulaViewerNode, ViewerNode};
 
@@ -0,0 +1,29 @@
+use crate::{UiConfigResponse, UiNode, UiNodeContext};
+use betula_common::placeholder_node::PlaceholderNode;
+
+impl UiNode for PlaceholderNode {
+    fn ui_title(&self) -> String {
+        format!("{} (unsupported)", self.config.node_type.0)
+    }
+
+    fn ui_icon(&self, ui: &mut egui::Ui, desired_size: egui::Vec2) {
+        let _ = desired_size;
+        ui.add(egui::Label::new("❓").selectable(false));
+    }
+
+    fn ui_config(
+        &mut self,
+        ctx: &dyn UiNodeContext,
+        ui: &mut egui::Ui,
+        scale: f32,
+    ) -> UiConfigResponse {
+        let _ = (ctx, scale);
+        ui.vertical(|ui| {
+            ui.label("⚠ node type not available, kept as is");
+            if let Some(config) = self.config.config.as_ref() {
+                ui.label(config.to_string());
+            }
+        });
+        UiConfigResponse::UnChanged
+    }
+}
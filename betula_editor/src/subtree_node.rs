@@ -0,0 +1,112 @@
+use crate::{UiConfigResponse, UiNode, UiNodeCategory, UiNodeContext};
+use betula_common::subtree_node::{SubTreeNode, SubTreePort};
+use betula_core::blackboard::PortDirection;
+
+impl UiNode for SubTreeNode {
+    fn ui_title(&self) -> String {
+        if let Some(file) = self.config.file.as_ref() {
+            format!("subtree {file}")
+        } else {
+            "subtree".to_owned()
+        }
+    }
+
+    fn ui_icon(&self, ui: &mut egui::Ui, desired_size: egui::Vec2) {
+        let _ = desired_size;
+        ui.add(egui::Label::new("🌳").selectable(false));
+    }
+
+    fn ui_config(
+        &mut self,
+        ctx: &dyn UiNodeContext,
+        ui: &mut egui::Ui,
+        scale: f32,
+    ) -> UiConfigResponse {
+        let _ = ctx;
+        let mut modified = false;
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                ui.label("File");
+                let mut file = self.config.file.clone().unwrap_or_default();
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut file)
+                        .hint_text("tree file, relative to the directory")
+                        .min_size(egui::vec2(100.0 * scale, 0.0)),
+                );
+                if response.changed() {
+                    self.config.file = if file.is_empty() { None } else { Some(file) };
+                }
+                // Only apply the file when done typing, such that it isn't loaded for every key.
+                modified |= response.lost_focus();
+            });
+
+            let mut remove = None;
+            for (i, port) in self.config.ports.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    let direction = match port.direction {
+                        PortDirection::Input => "in",
+                        PortDirection::Output => "out",
+                    };
+                    if ui
+                        .button(direction)
+                        .on_hover_text("toggle the port direction")
+                        .clicked()
+                    {
+                        port.direction = match port.direction {
+                            PortDirection::Input => PortDirection::Output,
+                            PortDirection::Output => PortDirection::Input,
+                        };
+                        modified = true;
+                    }
+                    let r = ui.add(
+                        egui::TextEdit::singleline(&mut port.blackboard)
+                            .hint_text("blackboard")
+                            .desired_width(60.0 * scale),
+                    );
+                    modified |= r.changed();
+                    let r = ui.add(
+                        egui::TextEdit::singleline(&mut port.name.0)
+                            .hint_text("port")
+                            .desired_width(60.0 * scale),
+                    );
+                    modified |= r.changed();
+                    if ui.button("🗑").clicked() {
+                        remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                self.config.ports.remove(i);
+                modified = true;
+            }
+            if ui.button("add port").clicked() {
+                self.config.ports.push(SubTreePort {
+                    blackboard: String::new(),
+                    name: "".into(),
+                    direction: PortDirection::Input,
+                });
+                modified = true;
+            }
+
+            if let Some(e) = self.load_error() {
+                ui.label(format!("⚠ {e}"));
+            }
+        });
+
+        if modified {
+            // Update the ports, the tree is loaded in the same way on the server. Errors are shown
+            // above, from the next frame on.
+            let _ = self.update();
+            UiConfigResponse::Changed
+        } else {
+            UiConfigResponse::UnChanged
+        }
+    }
+
+    fn ui_category() -> Vec<UiNodeCategory> {
+        vec![
+            UiNodeCategory::Folder("structural".to_owned()),
+            UiNodeCategory::Name("subtree".to_owned()),
+        ]
+    }
+}
@@ -1,6 +1,10 @@
 use betula_common::{
-    tree_support::SerializedBlackboardValues, tree_support::SerializedValue,
-    type_support::DefaultValueRequirements, TreeSupport,
+    placeholder_node::PlaceholderNode,
+    subtree_node::{SubTreeNode, SubTreeSupportCreator},
+    tree_support::SerializedBlackboardValues,
+    tree_support::SerializedValue,
+    type_support::DefaultValueRequirements,
+    TreeSupport,
 };
 use egui::Ui;
 use std::collections::HashMap;
@@ -46,9 +50,9 @@ pub trait UiNode: Node {
         let _ = (ui, desired_size);
     }
 
-    /// The range of children this node may have.
+    /// The range of children this node may have, defaults to [`Node::child_range`].
     fn ui_child_range(&self) -> std::ops::Range<usize> {
-        0..usize::MAX
+        self.child_range()
     }
 
     /// Function to render the ui, responds whether changes were made.
@@ -253,14 +257,43 @@ impl UiSupport {
         &mut self,
     ) {
         self.tree.add_node_default::<T>();
+        self.add_ui_node(
+            T::static_type(),
+            T::ui_category(),
+            Box::new(|| Box::new(T::default())),
+        );
+    }
+
+    /// Show nodes of unsupported types as a [`PlaceholderNode`], see
+    /// [`TreeSupport::add_node_placeholder`].
+    pub fn add_node_placeholder(&mut self) {
+        self.tree.add_node_placeholder();
+    }
+
+    /// Add support for the [`betula_common::subtree_node::SubTreeNode`], subtrees are loaded with
+    /// tree support from `creator`.
+    pub fn add_node_subtree(&mut self, creator: SubTreeSupportCreator) {
+        self.tree.add_node_subtree(creator.clone());
+        self.add_ui_node(
+            SubTreeNode::static_type(),
+            SubTreeNode::ui_category(),
+            Box::new(move || Box::new(SubTreeNode::with_support(creator.clone()))),
+        );
+    }
+
+    fn add_ui_node(
+        &mut self,
+        node_type: NodeType,
+        category: Vec<UiNodeCategory>,
+        node_factory: UiNodeFactory,
+    ) {
         let ui_support = UiNodeSupport {
-            display_name: T::static_type().0.clone(),
-            node_factory: Box::new(|| Box::new(T::default())),
+            display_name: node_type.0.clone(),
+            node_factory,
         };
-        self.ui_node.insert(T::static_type(), ui_support);
+        self.ui_node.insert(node_type.clone(), ui_support);
 
         // Go from categories to the tree;
-        let category = T::ui_category();
         let mut current = &mut self.node_categories;
         for c in category {
             match c {
@@ -277,7 +310,7 @@ impl UiSupport {
                         .sub_elements()
                 }
                 UiNodeCategory::Name(ref v) => {
-                    current.insert(v.to_owned(), UiCategoryNode::Value(T::static_type()));
+                    current.insert(v.to_owned(), UiCategoryNode::Value(node_type.clone()));
                 }
             }
         }
@@ -293,6 +326,16 @@ impl UiSupport {
         self.add_node_default::<N>();
     }
 
+    pub fn add_node_default_with_versioned_config<
+        N: UiNode + betula_common::type_support::DefaultNodeFactoryRequirements,
+        C: betula_common::type_support::VersionedConfigRequirements,
+    >(
+        &mut self,
+    ) {
+        self.tree.add_node_default_with_versioned_config::<N, C>();
+        self.add_node_default::<N>();
+    }
+
     pub fn add_value_default_named<V: betula_common::type_support::DefaultValueRequirements>(
         &mut self,
         name: &str,
@@ -327,6 +370,14 @@ impl UiSupport {
             .insert(std::any::type_name::<V>().to_owned(), value_support);
     }
 
+    /// Add a conversion between value types, see [`TreeSupport::add_conversion`].
+    pub fn add_conversion<F: 'static + Chalkable, T: 'static + Chalkable>(
+        &mut self,
+        convert: impl Fn(&F) -> Result<T, BetulaError> + Send + Sync + 'static,
+    ) {
+        self.tree.add_conversion::<F, T>(convert)
+    }
+
     /*
     pub fn set_blackboard_factory(&mut self, blackboard_factory: betula_common::tree_support::BlackboardFactory) {
         self.tree.set_blackboard_factory(blackboard_factory);
@@ -352,6 +403,8 @@ impl UiSupport {
     pub fn create_ui_node(&self, node_type: &NodeType) -> Result<Box<dyn UiNode>, BetulaError> {
         if let Some(node_support) = self.node_support(node_type) {
             Ok((node_support.node_factory)())
+        } else if self.tree.is_placeholder(node_type) {
+            Ok(Box::new(PlaceholderNode::default()))
         } else {
             Err(format!("no ui node support for {node_type:?}").into())
         }
@@ -58,6 +58,7 @@ use egui::{Color32, Ui};
 
 use betula_core::{
     blackboard::{BlackboardPort, NodePort, PortConnection, PortDirection, PortName},
+    error::ErrorReport,
     BetulaError, BlackboardId, ExecutionStatus, NodeId as BetulaNodeId, NodeType,
 };
 
@@ -82,7 +83,7 @@ const UNKNOWN_COLOR: Color32 = Color32::from_rgb(0x80, 0x80, 0x80);
 
 fn color_with_status(
     current: Color32,
-    status: Option<&Result<ExecutionStatus, String>>,
+    status: Option<&Result<ExecutionStatus, ErrorReport>>,
     saturation_bump: f32,
     value_bump: (f32, f32),
 ) -> Option<Color32> {
@@ -114,7 +115,7 @@ fn color_with_status(
 
 fn color_wire_status(
     current: Color32,
-    status: Option<&Result<ExecutionStatus, String>>,
+    status: Option<&Result<ExecutionStatus, ErrorReport>>,
 ) -> Option<Color32> {
     let value_threshold = 0.0;
     let value_increase = 0.0;
@@ -128,7 +129,7 @@ fn color_wire_status(
 }
 fn color_edge_status(
     current: Color32,
-    status: Option<&Result<ExecutionStatus, String>>,
+    status: Option<&Result<ExecutionStatus, ErrorReport>>,
 ) -> Option<Color32> {
     let value_threshold = 0.5;
     let value_increase = 0.5;
@@ -150,7 +151,7 @@ pub struct NodeData {
     /// The previous node execution status.
     ///
     /// Used for coloring the node border if enabled.
-    node_status: Option<Result<ExecutionStatus, String>>,
+    node_status: Option<Result<ExecutionStatus, ErrorReport>>,
 
     /// The actual ui node handling.
     ui_node: Box<dyn UiNode>,
@@ -527,6 +528,9 @@ pub struct BlackboardData {
 
     should_remove: bool,
     should_prune: Option<Vec<PortName>>,
+
+    persistent_remote: BTreeSet<PortName>,
+    persistent_local: Option<BTreeSet<PortName>>,
 }
 impl BlackboardData {
     /// Return whether local and remote are identical.
@@ -1023,6 +1027,7 @@ impl ViewerBlackboard {
             }
         });
 
+        let mut persistent_local = None;
         for name in data.ui_values.keys() {
             ui.menu_button(name.to_string(), |ui| {
                 let mut currently_shown = self.ports.contains_key(name);
@@ -1036,6 +1041,19 @@ impl ViewerBlackboard {
                     }
                     self.is_dirty = true;
                 }
+                let mut persistent = data.persistent_remote.contains(name);
+                let r = ui
+                    .checkbox(&mut persistent, "Persistent")
+                    .on_hover_text("Store this value in blackboard snapshots.");
+                if r.changed() {
+                    let mut ports = data.persistent_remote.clone();
+                    if persistent {
+                        ports.insert(name.clone());
+                    } else {
+                        ports.remove(name);
+                    }
+                    persistent_local = Some(ports);
+                }
                 for (label, direction) in [
                     ("⬅ Writers", PortDirection::Output),
                     ("➡ Readers", PortDirection::Input),
@@ -1071,6 +1089,9 @@ impl ViewerBlackboard {
                 }
             });
         }
+        if persistent_local.is_some() {
+            data.persistent_local = persistent_local;
+        }
     }
 }
 
@@ -1664,6 +1685,12 @@ impl BetulaViewer {
                 let cmd = InteractionCommand::remove_blackboard_ports(blackboard.id, &v);
                 self.client.send_command(cmd)?;
             }
+            if let Some(v) = blackboard.persistent_local.take() {
+                let ports = v.into_iter().collect::<Vec<_>>();
+                let cmd =
+                    InteractionCommand::set_blackboard_persistent_ports(blackboard.id, &ports);
+                self.client.send_command(cmd)?;
+            }
         }
 
         if self.tree_roots_remote != self.tree_roots_local {
@@ -1929,6 +1956,7 @@ impl BetulaViewer {
                 }
                 bb.update_values(&self.ui_support, v.port_values)?;
                 (*bb).name_remote = v.name;
+                (*bb).persistent_remote = v.persistent.iter().cloned().collect();
             }
 
             // Handle any pending connections.
@@ -1954,6 +1982,8 @@ impl BetulaViewer {
                 name_local: None,
                 should_remove: false,
                 should_prune: None,
+                persistent_remote: v.persistent.iter().cloned().collect(),
+                persistent_local: None,
             }));
             let cloned_rc = Rc::clone(&rc);
             self.blackboards.insert(v.id, cloned_rc);
@@ -1976,7 +2006,8 @@ impl BetulaViewer {
     pub fn service(&mut self, snarl: &mut Snarl<BetulaViewerNode>) -> Result<(), BetulaError> {
         use betula_common::control::InteractionCommand::RemoveNode;
         use betula_common::control::InteractionCommand::{
-            AddBlackboard, PortDisconnectConnect, RemoveBlackboard,
+            AddBlackboard, PortDisconnectConnect, RemoveBlackboard, RestoreBlackboards,
+            SnapshotBlackboards,
         };
         use betula_common::control::InteractionEvent;
 
@@ -2046,6 +2077,11 @@ impl BetulaViewer {
                                     snarl.remove_node(snarl_id);
                                 }
                             }
+                            SnapshotBlackboards(_) | RestoreBlackboards(_) => {
+                                if let Some(failure_reason) = c.error {
+                                    println!("Blackboard snapshot failed: {failure_reason}");
+                                }
+                            }
                             _ => {}
                         }
                     }
@@ -2061,6 +2097,12 @@ impl BetulaViewer {
                     InteractionEvent::ExecutionResult(results) => {
                         self.clear_execution_results(snarl);
                         self.set_execution_results(&results.node_status);
+                    }
+                    InteractionEvent::ExecutionProfile(_) => {
+                        // The editor doesn't request profiles, betula_run prints them.
+                    }
+                    InteractionEvent::DebugPaused(_) => {
+                        // The editor doesn't set breakpoints, betula_run does.
                     } // unhandled => panic!("unhandled event: {unhandled:?}"),
                 }
             } else {
@@ -2524,8 +2566,28 @@ impl SnarlViewer<BetulaViewerNode> for BetulaViewer {
                             if let Some(port) = ui_node.ui_input_port(input_port) {
                                 let display_name =
                                     self.ui_support.port_display_name(&port.port_type());
-                                ui.label(format!("{:} [{:}]", port.name().as_ref(), display_name));
-                                PinInfo::triangle().with_fill(BLACKBOARD_COLOR)
+                                match port.default_value() {
+                                    // Unconnected optional ports show the default they use.
+                                    Some(default) if pin.remotes.is_empty() => {
+                                        ui.label(format!(
+                                            "{:} [{:}] = {:?}",
+                                            port.name().as_ref(),
+                                            display_name,
+                                            default
+                                        ));
+                                        PinInfo::triangle()
+                                            .with_fill(BLACKBOARD_COLOR)
+                                            .with_gamma(0.5)
+                                    }
+                                    _ => {
+                                        ui.label(format!(
+                                            "{:} [{:}]",
+                                            port.name().as_ref(),
+                                            display_name
+                                        ));
+                                        PinInfo::triangle().with_fill(BLACKBOARD_COLOR)
+                                    }
+                                }
                             } else {
                                 unreachable!("tried to get pin for input beyond range");
                             }
@@ -2716,7 +2778,12 @@ impl SnarlViewer<BetulaViewerNode> for BetulaViewer {
                     let r =
                         r.on_hover_text(format!("type: {}", data.ui_node.node_type().0.as_str()));
                     if let Some(Err(e)) = &data.node_status {
-                        r.on_hover_text(e);
+                        match e.node {
+                            Some(origin) if origin != data.id => {
+                                r.on_hover_text(format!("error from {origin:?}: {e}"))
+                            }
+                            _ => r.on_hover_text(format!("{:?}: {e}", e.kind)),
+                        };
                     }
                     data.ui_node
                         .ui_icon(ui, egui::vec2(14.0 * scale, 14.0 * scale));
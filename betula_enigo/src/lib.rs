@@ -204,13 +204,29 @@ pub struct CursorPosition {
     pub y: i32,
 }
 
+/// Register nodes to the tree support, for use without the editor.
+#[cfg(feature = "betula_common")]
+pub fn add_tree_support(tree_support: &mut betula_common::TreeSupport) {
+    tree_support
+        .add_node_default_with_config::<nodes::EnigoInstanceNode, nodes::EnigoInstanceNodeConfig>();
+    tree_support
+        .add_node_default_with_versioned_config::<nodes::EnigoNode, nodes::EnigoNodeConfig>();
+    tree_support.add_node_default_with_config::<nodes::EnigoReadNode, nodes::EnigoReadNodeConfig>();
+    tree_support
+        .add_node_default_with_config::<nodes::CursorScannerNode, nodes::CursorScannerNodeConfig>();
+    tree_support.add_node_default::<nodes::EnigoCursorNode>();
+    tree_support.add_value_default::<EnigoBlackboard>();
+    tree_support.add_value_default::<CursorPosition>();
+    tree_support.add_value_default::<EnigoTokens>();
+}
+
 /// Register enigo nodes to the ui support.
 #[cfg(feature = "betula_editor")]
 pub fn add_ui_support(ui_support: &mut betula_editor::UiSupport) {
     // ui_support.add_node_default::<nodes::EnigoInstanceNode>();
     ui_support
         .add_node_default_with_config::<nodes::EnigoInstanceNode, nodes::EnigoInstanceNodeConfig>();
-    ui_support.add_node_default_with_config::<nodes::EnigoNode, nodes::EnigoNodeConfig>();
+    ui_support.add_node_default_with_versioned_config::<nodes::EnigoNode, nodes::EnigoNodeConfig>();
     ui_support.add_node_default_with_config::<nodes::EnigoReadNode, nodes::EnigoReadNodeConfig>();
     ui_support
         .add_node_default_with_config::<nodes::CursorScannerNode, nodes::CursorScannerNodeConfig>();
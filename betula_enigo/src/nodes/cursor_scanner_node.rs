@@ -142,6 +142,12 @@ impl Node for CursorScannerNode {
         Ok(())
     }
 
+    fn halt(&mut self, ctx: &dyn HaltContext) -> Result<(), NodeError> {
+        // Stop the motion, the spiral starts from the center when executed again.
+        self.spiral = None;
+        ctx.halt_children()
+    }
+
     fn static_type() -> NodeType {
         "enigo_cursor_scanner".into()
     }
@@ -150,6 +156,10 @@ impl Node for CursorScannerNode {
         Self::static_type()
     }
 
+    fn always_execute(&self) -> bool {
+        true
+    }
+
     fn get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
         Ok(Some(Box::new(self.config.clone())))
     }
@@ -162,6 +172,10 @@ impl Node for CursorScannerNode {
     fn reset(&mut self) {
         self.spiral = None;
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..1
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -262,8 +276,5 @@ mod ui_support {
                 UiNodeCategory::Name("enigo_cursor_scanner".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..1
-        }
     }
 }
@@ -52,6 +52,14 @@ impl Node for EnigoCursorNode {
     fn node_type(&self) -> NodeType {
         Self::static_type()
     }
+
+    fn always_execute(&self) -> bool {
+        true
+    }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..0
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -84,8 +92,5 @@ mod ui_support {
                 UiNodeCategory::Name("enigo_cursor".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..0
-        }
     }
 }
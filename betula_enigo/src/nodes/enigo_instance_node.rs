@@ -79,6 +79,10 @@ impl Node for EnigoInstanceNode {
     fn reset(&mut self) {
         self.instance = None;
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..0
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -133,8 +137,5 @@ mod ui_support {
                 UiNodeCategory::Name("enigo".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..0
-        }
     }
 }
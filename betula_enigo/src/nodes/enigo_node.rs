@@ -10,6 +10,9 @@ pub struct EnigoNodeConfig {
     preset: Option<Vec<String>>,
 }
 impl IsNodeConfig for EnigoNodeConfig {}
+impl VersionedConfig for EnigoNodeConfig {
+    const VERSION: u32 = 0;
+}
 
 #[derive(Debug, Default)]
 pub struct EnigoNode {
@@ -107,6 +110,10 @@ impl Node for EnigoNode {
         Self::static_type()
     }
 
+    fn always_execute(&self) -> bool {
+        true
+    }
+
     fn get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
         Ok(Some(Box::new(self.config.clone())))
     }
@@ -123,6 +130,10 @@ impl Node for EnigoNode {
         self.directory = directory.map(|v| v.to_owned());
         let _ = self.load_presets();
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..1
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -616,8 +627,5 @@ mod ui_support {
                 UiNodeCategory::Name("enigo".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..1
-        }
     }
 }
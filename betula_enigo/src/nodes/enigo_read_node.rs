@@ -2,6 +2,8 @@ use betula_core::node_prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::{EnigoBlackboard, EnigoTokens};
+use enigo::agent::Token;
+use enigo::Direction;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EnigoReadNodeConfig {
@@ -28,12 +30,53 @@ pub struct EnigoReadNode {
     tokens: Input<EnigoTokens>,
     input: Input<EnigoBlackboard>,
     pub config: EnigoReadNodeConfig,
+
+    /// Keys and buttons pressed by the executed tokens, that are not yet released.
+    held: Vec<Token>,
 }
 
 impl EnigoReadNode {
     pub fn new() -> Self {
         EnigoReadNode::default()
     }
+
+    /// Update the held keys and buttons with the tokens that are executed.
+    fn track_held(&mut self, tokens: &[Token]) {
+        for token in tokens {
+            let pressed = match token {
+                Token::Key(key, direction) => {
+                    Some((Token::Key(*key, Direction::Press), *direction))
+                }
+                Token::Raw(code, direction) => {
+                    Some((Token::Raw(*code, Direction::Press), *direction))
+                }
+                Token::Button(button, direction) => {
+                    Some((Token::Button(*button, Direction::Press), *direction))
+                }
+                _ => None,
+            };
+            match pressed {
+                Some((pressed, Direction::Press)) if !self.held.contains(&pressed) => {
+                    self.held.push(pressed)
+                }
+                Some((pressed, Direction::Release)) => self.held.retain(|z| *z != pressed),
+                _ => {}
+            }
+        }
+    }
+
+    /// Tokens that release all held keys and buttons.
+    fn release_tokens(&mut self) -> Vec<Token> {
+        self.held
+            .drain(..)
+            .filter_map(|token| match token {
+                Token::Key(key, _) => Some(Token::Key(key, Direction::Release)),
+                Token::Raw(code, _) => Some(Token::Raw(code, Direction::Release)),
+                Token::Button(button, _) => Some(Token::Button(button, Direction::Release)),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 impl Node for EnigoReadNode {
@@ -46,6 +89,7 @@ impl Node for EnigoReadNode {
             }
         }
         if !self.config.dry_run {
+            self.track_held(&tokens.0);
             if self.config.execute_async {
                 interface.execute_async(&tokens.0)?;
             } else {
@@ -55,6 +99,21 @@ impl Node for EnigoReadNode {
         Ok(ExecutionStatus::Success)
     }
 
+    fn halt(&mut self, ctx: &dyn HaltContext) -> Result<(), NodeError> {
+        // Don't leave keys or buttons pressed when the branch we are in is stopped.
+        let release = self.release_tokens();
+        if !release.is_empty() {
+            // Same path as the presses, such that the releases are ordered after them.
+            let interface = self.input.get()?;
+            if self.config.execute_async {
+                interface.execute_async(&release)?;
+            } else {
+                interface.execute(&release)?;
+            }
+        }
+        ctx.halt_children()
+    }
+
     fn ports(&self) -> Result<Vec<Port>, NodeError> {
         Ok(vec![
             Port::input::<EnigoBlackboard>("enigo"),
@@ -78,6 +137,10 @@ impl Node for EnigoReadNode {
         Self::static_type()
     }
 
+    fn always_execute(&self) -> bool {
+        true
+    }
+
     fn get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
         Ok(Some(Box::new(self.config.clone())))
     }
@@ -85,6 +148,10 @@ impl Node for EnigoReadNode {
     fn set_config(&mut self, config: &dyn NodeConfig) -> Result<(), NodeError> {
         self.config.load_node_config(config)
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..0
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -135,8 +202,5 @@ mod ui_support {
                 UiNodeCategory::Name("enigo_read".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..0
-        }
     }
 }
@@ -325,6 +325,14 @@ impl std::fmt::Debug for HotkeyBlackboard {
     }
 }
 
+/// Register nodes to the tree support, for use without the editor.
+#[cfg(feature = "betula_common")]
+pub fn add_tree_support(tree_support: &mut betula_common::TreeSupport) {
+    tree_support.add_value_default::<HotkeyBlackboard>();
+    tree_support.add_node_default::<nodes::HotkeyInstanceNode>();
+    tree_support.add_node_default_with_config::<nodes::HotkeyNode, nodes::HotkeyNodeConfig>();
+}
+
 /// Register hotkey nodes to the ui support.
 #[cfg(feature = "betula_editor")]
 pub fn add_ui_support(ui_support: &mut betula_editor::UiSupport) {
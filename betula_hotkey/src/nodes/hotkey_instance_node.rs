@@ -48,6 +48,10 @@ impl Node for HotkeyInstanceNode {
     fn reset(&mut self) {
         self.instance = None;
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..0
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -66,8 +70,5 @@ mod ui_support {
                 UiNodeCategory::Name("hotkey".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..0
-        }
     }
 }
@@ -119,6 +119,10 @@ impl Node for HotkeyNode {
         Self::static_type()
     }
 
+    fn always_execute(&self) -> bool {
+        true
+    }
+
     fn get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
         Ok(Some(Box::new(self.config)))
     }
@@ -131,6 +135,10 @@ impl Node for HotkeyNode {
         }
         Ok(())
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..0
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -233,8 +241,5 @@ mod ui_support {
                 UiNodeCategory::Name("hotkey".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..0
-        }
     }
 }
@@ -96,12 +96,46 @@ mod enigo_support {
 #[cfg(feature = "betula_enigo")]
 pub use enigo_support::ImageCursor;
 
+/// Register the conversions from [`ImageCursor`], trees loaded without the editor need these too.
+#[cfg(feature = "betula_enigo")]
+pub fn add_conversions(tree_support: &mut betula_common::TreeSupport) {
+    tree_support.add_conversion::<ImageCursor, Image>(|v| Ok(v.image.clone()));
+    tree_support.add_conversion::<ImageCursor, betula_enigo::CursorPosition>(|v| Ok(v.cursor));
+}
+
+/// Register nodes to the tree support, for use without the editor.
+pub fn add_tree_support(tree_support: &mut betula_common::TreeSupport) {
+    tree_support.add_value_default::<Image>();
+    tree_support.add_node_default_with_versioned_config::<
+        nodes::ImageCaptureNode,
+        nodes::ImageCaptureNodeConfig,
+    >();
+    tree_support
+        .add_node_default_with_config::<nodes::ImageMatchNode, nodes::ImageMatchNodeConfig>();
+
+    #[cfg(feature = "betula_enigo")]
+    {
+        use betula_common::callback::CallbacksBlackboard;
+        tree_support.add_value_default::<ImageCursor>();
+        tree_support.add_value_default::<CallbacksBlackboard<ImageCursor>>();
+        add_conversions(tree_support);
+        tree_support.add_node_default_with_versioned_config::<
+            nodes::ImageCaptureCursorNode,
+            nodes::ImageCaptureNodeConfig,
+        >();
+        tree_support
+            .add_node_default_with_config::<nodes::ImageWriteCursorNode, nodes::ImageWriteCursorNodeConfig>();
+    }
+}
+
 /// Register nodes to the ui support.
 #[cfg(feature = "betula_editor")]
 pub fn add_ui_support(ui_support: &mut betula_editor::UiSupport) {
     ui_support.add_value_default_named::<Image>("Image");
-    ui_support
-        .add_node_default_with_config::<nodes::ImageCaptureNode, nodes::ImageCaptureNodeConfig>();
+    ui_support.add_node_default_with_versioned_config::<
+        nodes::ImageCaptureNode,
+        nodes::ImageCaptureNodeConfig,
+    >();
     ui_support.add_node_default_with_config::<nodes::ImageMatchNode, nodes::ImageMatchNodeConfig>();
 
     #[cfg(feature = "betula_enigo")]
@@ -109,8 +143,11 @@ pub fn add_ui_support(ui_support: &mut betula_editor::UiSupport) {
         use betula_common::callback::CallbacksBlackboard;
         ui_support.add_value_default_named::<ImageCursor>("ImageCursor");
         ui_support.add_value_default_named::<CallbacksBlackboard<ImageCursor>>("ImageCursorCB");
-        ui_support
-            .add_node_default_with_config::<nodes::ImageCaptureCursorNode, nodes::ImageCaptureNodeConfig>();
+        add_conversions(ui_support.tree_support_mut());
+        ui_support.add_node_default_with_versioned_config::<
+            nodes::ImageCaptureCursorNode,
+            nodes::ImageCaptureNodeConfig,
+        >();
         ui_support
             .add_node_default_with_config::<nodes::ImageWriteCursorNode, nodes::ImageWriteCursorNodeConfig>();
     }
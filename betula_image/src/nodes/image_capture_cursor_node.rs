@@ -199,6 +199,10 @@ impl Node for ImageCaptureCursorNode {
         Self::static_type()
     }
 
+    fn always_execute(&self) -> bool {
+        true
+    }
+
     fn get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
         self.node.get_config()
     }
@@ -211,6 +215,10 @@ impl Node for ImageCaptureCursorNode {
         self.setup_done = false;
         self.node.reset();
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..0
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -238,8 +246,5 @@ mod ui_support {
                 UiNodeCategory::Name("image_capture_cursor".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..0
-        }
     }
 }
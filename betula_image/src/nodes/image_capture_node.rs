@@ -9,6 +9,9 @@ pub struct ImageCaptureNodeConfig {
     pub capture: CaptureConfig,
 }
 impl IsNodeConfig for ImageCaptureNodeConfig {}
+impl VersionedConfig for ImageCaptureNodeConfig {
+    const VERSION: u32 = 0;
+}
 
 #[derive(Default)]
 pub struct ImageCaptureNode {
@@ -31,18 +34,17 @@ impl ImageCaptureNode {
 }
 
 impl Node for ImageCaptureNode {
-    fn execute(&mut self, _ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+    fn execute(&mut self, ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
         let c = self
             .capture
             .get_or_insert_with(|| ThreadedCapturer::new(self.config.capture.clone()));
         let info = c.latest();
         match info.result {
             Ok(img) => {
-                use std::time::UNIX_EPOCH;
                 self.output.set(Image::new(img))?;
-                let _ = self
-                    .output_time
-                    .set(info.time.duration_since(UNIX_EPOCH)?.as_secs_f64());
+                // The capture happens on another thread, express its time in the tree's clock.
+                let age = info.time.elapsed().unwrap_or_default().as_secs_f64();
+                let _ = self.output_time.set(ctx.clock().now()? - age);
                 let _ = self.output_duration.set(info.duration.as_secs_f64());
                 Ok(ExecutionStatus::Success)
             }
@@ -75,6 +77,10 @@ impl Node for ImageCaptureNode {
         Self::static_type()
     }
 
+    fn always_execute(&self) -> bool {
+        true
+    }
+
     fn get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
         Ok(Some(Box::new(self.config.clone())))
     }
@@ -90,6 +96,10 @@ impl Node for ImageCaptureNode {
     fn reset(&mut self) {
         self.capture = None;
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..0
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -222,8 +232,5 @@ mod ui_support {
                 UiNodeCategory::Name("image_capture".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..0
-        }
     }
 }
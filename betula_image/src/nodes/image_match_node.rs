@@ -28,6 +28,9 @@ pub struct ImageMatchNode {
 
     /// The available patterns for selection.
     pattern_library: Vec<PatternEntry>,
+
+    /// Generation of the last image that was matched, and whether it matched.
+    last_match: Option<(u64, bool)>,
 }
 impl std::fmt::Debug for ImageMatchNode {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
@@ -65,9 +68,22 @@ impl Node for ImageMatchNode {
             }
         }
         if let Some(pattern) = &self.pattern {
-            let image = self.input.get()?;
+            // Only match again if the image changed since the last match.
+            let matches = match self.last_match {
+                Some((last, matches)) if !self.input.changed_since(last).unwrap_or(true) => matches,
+                _ => {
+                    let image = self.input.get()?;
+                    let matches = pattern.matches_exact(&image);
+                    self.last_match = self
+                        .input
+                        .generation()
+                        .ok()
+                        .map(|g| (g.generation, matches));
+                    matches
+                }
+            };
             // let start = std::time::Instant::now();
-            if pattern.matches_exact(&image) {
+            if matches {
                 // println!("took: {:?}", std::time::Instant::now() - start);
                 if ctx.children() == 0 {
                     return Ok(ExecutionStatus::Success);
@@ -96,6 +112,8 @@ impl Node for ImageMatchNode {
         interface: &mut dyn BlackboardInputInterface,
     ) -> Result<(), NodeError> {
         self.input = interface.input::<Image>("image")?;
+        // Generations are per blackboard, the cached result may not apply to the new input.
+        self.last_match = None;
         Ok(())
     }
 
@@ -116,6 +134,7 @@ impl Node for ImageMatchNode {
         let r = self.config.load_node_config(config);
         if self.config.use_match != before {
             self.pattern = None;
+            self.last_match = None;
         }
         r
     }
@@ -127,6 +146,11 @@ impl Node for ImageMatchNode {
 
     fn reset(&mut self) {
         self.pattern = None;
+        self.last_match = None;
+    }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..1
     }
 }
 
@@ -238,8 +262,5 @@ mod ui_support {
                 UiNodeCategory::Name("image_match".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..1
-        }
     }
 }
@@ -136,6 +136,10 @@ impl Node for ImageWriteCursorNode {
         Self::static_type()
     }
 
+    fn always_execute(&self) -> bool {
+        true
+    }
+
     fn get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
         Ok(Some(Box::new(self.config.clone())))
     }
@@ -151,6 +155,10 @@ impl Node for ImageWriteCursorNode {
         self.directory = directory.map(|v| v.to_owned());
         self.ticket = None;
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..0
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -208,8 +216,5 @@ mod ui_support {
                 UiNodeCategory::Name("image_cursor_writer".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..0
-        }
     }
 }
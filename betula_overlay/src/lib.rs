@@ -193,6 +193,14 @@ pub fn get_overlays() -> Vec<OverlayHandle> {
     strong
 }
 
+/// Register nodes to the tree support, for use without the editor.
+pub fn add_tree_support(tree_support: &mut betula_common::TreeSupport) {
+    tree_support.add_value_default::<OverlayBlackboard>();
+    tree_support.add_node_default_with_config::<nodes::OverlayInstanceNode, nodes::OverlayInstanceNodeConfig>();
+    tree_support
+        .add_node_default_with_config::<nodes::OverlayTextNode, nodes::OverlayTextNodeConfig>();
+}
+
 /// Register nodes to the ui support.
 #[cfg(feature = "betula_editor")]
 pub fn add_ui_support(ui_support: &mut betula_editor::UiSupport) {
@@ -109,6 +109,10 @@ impl Node for OverlayInstanceNode {
     }
 
     fn reset(&mut self) {}
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..0
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -207,8 +211,5 @@ mod ui_support {
                 UiNodeCategory::Name("overlay".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..0
-        }
     }
 }
@@ -156,6 +156,10 @@ impl Node for OverlayTextNode {
         }
         self.needs_update = true;
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..0
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -228,8 +232,5 @@ mod ui_support {
                 UiNodeCategory::Name("overlay_text".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..0
-        }
     }
 }
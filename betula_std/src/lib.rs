@@ -1,5 +1,37 @@
 pub mod nodes;
 
+/// Register standard nodes to the tree support, for use without the editor.
+#[cfg(feature = "betula_common")]
+pub fn add_tree_support(tree_support: &mut betula_common::TreeSupport) {
+    tree_support.add_node_default_with_config::<nodes::SequenceNode, nodes::SequenceNodeConfig>();
+    tree_support.add_node_default_with_config::<nodes::SelectorNode, nodes::SelectorNodeConfig>();
+    tree_support.add_node_default::<nodes::FailureNode>();
+    tree_support.add_node_default::<nodes::SuccessNode>();
+    tree_support.add_node_default::<nodes::RunningNode>();
+    tree_support.add_node_default::<nodes::BlockResetNode>();
+    tree_support.add_node_default::<nodes::NegateNode>();
+    tree_support.add_node_default::<nodes::ForceSuccessNode>();
+
+    tree_support.add_node_default_with_config::<nodes::DelayNode, nodes::DelayNodeConfig>();
+    tree_support
+        .add_node_default_with_config::<nodes::IfTimeExceedsNode, nodes::IfTimeExceedsNodeConfig>();
+    tree_support.add_node_default_with_config::<nodes::ParallelNode, nodes::ParallelNodeConfig>();
+    tree_support.add_node_default_with_config::<nodes::RetryNode, nodes::RetryNodeConfig>();
+    tree_support.add_node_default::<nodes::TimeNode>();
+    tree_support.add_node_default::<nodes::StatusWriteNode>();
+    tree_support.add_node_default::<nodes::StatusReadNode>();
+    tree_support
+        .add_node_default_with_config::<nodes::IfThenElseNode, nodes::IfThenElseNodeConfig>();
+    tree_support.add_node_default_with_config::<nodes::TimeSliceNode, nodes::TimeSliceNodeConfig>();
+    tree_support.add_value_default::<f64>();
+    tree_support.add_value_default::<i64>();
+    tree_support.add_value_default::<String>();
+    tree_support.add_value_default::<betula_core::ExecutionStatus>();
+    tree_support.add_node_default_with_config::<nodes::IfExecutionStatusNode, nodes::IfExecutionStatusNodeConfig>();
+    tree_support
+        .add_node_default_with_config::<nodes::StringWriteNode, nodes::StringWriteNodeConfig>();
+}
+
 /// Register standard nodes to the ui support.
 #[cfg(feature = "betula_editor")]
 pub fn add_ui_support(ui_support: &mut betula_editor::UiSupport) {
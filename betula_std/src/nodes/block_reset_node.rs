@@ -33,6 +33,14 @@ impl Node for BlockResetNode {
         // Recursion stops here.
         Ok(())
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        1..1
+    }
+
+    fn blocks_reset(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -52,8 +60,5 @@ pub mod ui_support {
                 UiNodeCategory::Name("block_reset".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            1..1
-        }
     }
 }
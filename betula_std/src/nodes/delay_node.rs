@@ -1,3 +1,4 @@
+use super::time_input::TimeInput;
 use betula_core::node_prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -15,10 +16,11 @@ impl IsNodeConfig for DelayNodeConfig {}
 /// its one child node and returns its status. If there is no child node
 /// to be executed it returns [`ExecutionStatus::Running`].
 ///
-/// One input port `time`, of type `f64`, which usually is time in seconds.
+/// One input port `time`, of type `f64`, which usually is time in seconds. If this port is not
+/// connected the clock of the tree is used.
 #[derive(Debug, Default)]
 pub struct DelayNode {
-    time: Input<f64>,
+    time: TimeInput,
     last_time: f64,
     pub config: DelayNodeConfig,
 }
@@ -34,7 +36,7 @@ impl DelayNode {
 
 impl Node for DelayNode {
     fn execute(&mut self, ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
-        let time = self.time.get()?;
+        let time = self.time.get(ctx)?;
         if time < (self.last_time + self.config.interval) {
             return Ok(ExecutionStatus::Running);
         }
@@ -48,15 +50,14 @@ impl Node for DelayNode {
     }
 
     fn ports(&self) -> Result<Vec<Port>, NodeError> {
-        Ok(vec![Port::input::<f64>("time")])
+        Ok(vec![TimeInput::port()])
     }
 
     fn setup_inputs(
         &mut self,
         interface: &mut dyn BlackboardInputInterface,
     ) -> Result<(), NodeError> {
-        self.time = interface.input::<f64>("time")?;
-        Ok(())
+        self.time.setup(interface)
     }
 
     fn get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
@@ -78,6 +79,14 @@ impl Node for DelayNode {
     fn node_type(&self) -> NodeType {
         Self::static_type()
     }
+
+    fn always_execute(&self) -> bool {
+        self.time.uses_clock()
+    }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..1
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -113,9 +122,6 @@ pub mod ui_support {
 
             ui_response
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..1
-        }
 
         fn ui_category() -> Vec<UiNodeCategory> {
             vec![
@@ -163,4 +169,23 @@ mod tests {
         assert!(tree.execute(root)? == ExecutionStatus::Running);
         Ok(())
     }
+    #[test]
+    fn test_clock() -> Result<(), NodeError> {
+        use betula_core::{basic::LazyTree, clock::ManualClock, NodeId};
+        use uuid::Uuid;
+        let clock = ManualClock::new(10.0);
+        let mut tree = LazyTree::new();
+        tree.set_clock(Box::new(clock.clone()));
+
+        // Time port is not connected, so the clock of the tree is used.
+        let root = tree.add_node_boxed(NodeId(Uuid::new_v4()), Box::new(DelayNode::new(5.0)))?;
+        assert_eq!(tree.execute(root)?, ExecutionStatus::Success);
+        assert_eq!(tree.execute(root)?, ExecutionStatus::Running);
+        clock.advance(4.0);
+        assert_eq!(tree.execute(root)?, ExecutionStatus::Running);
+        clock.advance(1.0);
+        assert_eq!(tree.execute(root)?, ExecutionStatus::Success);
+        assert_eq!(tree.execute(root)?, ExecutionStatus::Running);
+        Ok(())
+    }
 }
@@ -27,6 +27,10 @@ impl Node for FailureNode {
     fn node_type(&self) -> NodeType {
         Self::static_type()
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..1
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -46,8 +50,5 @@ pub mod ui_support {
                 UiNodeCategory::Name("failure".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..1
-        }
     }
 }
@@ -30,6 +30,10 @@ impl Node for ForceSuccessNode {
     fn node_type(&self) -> NodeType {
         Self::static_type()
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        1..1
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -48,8 +52,5 @@ pub mod ui_support {
                 UiNodeCategory::Name("force_success".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            1..1
-        }
     }
 }
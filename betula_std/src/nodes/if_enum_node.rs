@@ -134,6 +134,10 @@ impl<T: IfEnumNodeEnum> Node for IfEnumNode<T> {
     fn node_type(&self) -> NodeType {
         Self::static_type()
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        1..1
+    }
 }
 #[cfg(feature = "betula_editor")]
 pub mod ui_support {
@@ -197,9 +201,6 @@ pub mod ui_support {
 
             ui_response
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            1..1
-        }
 
         fn ui_category() -> Vec<UiNodeCategory> {
             T::enum_node_category()
@@ -89,6 +89,10 @@ impl Node for IfThenElseNode {
     fn reset(&mut self) {
         self.child = None;
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        2..3
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -127,10 +131,6 @@ pub mod ui_support {
             }
         }
 
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            2..3
-        }
-
         fn ui_category() -> Vec<UiNodeCategory> {
             vec![
                 UiNodeCategory::Folder("control".to_owned()),
@@ -58,6 +58,10 @@ impl Node for IfTimeExceedsNode {
     fn node_type(&self) -> NodeType {
         Self::static_type()
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..1
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -92,9 +96,6 @@ pub mod ui_support {
 
             ui_response
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..1
-        }
 
         fn ui_category() -> Vec<UiNodeCategory> {
             vec![
@@ -31,6 +31,8 @@ mod block_reset_node;
 pub use block_reset_node::BlockResetNode;
 
 // Conditionals
+mod time_input;
+
 mod delay_node;
 pub use delay_node::{DelayNode, DelayNodeConfig};
 
@@ -1,3 +1,4 @@
+use super::time_input::TimeInput;
 use betula_core::node_prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -15,10 +16,11 @@ impl IsNodeConfig for RetryNodeConfig {}
 /// retry also returns success and the interval is reset. Once the time limit is reached, the
 /// interval is reset and a final [`ExecutionStatus::Failure`] is returned.
 ///
-/// One input port `time`, of type `f64`, which usually is time in seconds.
+/// One input port `time`, of type `f64`, which usually is time in seconds. If this port is not
+/// connected the clock of the tree is used.
 #[derive(Debug, Default)]
 pub struct RetryNode {
-    time: Input<f64>,
+    time: TimeInput,
     start_time: Option<f64>,
     pub config: RetryNodeConfig,
 }
@@ -29,7 +31,7 @@ impl Node for RetryNode {
             return Err("RetryNode must have exactly one child node".into());
         }
         // Check if we have actually failed.
-        let time = self.time.get()?;
+        let time = self.time.get(ctx)?;
         if let Some(last_start_time) = self.start_time.as_ref() {
             if last_start_time + self.config.time_limit < time {
                 self.start_time = None;
@@ -50,15 +52,14 @@ impl Node for RetryNode {
     }
 
     fn ports(&self) -> Result<Vec<Port>, NodeError> {
-        Ok(vec![Port::input::<f64>("time")])
+        Ok(vec![TimeInput::port()])
     }
 
     fn setup_inputs(
         &mut self,
         interface: &mut dyn BlackboardInputInterface,
     ) -> Result<(), NodeError> {
-        self.time = interface.input::<f64>("time")?;
-        Ok(())
+        self.time.setup(interface)
     }
 
     fn get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
@@ -80,6 +81,14 @@ impl Node for RetryNode {
     fn node_type(&self) -> NodeType {
         Self::static_type()
     }
+
+    fn always_execute(&self) -> bool {
+        self.time.uses_clock()
+    }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        1..1
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -115,9 +124,6 @@ pub mod ui_support {
 
             ui_response
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            1..1
-        }
 
         fn ui_category() -> Vec<UiNodeCategory> {
             vec![
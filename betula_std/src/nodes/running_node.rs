@@ -28,6 +28,10 @@ impl Node for RunningNode {
     fn node_type(&self) -> NodeType {
         Self::static_type()
     }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..1
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -47,8 +51,5 @@ pub mod ui_support {
                 UiNodeCategory::Name("running".to_owned()),
             ]
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..1
-        }
     }
 }
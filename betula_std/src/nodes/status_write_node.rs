@@ -3,8 +3,13 @@ use betula_core::node_prelude::*;
 /// Node that writes the child's execution status to a blackboard.
 ///
 /// One output port `status`, of type [`ExecutionStatus`], which is the status of the child node.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, betula_derive::Node)]
+#[node(
+    type = "std_status_write",
+    ui(title = "status 🖊", category = "provider/status")
+)]
 pub struct StatusWriteNode {
+    #[output("status", default = ExecutionStatus::Running)]
     status_output: Output<ExecutionStatus>,
 }
 
@@ -14,6 +19,7 @@ impl StatusWriteNode {
     }
 }
 
+#[betula_derive::node_impl]
 impl Node for StatusWriteNode {
     fn execute(&mut self, ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
         if ctx.children() != 1 {
@@ -23,45 +29,8 @@ impl Node for StatusWriteNode {
         self.status_output.set(res)?;
         Ok(res)
     }
-    fn ports(&self) -> Result<Vec<Port>, NodeError> {
-        Ok(vec![Port::output::<ExecutionStatus>("status")])
-    }
-    fn setup_outputs(
-        &mut self,
-        interface: &mut dyn BlackboardOutputInterface,
-    ) -> Result<(), NodeError> {
-        self.status_output =
-            interface.output::<ExecutionStatus>("status", ExecutionStatus::Running)?;
-        Ok(())
-    }
-
-    fn static_type() -> NodeType {
-        "std_status_write".into()
-    }
-
-    fn node_type(&self) -> NodeType {
-        Self::static_type()
-    }
-}
-
-#[cfg(feature = "betula_editor")]
-pub mod ui_support {
-    use super::*;
-    use betula_editor::{UiNode, UiNodeCategory};
-
-    impl UiNode for StatusWriteNode {
-        fn ui_title(&self) -> String {
-            "status 🖊".to_owned()
-        }
 
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            1..1
-        }
-        fn ui_category() -> Vec<UiNodeCategory> {
-            vec![
-                UiNodeCategory::Folder("provider".to_owned()),
-                UiNodeCategory::Name("status".to_owned()),
-            ]
-        }
+    fn child_range(&self) -> std::ops::Range<usize> {
+        1..1
     }
 }
@@ -12,12 +12,16 @@ impl IsNodeConfig for StringWriteNodeConfig {}
 /// Always succeeds and writes the fixed string to the blackboard.
 ///
 /// One output port `text`, of type `string`.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, betula_derive::Node)]
+#[node(type = "std_string_write")]
 pub struct StringWriteNode {
+    #[output("text")]
     text: Output<String>,
+    #[config]
     pub config: StringWriteNodeConfig,
 }
 
+#[betula_derive::node_impl]
 impl Node for StringWriteNode {
     fn execute(&mut self, ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
         let _ = ctx;
@@ -25,34 +29,10 @@ impl Node for StringWriteNode {
         Ok(ExecutionStatus::Success)
     }
 
-    fn ports(&self) -> Result<Vec<Port>, NodeError> {
-        Ok(vec![Port::output::<String>("text")])
-    }
-
-    fn setup_outputs(
-        &mut self,
-        interface: &mut dyn BlackboardOutputInterface,
-    ) -> Result<(), NodeError> {
-        self.text = interface.output::<String>("text", "".to_owned())?;
-        Ok(())
-    }
-
-    fn get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
-        Ok(Some(Box::new(self.config.clone())))
-    }
-
-    fn set_config(&mut self, config: &dyn NodeConfig) -> Result<(), NodeError> {
-        self.config.load_node_config(config)
-    }
-
     fn reset(&mut self) {}
 
-    fn static_type() -> NodeType {
-        "std_string_write".into()
-    }
-
-    fn node_type(&self) -> NodeType {
-        Self::static_type()
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..0
     }
 }
 
@@ -89,9 +69,6 @@ pub mod ui_support {
 
             ui_response
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..0
-        }
 
         fn ui_category() -> Vec<UiNodeCategory> {
             vec![
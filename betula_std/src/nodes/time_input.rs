@@ -0,0 +1,48 @@
+use betula_core::error::{ErrorKind, ExecutionError};
+use betula_core::node_prelude::*;
+
+/// Time input that falls back to the tree's clock if the port is not connected.
+///
+/// The `time` input takes precedence, such that a [`super::TimeNode`] or any other source of time
+/// can still be used. Nodes that read the clock must always be executed, see
+/// [`TimeInput::uses_clock`].
+#[derive(Debug, Default)]
+pub(crate) struct TimeInput {
+    input: Input<f64>,
+    uses_clock: bool,
+}
+
+impl TimeInput {
+    /// Obtain the time from the input, or from the clock of the context.
+    pub fn get(&mut self, ctx: &dyn RunContext) -> Result<f64, NodeError> {
+        match self.input.get() {
+            Ok(time) => {
+                self.uses_clock = false;
+                Ok(time)
+            }
+            Err(e) => match ExecutionError::find(&e).map(|e| e.kind()) {
+                Some(ErrorKind::MissingPort) => {
+                    self.uses_clock = true;
+                    ctx.clock().now()
+                }
+                _ => Err(e),
+            },
+        }
+    }
+
+    /// Whether the last time obtained was read from the clock.
+    pub fn uses_clock(&self) -> bool {
+        self.uses_clock
+    }
+
+    /// The port for this input.
+    pub fn port() -> Port {
+        Port::input::<f64>("time")
+    }
+
+    /// Setup the input, should be called from [`Node::setup_inputs`].
+    pub fn setup(&mut self, interface: &mut dyn BlackboardInputInterface) -> Result<(), NodeError> {
+        self.input = interface.input::<f64>("time")?;
+        Ok(())
+    }
+}
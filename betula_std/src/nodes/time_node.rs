@@ -3,9 +3,11 @@ use betula_core::node_prelude::*;
 /// Node that writes the time to a blackboard.
 ///
 /// One output port `time`, of type `f64`, which is time in seconds since
-/// the unix epoch.
-#[derive(Debug, Default)]
+/// the unix epoch, as provided by the clock of the tree.
+#[derive(Debug, Default, betula_derive::Node)]
+#[node(type = "std_time", ui(title = "time 🕓", category = "provider/time"))]
 pub struct TimeNode {
+    #[output("time")]
     time_output: Output<f64>,
 }
 
@@ -15,53 +17,19 @@ impl TimeNode {
     }
 }
 
+#[betula_derive::node_impl]
 impl Node for TimeNode {
-    fn execute(&mut self, _ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        let start = SystemTime::now();
-        let since_the_epoch = start.duration_since(UNIX_EPOCH)?;
-        self.time_output.set(since_the_epoch.as_secs_f64())?;
+    fn execute(&mut self, ctx: &dyn RunContext) -> Result<ExecutionStatus, NodeError> {
+        self.time_output.set(ctx.clock().now()?)?;
         Ok(ExecutionStatus::Success)
     }
-    fn ports(&self) -> Result<Vec<Port>, NodeError> {
-        Ok(vec![Port::output::<f64>("time")])
-    }
-    fn setup_outputs(
-        &mut self,
-        interface: &mut dyn BlackboardOutputInterface,
-    ) -> Result<(), NodeError> {
-        self.time_output = interface.output::<f64>("time", 0.0)?;
-        Ok(())
-    }
-
-    fn static_type() -> NodeType {
-        "std_time".into()
-    }
 
-    fn node_type(&self) -> NodeType {
-        Self::static_type()
+    fn always_execute(&self) -> bool {
+        true
     }
-}
-
-#[cfg(feature = "betula_editor")]
-pub mod ui_support {
-    use super::*;
-    use betula_editor::{UiNode, UiNodeCategory};
-
-    impl UiNode for TimeNode {
-        fn ui_title(&self) -> String {
-            "time 🕓".to_owned()
-        }
 
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..0
-        }
-        fn ui_category() -> Vec<UiNodeCategory> {
-            vec![
-                UiNodeCategory::Folder("provider".to_owned()),
-                UiNodeCategory::Name("time".to_owned()),
-            ]
-        }
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..0
     }
 }
 
@@ -1,3 +1,4 @@
+use super::time_input::TimeInput;
 use betula_core::node_prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -33,10 +34,11 @@ impl Default for TimeSliceNodeConfig {
 /// The node returns running whenever outside of the time window, inside the time window it calls
 /// the one child node and returns its state.
 ///
-/// One input port `time`, of type `f64`, which usually is time in seconds.
+/// One input port `time`, of type `f64`, which usually is time in seconds. If this port is not
+/// connected the clock of the tree is used.
 #[derive(Debug, Default)]
 pub struct TimeSliceNode {
-    time: Input<f64>,
+    time: TimeInput,
     pub config: TimeSliceNodeConfig,
 }
 
@@ -46,7 +48,7 @@ impl Node for TimeSliceNode {
             return Err("TimeSliceNode must have exactly one child node".into());
         }
 
-        let time = self.time.get()?;
+        let time = self.time.get(ctx)?;
 
         let time_in_period = time.rem_euclid(self.config.period);
 
@@ -61,15 +63,14 @@ impl Node for TimeSliceNode {
     }
 
     fn ports(&self) -> Result<Vec<Port>, NodeError> {
-        Ok(vec![Port::input::<f64>("time")])
+        Ok(vec![TimeInput::port()])
     }
 
     fn setup_inputs(
         &mut self,
         interface: &mut dyn BlackboardInputInterface,
     ) -> Result<(), NodeError> {
-        self.time = interface.input::<f64>("time")?;
-        Ok(())
+        self.time.setup(interface)
     }
 
     fn get_config(&self) -> Result<Option<Box<dyn NodeConfig>>, NodeError> {
@@ -87,6 +88,14 @@ impl Node for TimeSliceNode {
     fn node_type(&self) -> NodeType {
         Self::static_type()
     }
+
+    fn always_execute(&self) -> bool {
+        self.time.uses_clock()
+    }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..1
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -150,9 +159,6 @@ pub mod ui_support {
                 UiConfigResponse::UnChanged
             }
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..1
-        }
 
         fn ui_category() -> Vec<UiNodeCategory> {
             vec![
@@ -61,6 +61,13 @@ pub fn main_test() {
     }
 }
 
+/// Register nodes to the tree support, for use without the editor.
+#[cfg(feature = "betula_common")]
+pub fn add_tree_support(tree_support: &mut betula_common::TreeSupport) {
+    tree_support
+        .add_node_default_with_config::<nodes::WindowFocusNode, nodes::WindowFocusNodeConfig>();
+}
+
 /// Register nodes to the ui support.
 #[cfg(feature = "betula_editor")]
 pub fn add_ui_support(ui_support: &mut betula_editor::UiSupport) {
@@ -63,6 +63,14 @@ impl Node for WindowFocusNode {
     fn node_type(&self) -> NodeType {
         Self::static_type()
     }
+
+    fn always_execute(&self) -> bool {
+        true
+    }
+
+    fn child_range(&self) -> std::ops::Range<usize> {
+        0..1
+    }
 }
 
 #[cfg(feature = "betula_editor")]
@@ -145,9 +153,6 @@ pub mod ui_support {
 
             ui_response
         }
-        fn ui_child_range(&self) -> std::ops::Range<usize> {
-            0..1
-        }
 
         fn ui_category() -> Vec<UiNodeCategory> {
             vec![